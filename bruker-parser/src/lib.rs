@@ -259,6 +259,12 @@ fn read_block_definitions(buf: &[u8; HEADER_SIZE_BYTES]) -> Vec<BlockDefinition>
 pub struct OpusAbsorbanceData {
     pub wavenumber: Vec<f64>,
     pub absorbance: Vec<f64>,
+    /// Unit of the x-axis as given by the DXU parameter (e.g. "WN" for
+    /// wavenumbers or "MI" for micrometres), if present.
+    pub x_unit: Option<String>,
+    /// Set if the file contains no absorbance data and single channel data
+    /// was read instead.
+    pub single_channel: bool,
 }
 
 impl OpusAbsorbanceData {
@@ -285,7 +291,8 @@ impl OpusAbsorbanceData {
                 other => acc,
             });
         // If we did not find absorbance data, try to retrieve single channel data.
-        if maybe_data_definition.is_none() || maybe_params_definition.is_none() {
+        let single_channel = maybe_data_definition.is_none() || maybe_params_definition.is_none();
+        if single_channel {
             (maybe_data_definition, maybe_params_definition) =
                 blks.iter().fold((None, None), |mut acc, b| match b.kind {
                     BlockKind::ScSm => {
@@ -321,6 +328,11 @@ impl OpusAbsorbanceData {
             return Err("no data on x-range found".to_string());
         };
 
+        let x_unit = match params.get("DXU") {
+            Some(O::Text(unit)) => Some(unit.trim().to_owned()),
+            _ => None,
+        };
+
         let step = (xmax - xmin) / (data_definition.size as f64);
 
         let mut wavenumber = Vec::with_capacity(data_definition.size);
@@ -341,6 +353,8 @@ impl OpusAbsorbanceData {
             // Reverse order to make x-axis ascending.
             wavenumber: wavenumber.into_iter().rev().collect(),
             absorbance: data.into_iter().rev().collect(),
+            x_unit,
+            single_channel,
         })
     }
 
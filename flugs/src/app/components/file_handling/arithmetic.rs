@@ -0,0 +1,390 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend_state::{PlotData, XUnit, YUnit};
+
+use super::{
+    File, FileID, logic,
+    resample::{Interpolation, resample},
+};
+
+/// Placeholder stored as data of a derived file until its recipe was
+/// evaluated for the first time.
+pub const NOT_EVALUATED_MSG: &str = "Derived data not evaluated yet";
+
+/// Operations to combine the data of two or more files into a new one.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Operation {
+    /// A − k·B
+    Difference { factor: f64 },
+    /// A / B
+    Ratio,
+    /// Mean of all sources.
+    Mean,
+    /// Sum of all sources.
+    Sum,
+}
+
+/// Describes how a derived file is created from other files in the
+/// registry. Derived files are stored in sessions as recipes, their data is
+/// re-created when a session is loaded.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Recipe {
+    pub operation: Operation,
+    pub sources: Vec<FileID>,
+    #[serde(default)]
+    pub interpolation: Interpolation,
+}
+
+/// Buffers the user input for creating a new derived file.
+#[derive(Debug)]
+pub(super) struct ArithmeticForm {
+    pub(super) operation: Operation,
+    pub(super) minuend: Option<FileID>,
+    pub(super) subtrahend: Option<FileID>,
+    pub(super) selection: Vec<FileID>,
+    pub(super) interpolation: Interpolation,
+}
+
+impl Operation {
+    pub const ALL: [Operation; 4] = [
+        Operation::Difference { factor: 1.0 },
+        Operation::Ratio,
+        Operation::Mean,
+        Operation::Sum,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Difference { .. } => "A − k·B",
+            Operation::Ratio => "A / B",
+            Operation::Mean => "Mean",
+            Operation::Sum => "Sum",
+        }
+    }
+
+    /// Whether the operation takes exactly two operands (A and B).
+    pub fn is_binary(&self) -> bool {
+        matches!(self, Operation::Difference { .. } | Operation::Ratio)
+    }
+
+    fn check_num_sources(&self, n: usize) -> Result<(), String> {
+        if self.is_binary() && n != 2 {
+            return Err(format!("{} needs exactly two files, got {n}", self.name()));
+        }
+        if n < 2 {
+            return Err(format!("{} needs at least two files, got {n}", self.name()));
+        }
+        Ok(())
+    }
+
+    /// Combine the y-values of all sources at a single grid point.
+    fn apply(&self, values: &[f64]) -> f64 {
+        match self {
+            Operation::Difference { factor } => values[0] - factor * values[1],
+            Operation::Ratio => values[0] / values[1],
+            Operation::Mean => values.iter().sum::<f64>() / values.len() as f64,
+            Operation::Sum => values.iter().sum(),
+        }
+    }
+}
+
+impl Recipe {
+    /// Evaluate the recipe on the current data of its sources.
+    ///
+    /// The data of all sources is interpolated onto the x-values of the
+    /// first source, restricted to the x-range covered by all sources, using
+    /// the recipe's interpolation method.
+    ///
+    /// Returns `Ok(None)` if the data of a source is still being loaded.
+    pub fn evaluate(&self, registry: &HashMap<FileID, File>) -> Result<Option<PlotData>, String> {
+        let Some((grid, resampled)) = self.common_grid(registry)? else {
+            return Ok(None);
+        };
+
+        let mut xs = Vec::with_capacity(grid.len());
+        let mut ys = Vec::with_capacity(grid.len());
+        let mut row = Vec::with_capacity(resampled.len());
+        for (i, x) in grid.iter().enumerate() {
+            row.clear();
+            row.extend(resampled.iter().map(|col| col[i]));
+            let y = self.operation.apply(&row);
+            // Skip points where sources could not be interpolated or the
+            // operation is undefined (e.g. division by zero).
+            if y.is_finite() {
+                xs.push(*x);
+                ys.push(y);
+            }
+        }
+
+        PlotData::from_columns(vec![xs, ys], self.label(registry), self.units(registry)).map(Some)
+    }
+
+    /// Find the factor k which minimises the residual of A − k·B within the
+    /// x-window from `left` to `right` (linear least squares).
+    ///
+    /// Returns `Ok(None)` if the data of a source is still being loaded.
+    pub fn fit_difference_factor(
+        &self,
+        registry: &HashMap<FileID, File>,
+        left: f64,
+        right: f64,
+    ) -> Result<Option<f64>, String> {
+        if !matches!(self.operation, Operation::Difference { .. }) {
+            return Err("a factor can only be fitted for differences".to_string());
+        }
+        let Some((grid, resampled)) = self.common_grid(registry)? else {
+            return Ok(None);
+        };
+        let (left, right) = (left.min(right), left.max(right));
+
+        let (ab, bb) = grid
+            .iter()
+            .zip(resampled[0].iter().zip(resampled[1].iter()))
+            .filter(|(x, (a, b))| left <= **x && **x <= right && a.is_finite() && b.is_finite())
+            .fold((0.0, 0.0), |(ab, bb), (_, (a, b))| (ab + a * b, bb + b * b));
+
+        if bb == 0.0 {
+            return Err("subtrahend has no signal within the fit window".to_string());
+        }
+        Ok(Some(ab / bb))
+    }
+
+    /// Name under which a derived file is shown. The label must not contain
+    /// path separators, since it is stored as the file's path.
+    pub fn file_name(&self, registry: &HashMap<FileID, File>) -> PathBuf {
+        self.label(registry).replace(['/', '\\'], "_").into()
+    }
+
+    /// Interpolate the data of all sources onto the common x-grid.
+    ///
+    /// Returns `Ok(None)` if the data of a source is still being loaded.
+    #[allow(clippy::type_complexity)]
+    fn common_grid(
+        &self,
+        registry: &HashMap<FileID, File>,
+    ) -> Result<Option<(Vec<f64>, Vec<Vec<f64>>)>, String> {
+        self.operation.check_num_sources(self.sources.len())?;
+
+        let units = self.units(registry);
+        let mut inputs = Vec::with_capacity(self.sources.len());
+        for fid in self.sources.iter() {
+            let Some(file) = registry.get(fid) else {
+                return Err(format!("source file with ID {fid:?} not found"));
+            };
+            if !file.data.is_up_to_date() {
+                return Ok(None);
+            }
+            match file.data.value() {
+                Ok(_) => (),
+                Err(msg) if msg == NOT_EVALUATED_MSG => return Ok(None),
+                Err(_) => return Err(format!("source file {} has no data", file.file_name())),
+            }
+            // The data of all sources is combined in the displayed units.
+            if file.display_units() != units {
+                return Err(format!(
+                    "source file {} is displayed in different units",
+                    file.file_name()
+                ));
+            }
+            let (xs, ys) = file
+                .xy_data()
+                .map_err(|err| format!("source file {}: {err}", file.file_name()))?;
+            inputs.push((xs, ys));
+        }
+
+        // The x-range covered by all sources.
+        let (lower, upper) = inputs
+            .iter()
+            .fold((f64::MIN, f64::MAX), |(lo, hi), (xs, _)| {
+                let (min, max) = xs
+                    .iter()
+                    .filter(|x| x.is_finite())
+                    .fold((f64::MAX, f64::MIN), |(min, max), x| {
+                        (min.min(*x), max.max(*x))
+                    });
+                (lo.max(min), hi.min(max))
+            });
+
+        // Repeated x-values of the first source are merged into one grid point.
+        let (xs_first, _) = logic::ascending(&inputs[0].0, &inputs[0].1);
+        let grid: Vec<f64> = xs_first
+            .iter()
+            .filter(|x| lower <= **x && **x <= upper)
+            .copied()
+            .collect();
+        if grid.len() < 2 {
+            return Err("source files have no common x-range".to_string());
+        }
+
+        let resampled = inputs
+            .iter()
+            .map(|(xs, ys)| resample(xs, ys, &grid, self.interpolation))
+            .collect();
+
+        Ok(Some((grid, resampled)))
+    }
+
+    /// Units of the result, which are the displayed units of the first
+    /// source.
+    fn units(&self, registry: &HashMap<FileID, File>) -> (XUnit, YUnit) {
+        self.sources
+            .first()
+            .and_then(|fid| registry.get(fid))
+            .map(|file| file.display_units())
+            .unwrap_or_default()
+    }
+
+    /// Human readable description of the recipe, using the aliases or file
+    /// names of the sources.
+    pub fn label(&self, registry: &HashMap<FileID, File>) -> String {
+        let names: Vec<&str> = self
+            .sources
+            .iter()
+            .map(|fid| {
+                registry
+                    .get(fid)
+                    .map(|file| file.display_name())
+                    .unwrap_or("<missing>")
+            })
+            .collect();
+        match (self.operation, &names[..]) {
+            (Operation::Difference { factor }, [a, b]) => {
+                // Tuning the factor interactively yields long decimals.
+                let factor = (factor * 1e4).round() / 1e4;
+                format!("{a} − {factor}·{b}")
+            }
+            (Operation::Ratio, [a, b]) => format!("{a} ÷ {b}"),
+            (Operation::Mean, names) => format!("mean({})", names.join(", ")),
+            (Operation::Sum, names) => format!("sum({})", names.join(", ")),
+            (op, names) => format!("{}({})", op.name(), names.join(", ")),
+        }
+    }
+}
+
+impl Default for ArithmeticForm {
+    fn default() -> Self {
+        Self {
+            operation: Operation::Difference { factor: 1.0 },
+            minuend: None,
+            subtrahend: None,
+            selection: Vec::new(),
+            interpolation: Interpolation::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use app_core::frontend::UIParameter;
+
+    use super::*;
+
+    fn registry_with(data: Vec<(Vec<f64>, Vec<f64>)>) -> HashMap<FileID, File> {
+        data.into_iter()
+            .enumerate()
+            .map(|(i, (xs, ys))| {
+                let file = File {
+                    data: UIParameter::new(PlotData::from_columns(
+                        vec![xs, ys],
+                        String::new(),
+                        Default::default(),
+                    )),
+                    path: format!("file{i}.csv").into(),
+                    properties: Default::default(),
+                    recipe: None,
+                };
+                (FileID(i), file)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_difference_interpolates_onto_first_grid() {
+        let registry = registry_with(vec![
+            (vec![0.0, 1.0, 2.0, 3.0], vec![1.0, 2.0, 3.0, 4.0]),
+            (vec![0.5, 1.5, 2.5], vec![1.0, 1.0, 1.0]),
+        ]);
+        let recipe = Recipe {
+            operation: Operation::Difference { factor: 2.0 },
+            sources: vec![FileID(0), FileID(1)],
+            interpolation: Interpolation::Linear,
+        };
+        let data = recipe.evaluate(&registry).unwrap().unwrap();
+        // Only x = 1 and x = 2 lie within the common range [0.5, 2.5].
+        assert_eq!(data.columns[0], vec![1.0, 2.0]);
+        assert_eq!(data.columns[1], vec![0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_mean_and_sum() {
+        let xs = vec![0.0, 1.0, 2.0];
+        let registry = registry_with(vec![
+            (xs.clone(), vec![1.0, 2.0, 3.0]),
+            (xs.clone(), vec![3.0, 4.0, 5.0]),
+            (xs.clone(), vec![5.0, 6.0, 7.0]),
+        ]);
+        let sources = vec![FileID(0), FileID(1), FileID(2)];
+        let mean = Recipe {
+            operation: Operation::Mean,
+            sources: sources.clone(),
+            interpolation: Interpolation::Linear,
+        };
+        let sum = Recipe {
+            operation: Operation::Sum,
+            sources,
+            interpolation: Interpolation::Linear,
+        };
+        let mean = mean.evaluate(&registry).unwrap().unwrap();
+        let sum = sum.evaluate(&registry).unwrap().unwrap();
+        assert_eq!(mean.columns[1], vec![3.0, 4.0, 5.0]);
+        assert_eq!(sum.columns[1], vec![9.0, 12.0, 15.0]);
+    }
+
+    #[test]
+    fn test_ratio_skips_division_by_zero() {
+        let xs = vec![0.0, 1.0, 2.0];
+        let registry = registry_with(vec![
+            (xs.clone(), vec![1.0, 2.0, 3.0]),
+            (xs.clone(), vec![1.0, 0.0, 2.0]),
+        ]);
+        let recipe = Recipe {
+            operation: Operation::Ratio,
+            sources: vec![FileID(0), FileID(1)],
+            interpolation: Interpolation::Linear,
+        };
+        let data = recipe.evaluate(&registry).unwrap().unwrap();
+        assert_eq!(data.columns[0], vec![0.0, 2.0]);
+        assert_eq!(data.columns[1], vec![1.0, 1.5]);
+    }
+
+    #[test]
+    fn test_fit_difference_factor() {
+        let xs = vec![0.0, 1.0, 2.0, 3.0, 4.0];
+        let registry = registry_with(vec![
+            (xs.clone(), vec![1.0, 2.0, 7.0, 2.0, 1.0]),
+            (xs.clone(), vec![0.0, 0.0, 2.0, 0.0, 0.0]),
+        ]);
+        let recipe = Recipe {
+            operation: Operation::Difference { factor: 1.0 },
+            sources: vec![FileID(0), FileID(1)],
+            interpolation: Interpolation::Linear,
+        };
+        let k = recipe.fit_difference_factor(&registry, 1.5, 2.5).unwrap();
+        assert_eq!(k, Some(3.5));
+        // No signal of the subtrahend within the window.
+        assert!(recipe.fit_difference_factor(&registry, 3.0, 4.0).is_err());
+    }
+
+    #[test]
+    fn test_wrong_number_of_sources() {
+        let registry = registry_with(vec![(vec![0.0, 1.0], vec![1.0, 2.0])]);
+        let recipe = Recipe {
+            operation: Operation::Ratio,
+            sources: vec![FileID(0)],
+            interpolation: Interpolation::Linear,
+        };
+        assert!(recipe.evaluate(&registry).is_err());
+    }
+}
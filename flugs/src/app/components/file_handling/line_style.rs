@@ -0,0 +1,125 @@
+//! Line style and markers of a file, in the plotter and in exported figures.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+    /// Only the markers are drawn, e.g. for sparse data.
+    None,
+}
+
+impl LineStyle {
+    pub const ALL: [LineStyle; 4] = [
+        LineStyle::Solid,
+        LineStyle::Dashed,
+        LineStyle::Dotted,
+        LineStyle::None,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineStyle::Solid => "Solid",
+            LineStyle::Dashed => "Dashed",
+            LineStyle::Dotted => "Dotted",
+            LineStyle::None => "No Line",
+        }
+    }
+
+    /// The style of the line in the plotter, `None` if no line is drawn.
+    pub fn egui(&self, width: f32) -> Option<egui_plot::LineStyle> {
+        match self {
+            LineStyle::Solid => Some(egui_plot::LineStyle::Solid),
+            LineStyle::Dashed => Some(egui_plot::LineStyle::Dashed {
+                length: 6.0 * width.max(1.0),
+            }),
+            LineStyle::Dotted => Some(egui_plot::LineStyle::Dotted {
+                spacing: 3.0 * width.max(1.0),
+            }),
+            LineStyle::None => None,
+        }
+    }
+
+    pub fn export(&self) -> svg_export::LineStyle {
+        match self {
+            LineStyle::Solid => svg_export::LineStyle::Solid,
+            LineStyle::Dashed => svg_export::LineStyle::Dashed,
+            LineStyle::Dotted => svg_export::LineStyle::Dotted,
+            LineStyle::None => svg_export::LineStyle::None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Marker {
+    #[default]
+    None,
+    Circle,
+    Square,
+    Triangle,
+}
+
+impl Marker {
+    pub const ALL: [Marker; 4] = [
+        Marker::None,
+        Marker::Circle,
+        Marker::Square,
+        Marker::Triangle,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Marker::None => "None",
+            Marker::Circle => "Circle",
+            Marker::Square => "Square",
+            Marker::Triangle => "Triangle",
+        }
+    }
+
+    /// The marker drawn with `line_style`. Files without a line are always
+    /// drawn with markers, circles unless set otherwise.
+    pub fn drawn_with(&self, line_style: LineStyle) -> Marker {
+        match (self, line_style) {
+            (Marker::None, LineStyle::None) => Marker::Circle,
+            (marker, _) => *marker,
+        }
+    }
+
+    pub fn egui(&self) -> Option<egui_plot::MarkerShape> {
+        match self {
+            Marker::None => None,
+            Marker::Circle => Some(egui_plot::MarkerShape::Circle),
+            Marker::Square => Some(egui_plot::MarkerShape::Square),
+            Marker::Triangle => Some(egui_plot::MarkerShape::Up),
+        }
+    }
+
+    pub fn export(&self) -> Option<svg_export::Marker> {
+        match self {
+            Marker::None => None,
+            Marker::Circle => Some(svg_export::Marker::Circle),
+            Marker::Square => Some(svg_export::Marker::Square),
+            Marker::Triangle => Some(svg_export::Marker::Triangle),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marker_drawn_with() {
+        assert_eq!(Marker::None.drawn_with(LineStyle::Solid), Marker::None);
+        assert_eq!(Marker::None.drawn_with(LineStyle::None), Marker::Circle);
+        assert_eq!(Marker::Square.drawn_with(LineStyle::None), Marker::Square);
+        assert!(LineStyle::None.egui(1.0).is_none());
+        assert_eq!(
+            Marker::Triangle.export(),
+            Some(svg_export::Marker::Triangle)
+        );
+    }
+}
@@ -1,10 +1,16 @@
-use std::{collections::HashSet, path::Path};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    ops::Range,
+    path::Path,
+};
 
 use app_core::{
     BACKEND_HUNG_UP_MSG,
     backend::{BackendEventLoop, BackendLink, LinkReceiver},
     frontend::UIParameter,
 };
+use egui::Color32;
 use egui_plot::PlotPoint;
 use log::{debug, warn};
 
@@ -17,92 +23,96 @@ use crate::{
             search::{Match, ParsedData},
         },
     },
-    backend_state::PlotData,
+    backend_state::{PlotData, Unit, XUnit, YUnit, convert_units},
 };
 
-use super::{File, FileHandler, FileID, Group};
+use super::{
+    File, FileHandler, FileID, FileProperties, Group, NormalisationMode, Palette, Recipe,
+    resample::{Interpolation, resample},
+};
 
 impl File {
     pub fn get_cache(&self) -> Option<&[PlotPoint]> {
         self.data.value().as_ref().map(|dat| dat.get_cache()).ok()
     }
 
-    // Integrate data numerically using trapezoidal method.
-    //
-    // Returns NaN if something goes wrong.
-    pub fn integrate(&mut self, left: f64, right: f64, local_baseline: bool) -> f64 {
-        // Retrieve its data, if it was parsed correctly.
-        let Ok(data) = self.data.value() else {
-            log::error!(
-                "File {} was not parsed correctly, cannot integrate",
-                self.file_name()
-            );
-            return f64::NAN;
-        };
-
-        // Retrieve x and y data.
-        let msg = format!(
-            "File {} needs at least two columns for integration",
-            self.file_name()
-        );
-
-        let Some(xs) = data.columns.get(self.properties.selected_x_column) else {
-            log::error!("{msg}");
-            return f64::NAN;
-        };
-        let Some(ys) = data.columns.get(self.properties.selected_y_column) else {
-            log::error!("{msg}");
-            return f64::NAN;
-        };
+    /// Integrate the data within the x-window from `left` to `right`.
+    pub fn integrate(
+        &self,
+        left: f64,
+        right: f64,
+        method: &IntegrationMethod,
+        baseline: Option<&BaselineData>,
+    ) -> Result<f64, IntegrationError> {
+        self.window_samples(left, right, method, baseline)?
+            .area(method)
+            .and_then(checked_area)
+    }
 
-        // Filter out data points where both x- and y-value are finite and not NaN.
-        let (xs, ys) = xs
-            .iter()
-            .zip(ys)
-            .filter(|(x, y)| x.is_finite() && y.is_finite())
-            .fold(
-                (Vec::with_capacity(xs.len()), Vec::with_capacity(xs.len())),
-                |(mut xs, mut ys), (x, y)| {
-                    xs.push(*x);
-                    ys.push(*y);
-                    (xs, ys)
-                },
-            );
+    /// Area, peak maximum and centroid of the data within the x-window from
+    /// `left` to `right`.
+    pub fn window_statistics(
+        &self,
+        left: f64,
+        right: f64,
+        method: &IntegrationMethod,
+        baseline: Option<&BaselineData>,
+    ) -> Result<WindowStatistics, IntegrationError> {
+        // Retrieve x and y data in the displayed units.
+        let (xs, ys) = self.xy_data().map_err(IntegrationError::NotParsed)?;
+        let external = self
+            .checked_baseline(method, baseline)?
+            .map(|b| (b.xs.as_slice(), b.ys.as_slice()));
+        window_statistics(&xs, &ys, left, right, method, external)
+    }
 
-        // Apply trapezoidal integration.
+    /// Samples of the data and the baseline within the x-window from `left`
+    /// to `right`, in the displayed units.
+    pub fn window_samples(
+        &self,
+        left: f64,
+        right: f64,
+        method: &IntegrationMethod,
+        baseline: Option<&BaselineData>,
+    ) -> Result<WindowSamples, IntegrationError> {
+        let (xs, ys) = self.xy_data().map_err(IntegrationError::NotParsed)?;
+        let external = self
+            .checked_baseline(method, baseline)?
+            .map(|b| (b.xs.as_slice(), b.ys.as_slice()));
+        WindowSamples::new(&xs, &ys, left, right, method.baseline, external)
+    }
 
-        match trapz(&xs, &ys, left, right, local_baseline) {
-            Ok(area) => area,
-            Err(err) => {
-                log::error!("Failed to integrate file {}: {err}", self.file_name());
-                f64::NAN
-            }
+    /// Make sure that the external baseline (if needed) can be subtracted
+    /// from the data of this file.
+    fn checked_baseline<'a>(
+        &self,
+        method: &IntegrationMethod,
+        baseline: Option<&'a BaselineData>,
+    ) -> Result<Option<&'a BaselineData>, IntegrationError> {
+        let Baseline::External(_) = method.baseline else {
+            return Ok(None);
+        };
+        let baseline = baseline
+            .ok_or_else(|| IntegrationError::BaselineUnavailable("no baseline data".to_string()))?;
+        if baseline.units != self.display_units() {
+            return Err(IntegrationError::BaselineUnavailable(
+                "baseline is displayed in different units".to_string(),
+            ));
         }
+        Ok(Some(baseline))
     }
 
     pub fn local_minimum(&mut self, left: f64, right: f64, after_scaling: bool) -> f64 {
-        // Retrieve its data, if it was parsed correctly.
-        let Ok(data) = self.data.value() else {
-            log::error!(
-                "File {} was not parsed correctly, cannot determine local minimum ",
-                self.file_name()
-            );
-            return f64::NAN;
-        };
-
-        // Retrieve x and y data.
-        let msg = format!(
-            "File {} needs at least two columns to determine local minimum",
-            self.file_name()
-        );
-
-        let Some(xs) = data.columns.get(self.properties.selected_x_column) else {
-            log::error!("{msg}");
-            return f64::NAN;
-        };
-        let Some(ys) = data.columns.get(self.properties.selected_y_column) else {
-            log::error!("{msg}");
-            return f64::NAN;
+        // Retrieve x and y data in the displayed units.
+        let (xs, ys) = match self.xy_data() {
+            Ok(xy) => xy,
+            Err(err) => {
+                log::error!(
+                    "Cannot determine local minimum of file {}: {err}",
+                    self.file_name()
+                );
+                return f64::NAN;
+            }
         };
 
         // Make sure left and right are sorted correctly.
@@ -111,7 +121,7 @@ impl File {
         // Return minimum
         let mut minimum = xs
             .iter()
-            .zip(ys)
+            .zip(&ys)
             // Filter out y-values for which x is within left and right bound.
             .filter_map(|(x, y)| {
                 if *x < left || *x > right {
@@ -131,17 +141,88 @@ impl File {
         minimum
     }
 
+    /// Units of the data, as determined by the parser unless set by the user.
+    pub fn data_units(&self) -> (XUnit, YUnit) {
+        let (x_unit, y_unit) = self
+            .data
+            .value()
+            .as_ref()
+            .map(|data| (data.x_unit, data.y_unit))
+            .unwrap_or_default();
+        let units = &self.properties.units;
+        (
+            known(units.data_x).unwrap_or(x_unit),
+            known(units.data_y).unwrap_or(y_unit),
+        )
+    }
+
+    /// Units in which the file is displayed.
+    pub fn display_units(&self) -> (XUnit, YUnit) {
+        let (x_unit, y_unit) = self.data_units();
+        let units = &self.properties.units;
+        (
+            known(units.display_x).unwrap_or(x_unit),
+            known(units.display_y).unwrap_or(y_unit),
+        )
+    }
+
+    /// The selected x- and y-column, converted to the displayed units.
+    pub fn xy_data(&self) -> Result<(Vec<f64>, Vec<f64>), String> {
+        let data = self
+            .data
+            .value()
+            .as_ref()
+            .map_err(|_| "file was not parsed correctly".to_string())?;
+        let (Some(xs), Some(ys)) = (
+            data.columns.get(self.properties.selected_x_column),
+            data.columns.get(self.properties.selected_y_column),
+        ) else {
+            return Err("at least two columns are needed".to_string());
+        };
+        let ((x_from, y_from), (x_to, y_to)) = (self.data_units(), self.display_units());
+        convert_units(xs, ys, (x_from, x_to), (y_from, y_to))
+    }
+
+    /// Coefficients `(scale, shift)` of the normalisation of the file.
+    pub fn normalisation_coefficients(&self) -> Result<(f64, f64), String> {
+        if self.properties.normalisation.mode == NormalisationMode::None {
+            return Ok((1.0, 0.0));
+        }
+        let (xs, ys) = self.xy_data()?;
+        self.properties.normalisation.coefficients(&xs, &ys)
+    }
+
     pub fn refresh_cache(&mut self) {
         debug!("Refreshing cache for {:?}", self.file_name());
-        let Ok(data) = self.data.value_mut() else {
-            warn!("No cache to act upon yet for {:?}", self.file_name());
-            return;
+        let (xs, ys) = match self.xy_data() {
+            Ok(xy) => xy,
+            Err(err) => {
+                warn!("No cache to act upon for {:?}: {err}", self.file_name());
+                return;
+            }
         };
-        data.rescale(
-            self.properties.xoffset,
-            self.properties.yoffset,
-            self.properties.yscale,
-        );
+        // The normalisation is applied before the offsets and scaling.
+        let (nscale, nshift) = self.normalisation_coefficients().unwrap_or_else(|err| {
+            log::error!("Failed to normalise file {}: {err}", self.file_name());
+            (1.0, 0.0)
+        });
+        let FileProperties {
+            xoffset,
+            yoffset,
+            yscale,
+            ..
+        } = self.properties;
+        let cache = xs
+            .into_iter()
+            .zip(ys)
+            .map(|(x, y)| PlotPoint {
+                x: x + xoffset,
+                y: (y * nscale + nshift) * yscale + yoffset,
+            })
+            .collect();
+        if let Ok(data) = self.data.value_mut() {
+            data.set_cache(cache);
+        }
     }
 }
 
@@ -193,6 +274,7 @@ impl FileHandler {
                         path: search_path.join(fp),
                         data: csv_data,
                         properties: super::FileProperties::default(),
+                        recipe: None,
                     },
                 );
                 fid
@@ -289,6 +371,156 @@ impl FileHandler {
         }
     }
 
+    /// The displayed units shared by all plotted files. `None` if the units
+    /// differ or are unknown. The y-unit is only shared by the files on the
+    /// primary y-axis, see `secondary_y_unit`.
+    pub fn plotted_units(&self) -> (Option<XUnit>, Option<YUnit>) {
+        let units: Vec<(XUnit, YUnit, bool)> = self
+            .plotted_files()
+            .map(|file| {
+                let (x, y) = file.display_units();
+                (x, y, file.properties.secondary_y)
+            })
+            .collect();
+        (
+            shared(units.iter().map(|(x, _, _)| *x)),
+            shared(
+                units
+                    .iter()
+                    .filter(|(_, _, secondary)| !secondary)
+                    .map(|(_, y, _)| *y),
+            ),
+        )
+    }
+
+    /// The displayed y-unit shared by all plotted files on the secondary
+    /// y-axis.
+    pub fn secondary_y_unit(&self) -> Option<YUnit> {
+        shared(
+            self.plotted_files()
+                .filter(|file| file.properties.secondary_y)
+                .map(|file| file.display_units().1),
+        )
+    }
+
+    /// The files of all groups with the index of their color in the palette
+    /// of their group. Groups sharing a qualitative palette continue its
+    /// cycle, so that their files are distinct, while sequential palettes
+    /// span each group. The indices only depend on the order of the groups
+    /// and files, so they persist between sessions. Files in several groups
+    /// are listed once for each group.
+    pub fn palette_indices(&self) -> Vec<(&Group, FileID, usize)> {
+        let mut indices = Vec::new();
+        let mut cycles: HashMap<Palette, usize> = HashMap::new();
+        for grp in self.groups.iter().flatten() {
+            let palette = grp.palette.unwrap_or_default();
+            let offset = cycles.entry(palette).or_default();
+            for (i, fid) in grp.file_ids.iter().enumerate() {
+                let index = if palette.is_sequential() {
+                    i
+                } else {
+                    *offset + i
+                };
+                indices.push((grp, *fid, index));
+            }
+            if !palette.is_sequential() {
+                *offset += grp.file_ids.len();
+            }
+        }
+        indices
+    }
+
+    /// The colors of the files of all groups given by the palettes of the
+    /// groups (see `palette_indices`), regardless of custom colors. Files in
+    /// several groups take the color of the first one.
+    pub fn palette_colors(&self) -> HashMap<FileID, Color32> {
+        let mut colors = HashMap::new();
+        for (grp, fid, index) in self.palette_indices() {
+            let palette = grp.palette.unwrap_or_default();
+            colors
+                .entry(fid)
+                .or_insert_with(|| palette.color(index, grp.file_ids.len()));
+        }
+        colors
+    }
+
+    /// The colors of the files of all groups: their custom colors, or the
+    /// colors of the palettes of their groups.
+    pub fn file_colors(&self) -> HashMap<FileID, Color32> {
+        self.with_custom_colors(self.palette_colors())
+    }
+
+    /// The palette colors `colors` of the files, see `palette_colors`, with
+    /// the custom colors of the files in their place.
+    pub fn with_custom_colors(
+        &self,
+        mut colors: HashMap<FileID, Color32>,
+    ) -> HashMap<FileID, Color32> {
+        for (fid, color) in colors.iter_mut() {
+            if let Some(custom) = self
+                .registry
+                .get(fid)
+                .and_then(|file| file.properties.color)
+            {
+                *color = custom;
+            }
+        }
+        colors
+    }
+
+    /// All files with data in the plotted groups.
+    fn plotted_files(&self) -> impl Iterator<Item = &File> {
+        self.groups
+            .iter()
+            .filter_map(|g| g.as_ref().filter(|g| g.is_plotted))
+            .flat_map(|g| g.file_ids.iter())
+            .filter_map(|fid| self.registry.get(fid))
+            .filter(|file| file.get_cache().is_some())
+    }
+
+    /// Data of the external baseline of `method`, if it uses one.
+    pub fn baseline_data(
+        &self,
+        method: &IntegrationMethod,
+    ) -> Result<Option<BaselineData>, IntegrationError> {
+        let Baseline::External(fid) = method.baseline else {
+            return Ok(None);
+        };
+        let file = self.registry.get(&fid).ok_or_else(|| {
+            IntegrationError::BaselineUnavailable("baseline file was removed".to_string())
+        })?;
+        let (xs, ys) = file
+            .xy_data()
+            .map_err(IntegrationError::BaselineUnavailable)?;
+        Ok(Some(BaselineData {
+            xs,
+            ys,
+            units: file.display_units(),
+        }))
+    }
+
+    /// Human readable description of an integration method, which names the
+    /// file of an external baseline.
+    pub fn describe_method(&self, method: &IntegrationMethod) -> String {
+        let baseline = match method.baseline {
+            Baseline::External(fid) => format!(
+                "baseline from {}",
+                self.registry
+                    .get(&fid)
+                    .map(|file| file.display_name())
+                    .unwrap_or("<missing>")
+            ),
+            Baseline::None => "no baseline".to_string(),
+            other => format!("{} baseline", other.name().to_lowercase()),
+        };
+        let area = if method.absolute {
+            "absolute"
+        } else {
+            "signed"
+        };
+        format!("{} rule, {baseline}, {area} area", method.rule.name())
+    }
+
     pub fn current_id(&self) -> FileID {
         self.next_id
     }
@@ -305,6 +537,25 @@ impl FileHandler {
         fid
     }
 
+    /// Add a new derived file to the registry and to the group with ID
+    /// `gid`, which is created if it does not exist yet. The data of the
+    /// file is empty until its recipe is evaluated.
+    pub fn add_derived_file(&mut self, recipe: Recipe, gid: usize) -> FileID {
+        let file = File::derived(recipe.file_name(&self.registry), Default::default(), recipe);
+        let fid = self.add_new_file(file);
+        match &mut self.groups[gid] {
+            Some(grp) => grp.file_ids.push(fid),
+            None => {
+                self.groups[gid] = Some(Group {
+                    file_ids: vec![fid],
+                    name: format!("G{}", gid),
+                    ..Default::default()
+                })
+            }
+        }
+        fid
+    }
+
     fn fid_to_filename_str(&self, fid: &FileID) -> &str {
         self.registry
             .get(fid)
@@ -315,15 +566,21 @@ impl FileHandler {
     pub fn try_update(&mut self) -> bool {
         let mut was_updated = false;
         for file in self.registry.values_mut() {
-            was_updated = was_updated || file.data.try_update();
+            // Newly loaded data has to be shifted, scaled and normalised.
+            if file.data.try_update() {
+                file.refresh_cache();
+                was_updated = true;
+            }
         }
         was_updated
     }
 
     pub fn consolidate_files(&self, path: &Path) {
+        // Derived files have no counterpart on disk.
         let unique_paths: HashSet<_> = self
             .registry
             .values()
+            .filter(|file| file.recipe.is_none())
             .map(|file| file.path.to_owned())
             .collect();
 
@@ -339,6 +596,17 @@ impl FileHandler {
     }
 }
 
+/// Treat an unknown unit like an unset one.
+fn known<U: Unit + Default>(unit: Option<U>) -> Option<U> {
+    unit.filter(|unit| *unit != U::default())
+}
+
+/// The unit shared by all `units`, if it is known.
+fn shared<U: Unit + Default>(mut units: impl Iterator<Item = U>) -> Option<U> {
+    let first = units.next()?;
+    known(Some(first)).filter(|_| units.all(|unit| unit == first))
+}
+
 pub fn parse_csv(
     path: &Path,
     request_tx: &mut DynRequestSender,
@@ -362,120 +630,413 @@ pub fn parse_csv(
 // ----------------------------------------------------------------------------
 //
 //
-// Integration Utilities
+// Ordering Utilities
 //
 //
 // ----------------------------------------------------------------------------
 
-/// Trapezoidal integration.
-///
-/// Local baseline subtracts a linear baseline ranging from the start (left) to
-/// the end (right) point of the integration window.
-pub fn trapz(
-    x: &[f64],
-    y: &[f64],
-    left: f64,
-    right: f64,
-    local_baseline: bool,
-) -> Result<f64, String> {
-    let (mut left, right) = (left.min(right), left.max(right));
+/// Ordering of the x-values of data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XOrder {
+    /// Strictly ascending.
+    Ascending,
+    /// Strictly descending.
+    Descending,
+    /// Neither, e.g. because x-values are repeated.
+    Unsorted,
+}
 
-    let n = x.len().min(y.len());
-    if n <= 1 {
-        return Err("Not enough values to integrate".into());
-    }
-    if x[0] >= right || x[n - 1] <= left {
-        return Err("Integration window out of bounds".into());
+/// Detect the ordering of x-values. Data with less than two values counts as
+/// ascending.
+pub fn x_order(x: &[f64]) -> XOrder {
+    if x.windows(2).all(|w| w[0] < w[1]) {
+        XOrder::Ascending
+    } else if x.windows(2).all(|w| w[0] > w[1]) {
+        XOrder::Descending
+    } else {
+        XOrder::Unsorted
     }
+}
 
-    let mut area: f64;
-    // subtract local linear baseline, defined by start and end-point of integration window
-    if local_baseline {
-        let xs = vec![left, right];
-        let ys = linear_resample_array(x, y, &xs);
-        if ys.iter().any(|y| (*y).is_nan()) {
-            return Err("Integration window out of bounds.".into());
+/// Bring data into strictly ascending order of x. Points with non-finite
+/// values are dropped and the y-values of points with the same x-value are
+/// averaged. If the data is ascending already, it is borrowed.
+pub fn ascending<'a>(x: &'a [f64], y: &'a [f64]) -> (Cow<'a, [f64]>, Cow<'a, [f64]>) {
+    let n = x.len().min(y.len());
+    let (x, y) = (&x[..n], &y[..n]);
+    let all_finite = x.iter().chain(y).all(|v| v.is_finite());
+    match x_order(x) {
+        XOrder::Ascending if all_finite => (Cow::Borrowed(x), Cow::Borrowed(y)),
+        XOrder::Descending if all_finite => (
+            x.iter().rev().copied().collect(),
+            y.iter().rev().copied().collect(),
+        ),
+        _ => {
+            let (x, y) = sort_and_merge(x, y);
+            (Cow::Owned(x), Cow::Owned(y))
         }
-        area = -singletrapz(left, right, ys[0], ys[1])
-    } else {
-        area = 0.0_f64;
-    }
-
-    let mut inside_integration_window = false;
-    let mut lastiter = false;
-    let mut j = 2;
-
-    while j <= n {
-        let mut x0 = x[j - 1];
-        let mut x1 = x[j];
-        let mut y0 = y[j - 1];
-        let mut y1 = y[j];
-
-        if x1 <= left {
-            j += 1;
-            continue;
-        } else if !inside_integration_window {
-            // this will only run once, when we enter the integration window
-            // test whether x0 should be replaced by left
-            if x0 < left {
-                y0 = lininterp(left, x0, x1, y0, y1);
-                x0 = left;
-            } else {
-                // this case means that left <= x[0]
-                left = x0;
+    }
+}
+
+/// Sort data by x and merge points with the same x-value by averaging their
+/// y-values. Points with non-finite values are dropped.
+pub fn sort_and_merge(x: &[f64], y: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let mut points: Vec<(f64, f64)> = x
+        .iter()
+        .zip(y)
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+        .map(|(x, y)| (*x, *y))
+        .collect();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut xs: Vec<f64> = Vec::with_capacity(points.len());
+    let mut ys: Vec<f64> = Vec::with_capacity(points.len());
+    let mut count = 0;
+    for (x, y) in points {
+        if xs.last() == Some(&x) {
+            // Update the running mean of the merged points.
+            count += 1;
+            if let Some(last) = ys.last_mut() {
+                *last += (y - *last) / count as f64;
             }
-            inside_integration_window = true;
+        } else {
+            xs.push(x);
+            ys.push(y);
+            count = 1;
         }
+    }
+    (xs, ys)
+}
+
+// ----------------------------------------------------------------------------
+//
+//
+// Integration Utilities
+//
+//
+// ----------------------------------------------------------------------------
 
-        // test whether x1 should be replaced by right
-        if x1 >= right {
-            // we move out of the integration window
+/// Reasons why the integration of a window fails.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IntegrationError {
+    /// The data is not available, e.g. because the file was not parsed.
+    NotParsed(String),
+    TooFewPoints,
+    WindowOutOfBounds,
+    /// The area is zero, so it cannot be used for scaling.
+    ZeroArea,
+    NonFiniteArea,
+    /// Simpson's rule needs evenly spaced data points.
+    NonUniformGrid,
+    /// The data of an external baseline cannot be used.
+    BaselineUnavailable(String),
+}
 
-            if x1 != right {
-                y1 = lininterp(right, x0, x1, y0, y1)
-            };
-            x1 = right;
-            lastiter = true; // we shall break the loop after this iteration
+impl std::fmt::Display for IntegrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntegrationError::NotParsed(reason) => write!(f, "no data: {reason}"),
+            IntegrationError::TooFewPoints => write!(f, "not enough values to integrate"),
+            IntegrationError::WindowOutOfBounds => write!(f, "integration window out of bounds"),
+            IntegrationError::ZeroArea => write!(f, "area is zero"),
+            IntegrationError::NonFiniteArea => write!(f, "area is not finite"),
+            IntegrationError::NonUniformGrid => {
+                write!(
+                    f,
+                    "data points are not evenly spaced, cannot use Simpson's rule"
+                )
+            }
+            IntegrationError::BaselineUnavailable(reason) => {
+                write!(f, "baseline unavailable: {reason}")
+            }
         }
+    }
+}
 
-        area += singletrapz(x0, x1, y0, y1);
+/// Make sure that an area can be used for scaling, i.e. that its inverse is
+/// finite as well.
+pub fn checked_area(area: f64) -> Result<f64, IntegrationError> {
+    if !area.is_finite() {
+        Err(IntegrationError::NonFiniteArea)
+    } else if !(1.0 / area).is_finite() {
+        Err(IntegrationError::ZeroArea)
+    } else {
+        Ok(area)
+    }
+}
 
-        if lastiter {
-            break;
+/// Numerical integration rule.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IntegrationRule {
+    #[default]
+    Trapezoid,
+    /// Composite Simpson's rule (with the 3/8 rule for the last three
+    /// intervals of an odd number of intervals). The data points within the
+    /// window must be evenly spaced.
+    Simpson,
+}
+
+impl IntegrationRule {
+    pub const ALL: [IntegrationRule; 2] = [IntegrationRule::Trapezoid, IntegrationRule::Simpson];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntegrationRule::Trapezoid => "Trapezoid",
+            IntegrationRule::Simpson => "Simpson",
         }
+    }
+}
 
-        j += 1;
+/// Baseline that is subtracted from the data before integration.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Baseline {
+    None,
+    /// Straight line between the data at the window borders.
+    #[default]
+    Chord,
+    /// Horizontal line at the minimum of the data within the window.
+    WindowMinimum,
+    /// The data of another file, e.g. a baseline created by the processing
+    /// software or a derived file.
+    External(FileID),
+}
+
+impl Baseline {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Baseline::None => "None",
+            Baseline::Chord => "Chord",
+            Baseline::WindowMinimum => "Window Minimum",
+            Baseline::External(_) => "External",
+        }
     }
-    Ok(area)
 }
 
-pub fn linear_resample_array(xs: &[f64], ys: &[f64], grid: &[f64]) -> Vec<f64> {
-    let segments = xs
-        .iter()
-        .zip(ys.iter())
-        .zip(xs.iter().skip(1).zip(ys.iter().skip(1)))
-        .map(|((x0, y0), (x1, y1))| (*x0, *y0, *x1, *y1))
-        .collect::<Vec<_>>();
+/// Describes how the area of a window is calculated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IntegrationMethod {
+    pub rule: IntegrationRule,
+    pub baseline: Baseline,
+    /// Integrate the absolute value, so that areas below the baseline add to
+    /// the area instead of cancelling it.
+    pub absolute: bool,
+}
 
-    let mut yp = Vec::with_capacity(grid.len());
+/// Data of an external baseline, in the units it is displayed in.
+#[derive(Clone, Debug)]
+pub struct BaselineData {
+    pub xs: Vec<f64>,
+    pub ys: Vec<f64>,
+    pub units: (XUnit, YUnit),
+}
+
+/// Samples of the data and of the baseline within an integration window.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WindowSamples {
+    /// The (clamped) window borders and the data points in between.
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+    pub baseline: Vec<f64>,
+    /// Indices of `x` holding data points, as opposed to interpolated window
+    /// borders.
+    data: Range<usize>,
+}
 
-    for xi in grid.iter() {
-        if let Some((x0, y0, x1, y1)) = segments.iter().find(|(x0, _, x1, _)| xi >= x0 && xi < x1) {
-            yp.push(lininterp(*xi, *x0, *x1, *y0, *y1));
-            continue;
+impl WindowSamples {
+    /// Sample the data within a window. The data may be in any order of x
+    /// (see `ascending`). `external` is the data of `Baseline::External`.
+    pub fn new(
+        x: &[f64],
+        y: &[f64],
+        left: f64,
+        right: f64,
+        baseline: Baseline,
+        external: Option<(&[f64], &[f64])>,
+    ) -> Result<Self, IntegrationError> {
+        let (left, right) = (left.min(right), left.max(right));
+        let (x, y) = ascending(x, y);
+        let (x, y) = (x.as_ref(), y.as_ref());
+        let n = x.len();
+        if n <= 1 {
+            return Err(IntegrationError::TooFewPoints);
         }
-        // only applies if xi happens to be == the last value in xs
-        else if let Some((_, _, _, y1)) = segments.iter().last().filter(|(_, _, x1, _)| xi == x1)
-        {
-            yp.push(*y1);
-            continue;
+        if x[0] >= right || x[n - 1] <= left {
+            return Err(IntegrationError::WindowOutOfBounds);
         }
-        // applies if xi does not lie within the range of xs
-        else {
-            yp.push(f64::NAN)
+        // The chord needs data at both borders of the window.
+        if baseline == Baseline::Chord && (left < x[0] || x[n - 1] < right) {
+            return Err(IntegrationError::WindowOutOfBounds);
+        }
+
+        let (left, right) = (left.max(x[0]), right.min(x[n - 1]));
+        let inside: Vec<f64> = x
+            .iter()
+            .copied()
+            .filter(|x| left <= *x && *x <= right)
+            .collect();
+        let mut xw = Vec::with_capacity(inside.len() + 2);
+        if inside.first() != Some(&left) {
+            xw.push(left);
+        }
+        let first = xw.len();
+        xw.extend(inside.iter());
+        let last = xw.len();
+        if inside.last() != Some(&right) {
+            xw.push(right);
+        }
+        let yw = resample(x, y, &xw, Interpolation::Linear);
+
+        let (y0, y1) = (yw[0], yw[yw.len() - 1]);
+        let bw = match baseline {
+            Baseline::None => vec![0.0; xw.len()],
+            Baseline::Chord => xw
+                .iter()
+                .map(|x| lininterp(*x, left, right, y0, y1))
+                .collect(),
+            Baseline::WindowMinimum => {
+                let min = yw.iter().copied().fold(f64::INFINITY, f64::min);
+                vec![min; xw.len()]
+            }
+            Baseline::External(_) => {
+                let (xb, yb) = external.ok_or_else(|| {
+                    IntegrationError::BaselineUnavailable("no baseline data".to_string())
+                })?;
+                let bw = resample(xb, yb, &xw, Interpolation::Linear);
+                if bw.iter().any(|b| b.is_nan()) {
+                    return Err(IntegrationError::BaselineUnavailable(
+                        "baseline does not cover the window".to_string(),
+                    ));
+                }
+                bw
+            }
+        };
+        Ok(Self {
+            x: xw,
+            y: yw,
+            baseline: bw,
+            data: first..last,
+        })
+    }
+
+    /// The data with the baseline subtracted.
+    pub fn signal(&self) -> Vec<f64> {
+        self.y
+            .iter()
+            .zip(&self.baseline)
+            .map(|(y, b)| y - b)
+            .collect()
+    }
+
+    /// Integrate the data above the baseline. The area is not checked (see
+    /// `checked_area`).
+    pub fn area(&self, method: &IntegrationMethod) -> Result<f64, IntegrationError> {
+        let (x, s) = (&self.x, self.signal());
+        let area = match method.rule {
+            IntegrationRule::Trapezoid => x
+                .windows(2)
+                .zip(s.windows(2))
+                .map(|(x, s)| match method.absolute {
+                    true => abs_singletrapz(x[0], x[1], s[0], s[1]),
+                    false => singletrapz(x[0], x[1], s[0], s[1]),
+                })
+                .sum(),
+            IntegrationRule::Simpson => {
+                let s: Vec<f64> = match method.absolute {
+                    true => s.iter().map(|s| s.abs()).collect(),
+                    false => s,
+                };
+                let Range { start, end } = self.data;
+                if end - start < 2 {
+                    return Err(IntegrationError::TooFewPoints);
+                }
+                // The parts of the window between its borders and the
+                // outermost data points are integrated by the trapezoid rule.
+                let head: f64 = (0..start)
+                    .map(|i| singletrapz(x[i], x[i + 1], s[i], s[i + 1]))
+                    .sum();
+                let tail: f64 = (end - 1..x.len() - 1)
+                    .map(|i| singletrapz(x[i], x[i + 1], s[i], s[i + 1]))
+                    .sum();
+                head + simpson(&x[start..end], &s[start..end])? + tail
+            }
         };
+        Ok(area)
     }
-    yp
+}
+
+/// Composite Simpson's rule for evenly spaced, ascending x-values.
+fn simpson(x: &[f64], y: &[f64]) -> Result<f64, IntegrationError> {
+    let intervals = x.len() - 1;
+    let h = (x[intervals] - x[0]) / intervals as f64;
+    if x.windows(2).any(|w| ((w[1] - w[0]) - h).abs() > 1e-6 * h) {
+        return Err(IntegrationError::NonUniformGrid);
+    }
+    if intervals == 1 {
+        return Ok(singletrapz(x[0], x[1], y[0], y[1]));
+    }
+    // With an odd number of intervals, the last three are integrated by
+    // Simpson's 3/8 rule.
+    let (pairs, tail) = if intervals.is_multiple_of(2) {
+        (intervals, 0.0)
+    } else {
+        let i = intervals - 3;
+        (
+            i,
+            3.0 * h / 8.0 * (y[i] + 3.0 * y[i + 1] + 3.0 * y[i + 2] + y[i + 3]),
+        )
+    };
+    let area: f64 = (0..pairs)
+        .step_by(2)
+        .map(|i| h / 3.0 * (y[i] + 4.0 * y[i + 1] + y[i + 2]))
+        .sum();
+    Ok(area + tail)
+}
+
+/// Results of the integration of a window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowStatistics {
+    pub area: f64,
+    /// Maximum y-value within the window (after baseline subtraction).
+    pub peak_max: f64,
+    /// The x-value of the center of mass of the area.
+    pub centroid: f64,
+}
+
+/// Integrate the data within a window and determine the peak maximum and
+/// centroid as well. The centroid is always calculated with the trapezoid
+/// rule.
+pub fn window_statistics(
+    x: &[f64],
+    y: &[f64],
+    left: f64,
+    right: f64,
+    method: &IntegrationMethod,
+    external: Option<(&[f64], &[f64])>,
+) -> Result<WindowStatistics, IntegrationError> {
+    let samples = WindowSamples::new(x, y, left, right, method.baseline, external)?;
+    let area = samples.area(method).and_then(checked_area)?;
+
+    let weights: Vec<f64> = match method.absolute {
+        true => samples.signal().iter().map(|s| s.abs()).collect(),
+        false => samples.signal(),
+    };
+    let peak_max = weights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let (moment, weight) = samples
+        .x
+        .windows(2)
+        .zip(weights.windows(2))
+        .map(|(xs, ws)| {
+            (
+                singletrapz(xs[0], xs[1], xs[0] * ws[0], xs[1] * ws[1]),
+                singletrapz(xs[0], xs[1], ws[0], ws[1]),
+            )
+        })
+        .fold((0.0, 0.0), |(m, w), (dm, dw)| (m + dm, w + dw));
+    Ok(WindowStatistics {
+        area,
+        peak_max,
+        centroid: moment / weight,
+    })
 }
 
 /// Calculate area of single trapezoid.
@@ -483,7 +1044,407 @@ fn singletrapz(x0: f64, x1: f64, y0: f64, y1: f64) -> f64 {
     0.5 * f64::abs(x1 - x0) * (y1 + y0)
 }
 
+/// Calculate the area of the absolute value of a linearly interpolated
+/// segment, which may cross zero.
+fn abs_singletrapz(x0: f64, x1: f64, y0: f64, y1: f64) -> f64 {
+    if y0 * y1 < 0.0 {
+        let xc = x0 + (x1 - x0) * y0 / (y0 - y1);
+        0.5 * (xc - x0).abs() * y0.abs() + 0.5 * (x1 - xc).abs() * y1.abs()
+    } else {
+        singletrapz(x0, x1, y0.abs(), y1.abs())
+    }
+}
+
 /// Linearly interpolate y-value at position xp between two points (x0, y0) and (x1, y1).
 pub fn lininterp(xp: f64, x0: f64, x1: f64, y0: f64, y1: f64) -> f64 {
     (y1 * (xp - x0) + y0 * (x1 - xp)) / (x1 - x0)
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-12, "{a} != {b}");
+    }
+
+    /// Trapezoid rule with or without a chord baseline.
+    fn method(local_baseline: bool) -> IntegrationMethod {
+        IntegrationMethod {
+            rule: IntegrationRule::Trapezoid,
+            baseline: if local_baseline {
+                Baseline::Chord
+            } else {
+                Baseline::None
+            },
+            absolute: false,
+        }
+    }
+
+    fn area(
+        x: &[f64],
+        y: &[f64],
+        (left, right): (f64, f64),
+        method: &IntegrationMethod,
+        external: Option<(&[f64], &[f64])>,
+    ) -> f64 {
+        WindowSamples::new(x, y, left, right, method.baseline, external)
+            .and_then(|samples| samples.area(method))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_trapz() {
+        let xs = [0.0, 1.0, 2.0, 3.0];
+        let ys = [0.0, 2.0, 4.0, 6.0];
+        assert_close(area(&xs, &ys, (0.0, 3.0), &method(false), None), 9.0);
+        // Window borders between data points are interpolated.
+        assert_close(area(&xs, &ys, (0.5, 1.5), &method(false), None), 2.0);
+        // The order of the borders does not matter.
+        assert_close(area(&xs, &ys, (1.5, 0.5), &method(false), None), 2.0);
+        // A linear function has no area above its local baseline.
+        assert_close(area(&xs, &ys, (0.5, 2.5), &method(true), None), 0.0);
+    }
+
+    #[test]
+    fn test_trapz_first_and_last_segment() {
+        // Windows within the first and the last segment of the data, which
+        // were skipped or read past the end of the data before.
+        let xs = [0.0, 1.0, 2.0];
+        let ys = [1.0, 2.0, 3.0];
+        assert_close(area(&xs, &ys, (0.0, 0.5), &method(false), None), 0.625);
+        assert_close(area(&xs, &ys, (1.5, 2.0), &method(false), None), 1.375);
+        assert_close(area(&xs, &ys, (0.0, 2.0), &method(false), None), 4.0);
+    }
+
+    #[test]
+    fn test_trapz_window_partially_outside_data() {
+        let xs = [0.0, 1.0, 2.0];
+        let ys = [1.0, 1.0, 1.0];
+        assert_close(area(&xs, &ys, (-1.0, 1.5), &method(false), None), 1.5);
+        assert_close(area(&xs, &ys, (0.5, 5.0), &method(false), None), 1.5);
+        // The local baseline needs data at both borders.
+        assert_eq!(
+            WindowSamples::new(&xs, &ys, 0.5, 5.0, method(true).baseline, None),
+            Err(IntegrationError::WindowOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn test_trapz_descending_x() {
+        let xs = [3.0, 2.0, 1.0, 0.0];
+        let ys = [6.0, 4.0, 2.0, 0.0];
+        assert_close(area(&xs, &ys, (0.5, 1.5), &method(false), None), 2.0);
+        assert_close(area(&xs, &ys, (0.0, 3.0), &method(false), None), 9.0);
+        assert_close(area(&xs, &ys, (0.5, 2.5), &method(true), None), 0.0);
+        let stats = window_statistics(&xs, &ys, 0.0, 3.0, &method(false), None).unwrap();
+        let xs_asc = [0.0, 1.0, 2.0, 3.0];
+        let ys_asc = [0.0, 2.0, 4.0, 6.0];
+        assert_eq!(
+            stats,
+            window_statistics(&xs_asc, &ys_asc, 0.0, 3.0, &method(false), None).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_trapz_errors() {
+        assert_eq!(
+            WindowSamples::new(&[1.0], &[1.0], 0.0, 2.0, method(false).baseline, None),
+            Err(IntegrationError::TooFewPoints)
+        );
+        assert_eq!(
+            WindowSamples::new(&[], &[], 0.0, 2.0, method(false).baseline, None),
+            Err(IntegrationError::TooFewPoints)
+        );
+        let xs = [0.0, 1.0, 2.0];
+        assert_eq!(
+            WindowSamples::new(&xs, &xs, 3.0, 4.0, method(false).baseline, None),
+            Err(IntegrationError::WindowOutOfBounds)
+        );
+        assert_eq!(
+            WindowSamples::new(&xs, &xs, -2.0, 0.0, method(false).baseline, None),
+            Err(IntegrationError::WindowOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn test_checked_area() {
+        assert_eq!(checked_area(2.0), Ok(2.0));
+        assert_eq!(checked_area(0.0), Err(IntegrationError::ZeroArea));
+        assert_eq!(checked_area(1e-320), Err(IntegrationError::ZeroArea));
+        assert_eq!(checked_area(f64::NAN), Err(IntegrationError::NonFiniteArea));
+        assert_eq!(
+            checked_area(f64::INFINITY),
+            Err(IntegrationError::NonFiniteArea)
+        );
+        // A window in a region without signal cannot be used for scaling.
+        let xs = [0.0, 1.0, 2.0];
+        let ys = [0.0, 0.0, 0.0];
+        assert_eq!(
+            window_statistics(&xs, &ys, 0.0, 2.0, &method(false), None),
+            Err(IntegrationError::ZeroArea)
+        );
+    }
+
+    #[test]
+    fn test_window_statistics() {
+        // Triangle on top of a sloped baseline.
+        let xs = [0.0, 1.0, 2.0, 3.0, 4.0];
+        let ys = [0.0, 1.0, 4.0, 3.0, 4.0];
+        let stats = window_statistics(&xs, &ys, 0.0, 4.0, &method(true), None).unwrap();
+        assert!((stats.area - 2.0).abs() < 1e-12);
+        assert!((stats.peak_max - 2.0).abs() < 1e-12);
+        assert!((stats.centroid - 2.0).abs() < 1e-12);
+
+        let stats = window_statistics(&xs, &ys, 0.5, 1.5, &method(false), None).unwrap();
+        assert!((stats.area - 1.25).abs() < 1e-12);
+        assert!((stats.peak_max - 2.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_simpson() {
+        let xs: Vec<f64> = (0..=6).map(|i| i as f64 * 0.5).collect();
+        let ys: Vec<f64> = xs.iter().map(|x| x.powi(3) - x * x).collect();
+        let exact = |a: f64, b: f64| (b.powi(4) - a.powi(4)) / 4.0 - (b.powi(3) - a.powi(3)) / 3.0;
+        let simpson = IntegrationMethod {
+            rule: IntegrationRule::Simpson,
+            baseline: Baseline::None,
+            absolute: false,
+        };
+        // Simpson's rule is exact for cubic polynomials, for even and odd
+        // numbers of intervals.
+        assert_close(area(&xs, &ys, (0.0, 3.0), &simpson, None), exact(0.0, 3.0));
+        assert_close(area(&xs, &ys, (0.0, 2.5), &simpson, None), exact(0.0, 2.5));
+        assert!((area(&xs, &ys, (0.0, 3.0), &method(false), None) - exact(0.0, 3.0)).abs() > 1e-3);
+        // Borders between data points are integrated by the trapezoid rule.
+        let partial = area(&xs, &ys, (0.25, 3.0), &simpson, None);
+        let head = area(&xs, &ys, (0.25, 0.5), &method(false), None);
+        assert_close(partial, head + exact(0.5, 3.0));
+
+        let uneven = [0.0, 1.0, 3.0, 4.0];
+        assert_eq!(
+            WindowSamples::new(&uneven, &uneven, 0.0, 4.0, Baseline::None, None)
+                .and_then(|samples| samples.area(&simpson)),
+            Err(IntegrationError::NonUniformGrid)
+        );
+    }
+
+    #[test]
+    fn test_baselines() {
+        let xs = [0.0, 1.0, 2.0, 3.0, 4.0];
+        let ys = [2.0, 1.0, 3.0, 1.0, 2.0];
+        let with_baseline = |baseline| IntegrationMethod {
+            baseline,
+            ..method(false)
+        };
+        assert_close(area(&xs, &ys, (0.0, 4.0), &method(false), None), 7.0);
+        assert_close(area(&xs, &ys, (0.0, 4.0), &method(true), None), -1.0);
+        assert_close(
+            area(
+                &xs,
+                &ys,
+                (0.0, 4.0),
+                &with_baseline(Baseline::WindowMinimum),
+                None,
+            ),
+            3.0,
+        );
+        let external = ([0.0, 4.0], [0.5, 0.5]);
+        assert_close(
+            area(
+                &xs,
+                &ys,
+                (0.0, 4.0),
+                &with_baseline(Baseline::External(FileID(0))),
+                Some((&external.0, &external.1)),
+            ),
+            5.0,
+        );
+        // The external baseline has to cover the window.
+        let short = ([0.0, 2.0], [0.5, 0.5]);
+        assert!(matches!(
+            WindowSamples::new(
+                &xs,
+                &ys,
+                0.0,
+                4.0,
+                Baseline::External(FileID(0)),
+                Some((&short.0, &short.1))
+            ),
+            Err(IntegrationError::BaselineUnavailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_absolute_area() {
+        let xs = [0.0, 1.0, 2.0, 3.0];
+        let ys = [1.0, -1.0, -1.0, 1.0];
+        let absolute = IntegrationMethod {
+            absolute: true,
+            ..method(false)
+        };
+        assert_close(area(&xs, &ys, (0.0, 3.0), &method(false), None), -1.0);
+        // The segments crossing zero are split at the crossing.
+        assert_close(area(&xs, &ys, (0.0, 3.0), &absolute, None), 2.0);
+        let stats = window_statistics(&xs, &ys, 0.0, 3.0, &absolute, None).unwrap();
+        assert_close(stats.centroid, 1.5);
+        assert_close(stats.peak_max, 1.0);
+    }
+
+    #[test]
+    fn test_x_order() {
+        assert_eq!(x_order(&[0.0, 1.0, 2.0]), XOrder::Ascending);
+        assert_eq!(x_order(&[2.0, 1.0, 0.0]), XOrder::Descending);
+        assert_eq!(x_order(&[0.0, 1.0, 1.0]), XOrder::Unsorted);
+        assert_eq!(x_order(&[0.0, 2.0, 1.0]), XOrder::Unsorted);
+        assert_eq!(x_order(&[]), XOrder::Ascending);
+    }
+
+    #[test]
+    fn test_sort_and_merge() {
+        let (xs, ys) = sort_and_merge(
+            &[2.0, 0.0, 1.0, 2.0, f64::NAN, 0.0, 2.0],
+            &[1.0, 5.0, 3.0, 2.0, 7.0, 6.0, 6.0],
+        );
+        assert_eq!(xs, vec![0.0, 1.0, 2.0]);
+        assert_eq!(ys, vec![5.5, 3.0, 3.0]);
+    }
+
+    #[test]
+    fn test_file_colors() {
+        let file = |color| File {
+            data: UIParameter::new(Err(String::new())),
+            path: "file.csv".into(),
+            properties: FileProperties {
+                color,
+                ..Default::default()
+            },
+            recipe: None,
+        };
+        let custom = Color32::from_rgb(1, 2, 3);
+        let registry = (0..6)
+            .map(|i| (FileID(i), file((i == 1).then_some(custom))))
+            .collect();
+        let group = |ids: Vec<usize>, palette| {
+            Some(Group {
+                file_ids: ids.into_iter().map(FileID).collect(),
+                palette,
+                ..Default::default()
+            })
+        };
+        let mut groups = [const { None }; 10];
+        groups[0] = group(vec![0, 1], None);
+        groups[1] = group(vec![2, 3, 4], Some(Palette::Viridis));
+        groups[2] = group(vec![5], Some(Palette::OkabeIto));
+        let handler = FileHandler::new(groups, registry, FileID(6));
+
+        // Groups sharing a qualitative palette continue its cycle, and
+        // sequential palettes span their group.
+        let colors = handler.palette_colors();
+        assert_eq!(colors[&FileID(0)], Palette::OkabeIto.color(0, 2));
+        assert_eq!(colors[&FileID(1)], Palette::OkabeIto.color(1, 2));
+        assert_eq!(colors[&FileID(5)], Palette::OkabeIto.color(2, 1));
+        assert_eq!(colors[&FileID(2)], Palette::Viridis.color(0, 3));
+        assert_eq!(colors[&FileID(4)], Palette::Viridis.color(2, 3));
+        let indices: Vec<(FileID, usize)> = handler
+            .palette_indices()
+            .into_iter()
+            .map(|(_, fid, index)| (fid, index))
+            .collect();
+        assert_eq!(indices[5], (FileID(5), 2));
+
+        // Custom colors override the palette.
+        let colors = handler.file_colors();
+        assert_eq!(colors[&FileID(1)], custom);
+        assert_eq!(colors[&FileID(0)], Palette::OkabeIto.color(0, 2));
+    }
+
+    type Data = (Vec<f64>, Vec<f64>);
+
+    /// Strictly ascending x-values with arbitrary y-values.
+    fn sorted_data() -> impl Strategy<Value = Data> {
+        prop::collection::vec((0.01..10.0f64, -100.0..100.0f64), 2..50).prop_map(|steps| {
+            let mut x = 0.0;
+            steps
+                .into_iter()
+                .map(|(dx, y)| {
+                    x += dx;
+                    (x, y)
+                })
+                .unzip()
+        })
+    }
+
+    /// Sorted data, together with the same points in random order and a
+    /// window within the data.
+    fn shuffled_data() -> impl Strategy<Value = (Data, Data, (f64, f64))> {
+        sorted_data().prop_flat_map(|(xs, ys)| {
+            let (xmin, xmax) = (xs[0], xs[xs.len() - 1]);
+            let points: Vec<(f64, f64)> = xs.iter().copied().zip(ys.iter().copied()).collect();
+            (
+                Just((xs, ys)),
+                Just(points)
+                    .prop_shuffle()
+                    .prop_map(|p| p.into_iter().unzip()),
+                (xmin..=xmax, xmin..=xmax),
+            )
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_trapz_independent_of_order(
+            ((xs, ys), (xs_shuffled, ys_shuffled), (left, right)) in shuffled_data(),
+            local_baseline in any::<bool>(),
+        ) {
+            let method = method(local_baseline);
+            let area = |x: &[f64], y: &[f64]| {
+                WindowSamples::new(x, y, left, right, method.baseline, None)
+                    .and_then(|samples| samples.area(&method))
+            };
+            let reference = area(&xs, &ys);
+            let xs_rev: Vec<f64> = xs.iter().rev().copied().collect();
+            let ys_rev: Vec<f64> = ys.iter().rev().copied().collect();
+            prop_assert_eq!(&area(&xs_rev, &ys_rev), &reference);
+            prop_assert_eq!(&area(&xs_shuffled, &ys_shuffled), &reference);
+        }
+
+        #[test]
+        fn prop_resample_independent_of_order(
+            ((xs, ys), (xs_shuffled, ys_shuffled), (left, right)) in shuffled_data(),
+        ) {
+            let grid = [left, 0.5 * (left + right), right];
+            for method in Interpolation::ALL {
+                prop_assert_eq!(
+                    resample(&xs_shuffled, &ys_shuffled, &grid, method),
+                    resample(&xs, &ys, &grid, method)
+                );
+            }
+        }
+
+        #[test]
+        fn prop_duplicates_are_averaged(
+            (xs, ys) in sorted_data(),
+            repeats in prop::collection::vec(1..4usize, 50),
+        ) {
+            // Repeat every point, shifting the y-values symmetrically around
+            // the original value, so that their mean is the original value.
+            let (xs_repeated, ys_repeated): (Vec<f64>, Vec<f64>) = xs
+                .iter()
+                .zip(&ys)
+                .zip(&repeats)
+                .flat_map(|((x, y), n)| {
+                    (0..*n).map(move |i| (*x, y + i as f64 - (*n - 1) as f64 / 2.0))
+                })
+                .rev()
+                .unzip();
+            let (xs_merged, ys_merged) = sort_and_merge(&xs_repeated, &ys_repeated);
+            prop_assert_eq!(&xs_merged, &xs);
+            for (merged, y) in ys_merged.iter().zip(&ys) {
+                prop_assert!((merged - y).abs() < 1e-9);
+            }
+        }
+    }
+}
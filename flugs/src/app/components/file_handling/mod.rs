@@ -1,15 +1,30 @@
+mod arithmetic;
+mod line_style;
 mod logic;
+mod normalise;
+mod palette;
+mod resample;
 mod ui;
 
 use std::collections::HashMap;
 use std::path::PathBuf;
 
 use crate::app::DynRequestSender;
-use crate::backend_state::PlotData;
+use crate::backend_state::{PlotData, XUnit, YUnit};
 use app_core::frontend::UIParameter;
+use arithmetic::ArithmeticForm;
+pub use arithmetic::{NOT_EVALUATED_MSG, Operation, Recipe};
 use egui::Color32;
-pub use logic::parse_csv;
+pub use line_style::{LineStyle, Marker};
+pub use logic::{
+    Baseline, BaselineData, IntegrationError, IntegrationMethod, IntegrationRule, WindowStatistics,
+    parse_csv,
+};
+pub use normalise::{Normalisation, NormalisationMode};
+pub use palette::Palette;
+pub use resample::{Interpolation, resample, uniform_grid};
 use serde::{Deserialize, Serialize};
+pub use ui::unit_combobox;
 
 #[derive(
     Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
@@ -22,7 +37,11 @@ pub struct FileHandler {
     pub registry: HashMap<FileID, File>,
     next_id: FileID,
     group_name_buffer: [String; 10],
+    /// The colors of the palettes of the files in the current frame, see
+    /// `FileHandler::palette_colors`.
+    palette_color_buffer: HashMap<FileID, Color32>,
     active_element: ActiveElement,
+    arithmetic_form: ArithmeticForm,
 }
 
 #[derive(Clone, Debug)]
@@ -30,6 +49,9 @@ pub struct File {
     pub data: UIParameter<Result<PlotData, String>>,
     pub path: PathBuf,
     pub properties: FileProperties,
+    /// Set if the file was derived from other files, rather than loaded
+    /// from disk.
+    pub recipe: Option<Recipe>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -54,6 +76,27 @@ pub struct FileProperties {
     // default values other than Default::default()
     #[serde(default = "serde_default_usize_1")]
     pub selected_y_column: usize,
+    #[serde(default)]
+    pub normalisation: Normalisation,
+    #[serde(default)]
+    pub units: UnitSettings,
+    /// Plot the file against the secondary (right) y-axis.
+    #[serde(default)]
+    pub secondary_y: bool,
+    #[serde(default)]
+    pub line_style: LineStyle,
+    #[serde(default)]
+    pub marker: Marker,
+}
+
+/// Units of a file. If not set (or unknown), the units determined by the
+/// parser are used and the data is displayed as is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct UnitSettings {
+    pub data_x: Option<XUnit>,
+    pub data_y: Option<YUnit>,
+    pub display_x: Option<XUnit>,
+    pub display_y: Option<YUnit>,
 }
 
 fn serde_default_usize_1() -> usize {
@@ -65,6 +108,9 @@ pub struct Annotation {
     pub x: f32,
     pub y: f32,
     pub text: String,
+    /// Whether a vertical line is drawn at `x`, e.g. to mark a band.
+    #[serde(default)]
+    pub line: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -72,6 +118,11 @@ pub struct Group {
     pub file_ids: Vec<FileID>,
     pub is_plotted: bool,
     pub name: String,
+    /// The palette of the files without a custom color, `None` for the
+    /// default palette (or the color cycle of the export style, at the same
+    /// positions).
+    #[serde(default)]
+    pub palette: Option<Palette>,
 }
 
 #[derive(Debug)]
@@ -91,7 +142,9 @@ impl FileHandler {
             registry,
             next_id,
             group_name_buffer: [const { String::new() }; 10],
+            palette_color_buffer: HashMap::new(),
             active_element: ActiveElement::Group(0),
+            arithmetic_form: Default::default(),
         }
     }
 }
@@ -108,6 +161,17 @@ impl File {
             data: csv_data,
             path,
             properties,
+            recipe: None,
+        }
+    }
+    /// Create a file whose data is computed from other files. The data is
+    /// filled in once the recipe is evaluated.
+    pub fn derived(path: PathBuf, properties: FileProperties, recipe: Recipe) -> Self {
+        File {
+            data: UIParameter::new(Err(NOT_EVALUATED_MSG.to_string())),
+            path,
+            properties,
+            recipe: Some(recipe),
         }
     }
     pub fn file_name(&self) -> &str {
@@ -116,6 +180,14 @@ impl File {
             .and_then(|name| name.to_str())
             .unwrap_or("unreadable filename")
     }
+    /// The alias of the file, or the file name if no alias is set.
+    pub fn display_name(&self) -> &str {
+        if self.properties.alias.is_empty() {
+            self.file_name()
+        } else {
+            &self.properties.alias
+        }
+    }
 }
 
 impl Default for FileProperties {
@@ -130,6 +202,11 @@ impl Default for FileProperties {
             annotations: Vec::new(),
             selected_x_column: 0,
             selected_y_column: 1,
+            normalisation: Normalisation::default(),
+            units: UnitSettings::default(),
+            secondary_y: false,
+            line_style: LineStyle::default(),
+            marker: Marker::default(),
         }
     }
 }
@@ -146,6 +223,7 @@ impl Default for Group {
             file_ids: Default::default(),
             is_plotted: true,
             name: Default::default(),
+            palette: None,
         }
     }
 }
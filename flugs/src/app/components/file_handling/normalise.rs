@@ -0,0 +1,221 @@
+//! Normalisation of spectra.
+//!
+//! A normalisation is an affine map `y * scale + shift` that is computed from
+//! the data of a file and applied before the offsets and scaling set in its
+//! `FileProperties`. Because only the mode and window are stored, the
+//! normalisation follows along if the data changes.
+
+use serde::{Deserialize, Serialize};
+
+use super::resample::{Interpolation, resample};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NormalisationMode {
+    #[default]
+    None,
+    /// Divide by the maximum y-value.
+    MaxInWindow,
+    /// Divide by the y-value at a reference point.
+    ReferencePoint,
+    /// Divide by the vector (L2) norm.
+    VectorNorm,
+    /// Map the y-values to [0, 1].
+    MinMax,
+    /// Standard normal variate: subtract the mean, divide by the standard
+    /// deviation.
+    Snv,
+}
+
+impl NormalisationMode {
+    pub const ALL: [NormalisationMode; 6] = [
+        NormalisationMode::None,
+        NormalisationMode::MaxInWindow,
+        NormalisationMode::ReferencePoint,
+        NormalisationMode::VectorNorm,
+        NormalisationMode::MinMax,
+        NormalisationMode::Snv,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NormalisationMode::None => "None",
+            NormalisationMode::MaxInWindow => "Max. Peak Height",
+            NormalisationMode::ReferencePoint => "Reference Point",
+            NormalisationMode::VectorNorm => "Vector (L2) Norm",
+            NormalisationMode::MinMax => "Min-Max",
+            NormalisationMode::Snv => "Standard Normal Variate",
+        }
+    }
+
+    /// Whether the statistics of this mode can be restricted to a window.
+    pub fn uses_window(&self) -> bool {
+        !matches!(
+            self,
+            NormalisationMode::None | NormalisationMode::ReferencePoint
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Normalisation {
+    pub mode: NormalisationMode,
+    /// Only data points within this x-window are taken into account. If not
+    /// set, all data points are used.
+    #[serde(default)]
+    pub window: Option<(f64, f64)>,
+    /// The x-value used by `NormalisationMode::ReferencePoint`.
+    #[serde(default)]
+    pub reference_x: f64,
+}
+
+impl Normalisation {
+    /// Calculate `(scale, shift)` such that `y * scale + shift` is the
+    /// normalised data.
+    pub fn coefficients(&self, xs: &[f64], ys: &[f64]) -> Result<(f64, f64), String> {
+        let ys_window: Vec<f64> = xs
+            .iter()
+            .zip(ys)
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .filter(|(x, _)| match self.window {
+                Some((a, b)) => (a.min(b)..=a.max(b)).contains(*x),
+                None => true,
+            })
+            .map(|(_, y)| *y)
+            .collect();
+        if self.mode.uses_window() && ys_window.is_empty() {
+            return Err("no data points within the normalisation window".to_string());
+        }
+
+        let max = || ys_window.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let min = || ys_window.iter().copied().fold(f64::INFINITY, f64::min);
+
+        let (divisor, subtrahend) = match self.mode {
+            NormalisationMode::None => (1.0, 0.0),
+            NormalisationMode::MaxInWindow => (max(), 0.0),
+            NormalisationMode::ReferencePoint => {
+                let yref = resample(xs, ys, &[self.reference_x], Interpolation::Linear)[0];
+                if yref.is_nan() {
+                    return Err(format!(
+                        "reference point {} is outside of the data",
+                        self.reference_x
+                    ));
+                }
+                (yref, 0.0)
+            }
+            NormalisationMode::VectorNorm => {
+                (ys_window.iter().map(|y| y * y).sum::<f64>().sqrt(), 0.0)
+            }
+            NormalisationMode::MinMax => (max() - min(), min()),
+            NormalisationMode::Snv => {
+                if ys_window.len() < 2 {
+                    return Err("at least two data points are needed for SNV".to_string());
+                }
+                let n = ys_window.len() as f64;
+                let mean = ys_window.iter().sum::<f64>() / n;
+                let variance =
+                    ys_window.iter().map(|y| (y - mean).powi(2)).sum::<f64>() / (n - 1.0);
+                (variance.sqrt(), mean)
+            }
+        };
+
+        if divisor == 0.0 || !divisor.is_finite() {
+            return Err(format!(
+                "cannot normalise by {}, divisor is {divisor}",
+                self.mode.name()
+            ));
+        }
+        Ok((1.0 / divisor, -subtrahend / divisor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XS: [f64; 5] = [0.0, 1.0, 2.0, 3.0, 4.0];
+    const YS: [f64; 5] = [1.0, 3.0, 2.0, 5.0, 4.0];
+
+    fn normalised(normalisation: Normalisation) -> Vec<f64> {
+        let (scale, shift) = normalisation.coefficients(&XS, &YS).unwrap();
+        YS.iter().map(|y| y * scale + shift).collect()
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-12, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_max_in_window() {
+        let ys = normalised(Normalisation {
+            mode: NormalisationMode::MaxInWindow,
+            window: Some((2.5, 0.5)),
+            reference_x: 0.0,
+        });
+        assert_close(ys[1], 1.0);
+        assert_close(ys[3], 5.0 / 3.0);
+    }
+
+    #[test]
+    fn test_reference_point() {
+        let ys = normalised(Normalisation {
+            mode: NormalisationMode::ReferencePoint,
+            window: None,
+            reference_x: 1.5,
+        });
+        assert_close(ys[0], 0.4);
+        let outside = Normalisation {
+            mode: NormalisationMode::ReferencePoint,
+            window: None,
+            reference_x: 5.0,
+        };
+        assert!(outside.coefficients(&XS, &YS).is_err());
+    }
+
+    #[test]
+    fn test_vector_norm() {
+        let ys = normalised(Normalisation {
+            mode: NormalisationMode::VectorNorm,
+            ..Default::default()
+        });
+        assert_close(ys.iter().map(|y| y * y).sum::<f64>(), 1.0);
+    }
+
+    #[test]
+    fn test_min_max() {
+        let ys = normalised(Normalisation {
+            mode: NormalisationMode::MinMax,
+            ..Default::default()
+        });
+        assert_close(ys[0], 0.0);
+        assert_close(ys[3], 1.0);
+        assert_close(ys[2], 0.25);
+    }
+
+    #[test]
+    fn test_snv() {
+        let ys = normalised(Normalisation {
+            mode: NormalisationMode::Snv,
+            ..Default::default()
+        });
+        let mean = ys.iter().sum::<f64>() / 5.0;
+        let variance = ys.iter().map(|y| (y - mean).powi(2)).sum::<f64>() / 4.0;
+        assert_close(mean, 0.0);
+        assert_close(variance, 1.0);
+    }
+
+    #[test]
+    fn test_degenerate_data() {
+        let constant = [2.0; 5];
+        let normalisation = Normalisation {
+            mode: NormalisationMode::MinMax,
+            ..Default::default()
+        };
+        assert!(normalisation.coefficients(&XS, &constant).is_err());
+        let empty_window = Normalisation {
+            mode: NormalisationMode::MaxInWindow,
+            window: Some((10.0, 11.0)),
+            reference_x: 0.0,
+        };
+        assert!(empty_window.coefficients(&XS, &YS).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+//! Palettes of the files of a group, in the plotter and in exported figures.
+//! Qualitative palettes cycle through distinct colors, sequential palettes
+//! color the files of a group in order, e.g. for a temperature series.
+
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+
+/// Okabe and Ito, distinguishable with all common forms of color blindness.
+const OKABE_ITO: [u32; 8] = [
+    0x0072b2, 0xe69f00, 0x009e73, 0xcc79a7, 0x56b4e9, 0xd55e00, 0xf0e442, 0x000000,
+];
+/// Paul Tol's bright and muted schemes, which are color-blind safe as well.
+const TOL_BRIGHT: [u32; 7] = [
+    0x4477aa, 0xee6677, 0x228833, 0xccbb44, 0x66ccee, 0xaa3377, 0xbbbbbb,
+];
+const TOL_MUTED: [u32; 9] = [
+    0x332288, 0x88ccee, 0x44aa99, 0x117733, 0x999933, 0xddcc77, 0xcc6677, 0x882255, 0xaa4499,
+];
+/// Equidistant samples of the colormaps of matplotlib, which are
+/// interpolated linearly.
+const VIRIDIS: [u32; 9] = [
+    0x440154, 0x472c7a, 0x3b518b, 0x2c718e, 0x21918c, 0x28ae80, 0x5ec962, 0xaddc30, 0xfde725,
+];
+const PLASMA: [u32; 9] = [
+    0x0d0887, 0x4c02a1, 0x7e03a8, 0xa92395, 0xcc4778, 0xe56b5d, 0xf89540, 0xfdc328, 0xf0f921,
+];
+const MAGMA: [u32; 9] = [
+    0x000004, 0x1c1044, 0x4f127b, 0x812581, 0xb5367a, 0xe55064, 0xfb8761, 0xfec287, 0xfcfdbf,
+];
+
+/// The part of a colormap used for a series. The bright end is left out, as
+/// it is hard to see on a white background.
+const COLORMAP_RANGE: f32 = 0.9;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Palette {
+    #[default]
+    OkabeIto,
+    TolBright,
+    TolMuted,
+    Viridis,
+    Plasma,
+    Magma,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 6] = [
+        Palette::OkabeIto,
+        Palette::TolBright,
+        Palette::TolMuted,
+        Palette::Viridis,
+        Palette::Plasma,
+        Palette::Magma,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::OkabeIto => "Okabe-Ito",
+            Palette::TolBright => "Tol Bright",
+            Palette::TolMuted => "Tol Muted",
+            Palette::Viridis => "Viridis",
+            Palette::Plasma => "Plasma",
+            Palette::Magma => "Magma",
+        }
+    }
+
+    /// Whether the palette is a colormap, which spans the files of a group
+    /// instead of cycling through its colors.
+    pub fn is_sequential(&self) -> bool {
+        matches!(self, Palette::Viridis | Palette::Plasma | Palette::Magma)
+    }
+
+    fn colors(&self) -> &'static [u32] {
+        match self {
+            Palette::OkabeIto => &OKABE_ITO,
+            Palette::TolBright => &TOL_BRIGHT,
+            Palette::TolMuted => &TOL_MUTED,
+            Palette::Viridis => &VIRIDIS,
+            Palette::Plasma => &PLASMA,
+            Palette::Magma => &MAGMA,
+        }
+    }
+
+    /// The colors of the palette as `#rrggbb`, e.g. for the color cycle of
+    /// an export style.
+    pub fn hex_colors(&self) -> Vec<String> {
+        self.colors().iter().map(|c| format!("#{c:06x}")).collect()
+    }
+
+    /// The color of the `index`-th of `count` files.
+    pub fn color(&self, index: usize, count: usize) -> Color32 {
+        let colors = self.colors();
+        if !self.is_sequential() {
+            return rgb(colors[index % colors.len()]);
+        }
+        let t = match count {
+            0 | 1 => 0.0,
+            _ => COLORMAP_RANGE * index.min(count - 1) as f32 / (count - 1) as f32,
+        };
+        let position = t * (colors.len() - 1) as f32;
+        let i = (position.floor() as usize).min(colors.len() - 2);
+        rgb(colors[i]).lerp_to_gamma(rgb(colors[i + 1]), position - i as f32)
+    }
+}
+
+fn rgb(hex: u32) -> Color32 {
+    let [_, r, g, b] = hex.to_be_bytes();
+    Color32::from_rgb(r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_colors() {
+        // Qualitative palettes cycle.
+        let palette = Palette::OkabeIto;
+        assert_eq!(palette.color(0, 3), Color32::from_rgb(0x00, 0x72, 0xb2));
+        assert_eq!(palette.color(8, 3), palette.color(0, 3));
+        assert_eq!(palette.hex_colors()[0], "#0072b2");
+        assert_eq!(palette.hex_colors()[7], "#000000");
+
+        // Sequential palettes span the series, leaving out the bright end.
+        let palette = Palette::Viridis;
+        assert_eq!(palette.color(0, 5), Color32::from_rgb(0x44, 0x01, 0x54));
+        assert_eq!(palette.color(0, 1), palette.color(0, 5));
+        assert_eq!(palette.color(2, 3), palette.color(4, 5));
+        assert!((0xad..0xfd).contains(&palette.color(4, 5).r()));
+    }
+}
@@ -0,0 +1,255 @@
+//! Resampling of xy-data onto arbitrary or uniform x-grids.
+//!
+//! The input data may be in any order of x (see `logic::ascending`). Grid
+//! points outside of the x-range of the data yield NaN.
+
+use serde::{Deserialize, Serialize};
+
+/// Interpolation method used for resampling.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    /// Natural cubic spline (second derivative vanishes at the end points).
+    CubicSpline,
+    /// Piecewise cubic Hermite interpolation, which preserves monotonicity
+    /// and does not overshoot.
+    Pchip,
+}
+
+impl Interpolation {
+    pub const ALL: [Interpolation; 3] = [
+        Interpolation::Linear,
+        Interpolation::CubicSpline,
+        Interpolation::Pchip,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Interpolation::Linear => "Linear",
+            Interpolation::CubicSpline => "Cubic Spline",
+            Interpolation::Pchip => "PCHIP",
+        }
+    }
+}
+
+/// Interpolate the data `xs`, `ys` onto the x-values in `grid`.
+pub fn resample(xs: &[f64], ys: &[f64], grid: &[f64], method: Interpolation) -> Vec<f64> {
+    let (xs, ys) = super::logic::ascending(xs, ys);
+    if xs.len() < 2 {
+        return vec![f64::NAN; grid.len()];
+    }
+
+    // Derivatives needed for the cubic methods.
+    let derivatives = match method {
+        Interpolation::Linear => None,
+        Interpolation::CubicSpline => Some(spline_second_derivatives(&xs, &ys)),
+        Interpolation::Pchip => Some(pchip_slopes(&xs, &ys)),
+    };
+
+    let mut yp = Vec::with_capacity(grid.len());
+    let mut hint = 0;
+    for xi in grid.iter() {
+        let Some(i) = segment_index(&xs, *xi, hint) else {
+            yp.push(f64::NAN);
+            continue;
+        };
+        hint = i;
+        let (x0, x1, y0, y1) = (xs[i], xs[i + 1], ys[i], ys[i + 1]);
+        let y = match (method, &derivatives) {
+            (Interpolation::CubicSpline, Some(m)) => {
+                let h = x1 - x0;
+                let (a, b) = ((x1 - xi) / h, (xi - x0) / h);
+                a * y0
+                    + b * y1
+                    + ((a.powi(3) - a) * m[i] + (b.powi(3) - b) * m[i + 1]) * h * h / 6.0
+            }
+            (Interpolation::Pchip, Some(d)) => {
+                let h = x1 - x0;
+                let t = (xi - x0) / h;
+                let (t2, t3) = (t * t, t * t * t);
+                (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+                    + (t3 - 2.0 * t2 + t) * h * d[i]
+                    + (-2.0 * t3 + 3.0 * t2) * y1
+                    + (t3 - t2) * h * d[i + 1]
+            }
+            _ => super::logic::lininterp(*xi, x0, x1, y0, y1),
+        };
+        yp.push(y);
+    }
+    yp
+}
+
+/// Create `n` evenly spaced grid points from `start` to `stop` (inclusive).
+pub fn uniform_grid(start: f64, stop: f64, n: usize) -> Vec<f64> {
+    match n {
+        0 => Vec::new(),
+        1 => vec![start],
+        _ => {
+            let step = (stop - start) / (n - 1) as f64;
+            (0..n).map(|i| start + i as f64 * step).collect()
+        }
+    }
+}
+
+/// Index `i` of the segment `xs[i]..=xs[i + 1]` containing `xi`.
+///
+/// `hint` is the segment of the previous grid point: for ascending grids the
+/// segments are found by merging, otherwise by binary search.
+fn segment_index(xs: &[f64], xi: f64, hint: usize) -> Option<usize> {
+    let n = xs.len();
+    if n < 2 || !(xs[0] <= xi && xi <= xs[n - 1]) {
+        return None;
+    }
+    for i in [hint, hint + 1] {
+        if i + 1 < n && xs[i] <= xi && xi <= xs[i + 1] {
+            return Some(i);
+        }
+    }
+    // Index of the first x-value larger than xi.
+    let i = xs.partition_point(|x| *x <= xi);
+    Some(i.saturating_sub(1).min(n - 2))
+}
+
+/// Second derivatives of the natural cubic spline through the data points,
+/// calculated by solving the tridiagonal system with the Thomas algorithm.
+fn spline_second_derivatives(xs: &[f64], ys: &[f64]) -> Vec<f64> {
+    let n = xs.len();
+    let mut m = vec![0.0; n];
+    if n < 3 {
+        return m;
+    }
+    // Forward sweep.
+    let mut c_prime = vec![0.0; n];
+    let mut d_prime = vec![0.0; n];
+    for i in 1..n - 1 {
+        let (h0, h1) = (xs[i] - xs[i - 1], xs[i + 1] - xs[i]);
+        let a = h0 / 6.0;
+        let b = (h0 + h1) / 3.0;
+        let c = h1 / 6.0;
+        let d = (ys[i + 1] - ys[i]) / h1 - (ys[i] - ys[i - 1]) / h0;
+        let denom = b - a * c_prime[i - 1];
+        c_prime[i] = c / denom;
+        d_prime[i] = (d - a * d_prime[i - 1]) / denom;
+    }
+    // Back substitution.
+    for i in (1..n - 1).rev() {
+        m[i] = d_prime[i] - c_prime[i] * m[i + 1];
+    }
+    m
+}
+
+/// Slopes at the data points for PCHIP (Fritsch–Carlson method).
+fn pchip_slopes(xs: &[f64], ys: &[f64]) -> Vec<f64> {
+    let n = xs.len();
+    let h: Vec<f64> = xs.windows(2).map(|w| w[1] - w[0]).collect();
+    let delta: Vec<f64> = ys
+        .windows(2)
+        .zip(h.iter())
+        .map(|(w, h)| (w[1] - w[0]) / h)
+        .collect();
+    if n == 2 {
+        return vec![delta[0]; 2];
+    }
+
+    let mut d = vec![0.0; n];
+    for i in 1..n - 1 {
+        if delta[i - 1] * delta[i] > 0.0 {
+            // Weighted harmonic mean of the neighbouring secants.
+            let w1 = 2.0 * h[i] + h[i - 1];
+            let w2 = h[i] + 2.0 * h[i - 1];
+            d[i] = (w1 + w2) / (w1 / delta[i - 1] + w2 / delta[i]);
+        }
+    }
+    d[0] = pchip_end_slope(h[0], h[1], delta[0], delta[1]);
+    d[n - 1] = pchip_end_slope(h[n - 2], h[n - 3], delta[n - 2], delta[n - 3]);
+    d
+}
+
+/// One-sided, shape-preserving three-point estimate of the slope at an end
+/// point.
+fn pchip_end_slope(h0: f64, h1: f64, delta0: f64, delta1: f64) -> f64 {
+    let d = ((2.0 * h0 + h1) * delta0 - h0 * delta1) / (h0 + h1);
+    if delta0 == 0.0 || d.signum() != delta0.signum() {
+        0.0
+    } else if delta0.signum() != delta1.signum() && d.abs() > 3.0 * delta0.abs() {
+        3.0 * delta0
+    } else {
+        d
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b) {
+            assert!((a - b).abs() < 1e-9, "{a} != {b}");
+        }
+    }
+
+    #[test]
+    fn test_linear() {
+        let xs = [0.0, 1.0, 2.0, 4.0];
+        let ys = [0.0, 2.0, 2.0, 6.0];
+        let grid = [-1.0, 0.0, 0.5, 2.0, 3.0, 4.0, 5.0];
+        let yp = resample(&xs, &ys, &grid, Interpolation::Linear);
+        assert!(yp[0].is_nan() && yp[6].is_nan());
+        assert_close(&yp[1..6], &[0.0, 1.0, 2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn test_descending_data_and_grid() {
+        let xs = [4.0, 2.0, 1.0, 0.0];
+        let ys = [6.0, 2.0, 2.0, 0.0];
+        let grid = [4.0, 3.0, 0.5];
+        for method in Interpolation::ALL {
+            let yp = resample(&xs, &ys, &grid, method);
+            assert_close(&yp[..1], &[6.0]);
+        }
+        let yp = resample(&xs, &ys, &grid, Interpolation::Linear);
+        assert_close(&yp, &[6.0, 4.0, 1.0]);
+    }
+
+    #[test]
+    fn test_spline_reproduces_linear_data() {
+        let xs = [0.0, 1.0, 3.0, 4.0, 7.0];
+        let ys: Vec<f64> = xs.iter().map(|x| 2.0 * x - 1.0).collect();
+        let grid = uniform_grid(0.0, 7.0, 15);
+        let expected: Vec<f64> = grid.iter().map(|x| 2.0 * x - 1.0).collect();
+        for method in Interpolation::ALL {
+            assert_close(&resample(&xs, &ys, &grid, method), &expected);
+        }
+    }
+
+    #[test]
+    fn test_spline_passes_through_data_points() {
+        let xs = [0.0, 1.0, 2.0, 3.0, 4.0];
+        let ys = [0.0, 1.0, 0.0, 1.0, 0.0];
+        for method in [Interpolation::CubicSpline, Interpolation::Pchip] {
+            assert_close(&resample(&xs, &ys, &xs, method), &ys);
+        }
+    }
+
+    #[test]
+    fn test_pchip_does_not_overshoot() {
+        let xs = [0.0, 1.0, 2.0, 3.0, 4.0];
+        let ys = [0.0, 0.0, 1.0, 1.0, 1.0];
+        let grid = uniform_grid(0.0, 4.0, 81);
+        let yp = resample(&xs, &ys, &grid, Interpolation::Pchip);
+        assert!(yp.iter().all(|y| (0.0..=1.0).contains(y)));
+        assert!(yp.windows(2).all(|w| w[1] >= w[0]));
+        // The natural spline does overshoot on this data.
+        let yp = resample(&xs, &ys, &grid, Interpolation::CubicSpline);
+        assert!(yp.iter().any(|y| *y < 0.0 || *y > 1.0));
+    }
+
+    #[test]
+    fn test_uniform_grid() {
+        assert_close(&uniform_grid(1.0, 2.0, 5), &[1.0, 1.25, 1.5, 1.75, 2.0]);
+        assert_close(&uniform_grid(2.0, 1.0, 3), &[2.0, 1.5, 1.0]);
+        assert!(uniform_grid(0.0, 1.0, 0).is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Write;
+use std::{collections::HashMap, fmt::Write};
 
 use egui::{Color32, FontId, text::LayoutJob};
 
@@ -7,13 +7,17 @@ use crate::{
     app::{
         DynRequestSender,
         events::{
-            CloneFile, CopyFile, EventQueue, LocateFile, MoveFile, RefreshCache, RemoveFile,
-            RemoveGroup, ResetScaling,
+            AddDerivedFile, CloneFile, CopyFile, EvaluateRecipe, EventQueue, LocateFile, MoveFile,
+            RefreshCache, RemoveFile, RemoveGroup, ResetScaling,
         },
     },
+    backend_state::Unit,
 };
 
-use super::{ActiveElement, File, FileHandler, FileID};
+use super::{
+    ActiveElement, File, FileHandler, FileID, Interpolation, LineStyle, Marker, Normalisation,
+    NormalisationMode, Operation, Palette, Recipe,
+};
 
 impl FileHandler {
     pub(crate) fn render(
@@ -32,6 +36,9 @@ impl FileHandler {
             }
         }
 
+        // The palettes are resolved once per frame for all files.
+        self.palette_color_buffer = self.palette_colors();
+
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
             ui.heading("Groups and Files")
         });
@@ -155,12 +162,137 @@ impl FileHandler {
             let lab = ui.label("rename:");
             ui.text_edit_singleline(&mut grp.name).labelled_by(lab.id);
         });
+        ui.horizontal(|ui| {
+            // Assigns all files of the group, which can still be changed
+            // for each file.
+            let files = || grp.file_ids.iter().filter_map(|fid| self.registry.get(fid));
+            let mut secondary =
+                files().count() > 0 && files().all(|file| file.properties.secondary_y);
+            if ui
+                .checkbox(&mut secondary, "plot on right y-axis")
+                .changed()
+            {
+                for fid in grp.file_ids.iter() {
+                    if let Some(file) = self.registry.get_mut(fid) {
+                        file.properties.secondary_y = secondary;
+                    }
+                }
+            }
+        });
+        egui::ComboBox::new((gid, "palette"), "Palette")
+            .selected_text(grp.palette.map_or("Default", |palette| palette.name()))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut grp.palette, None, "Default")
+                    .on_hover_text("Okabe-Ito, or the color cycle of the export style");
+                for palette in Palette::ALL {
+                    ui.selectable_value(&mut grp.palette, Some(palette), palette.name());
+                }
+            });
         ui.horizontal(|ui| {
             ui.label("delete group:");
             if ui.small_button("🗑").clicked() {
                 event_queue.queue_event(Box::new(RemoveGroup::new(gid)));
             }
         });
+        self.arithmetic_settings(gid, event_queue, ui);
+    }
+
+    fn arithmetic_settings(
+        &mut self,
+        gid: usize,
+        event_queue: &mut EventQueue<EguiApp>,
+        ui: &mut egui::Ui,
+    ) {
+        let Some(grp) = &self.groups[gid] else {
+            return;
+        };
+        let form = &mut self.arithmetic_form;
+        let registry = &self.registry;
+
+        ui.separator();
+        ui.heading("Spectral Arithmetic");
+
+        egui::ComboBox::new((gid, "arithmetic_operation"), "Operation")
+            .selected_text(form.operation.name())
+            .show_ui(ui, |ui| {
+                for op in Operation::ALL {
+                    let is_selected = form.operation.name() == op.name();
+                    if ui.selectable_label(is_selected, op.name()).clicked() && !is_selected {
+                        form.operation = op;
+                    }
+                }
+            });
+
+        let sources: Vec<FileID> = if form.operation.is_binary() {
+            for (label, slot) in [("A", &mut form.minuend), ("B", &mut form.subtrahend)] {
+                let selected_text = slot
+                    .map(|fid| display_name(registry, &fid))
+                    .unwrap_or_default();
+                egui::ComboBox::new((gid, label), label)
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        for fid in grp.file_ids.iter() {
+                            ui.selectable_value(slot, Some(*fid), display_name(registry, fid));
+                        }
+                    });
+            }
+            if let Operation::Difference { factor } = &mut form.operation {
+                ui.horizontal(|ui| {
+                    ui.label("k: ");
+                    ui.add(egui::DragValue::new(factor).speed(0.01));
+                });
+            }
+            [form.minuend, form.subtrahend]
+                .into_iter()
+                .flatten()
+                .filter(|fid| grp.file_ids.contains(fid))
+                .collect()
+        } else {
+            for fid in grp.file_ids.iter() {
+                let mut checked = form.selection.contains(fid);
+                if ui
+                    .checkbox(&mut checked, display_name(registry, fid))
+                    .changed()
+                {
+                    if checked {
+                        form.selection.push(*fid);
+                    } else {
+                        form.selection.retain(|sfid| sfid != fid);
+                    }
+                }
+            }
+            form.selection
+                .iter()
+                .filter(|fid| grp.file_ids.contains(fid))
+                .copied()
+                .collect()
+        };
+
+        egui::ComboBox::new((gid, "arithmetic_interpolation"), "Interpolation")
+            .selected_text(form.interpolation.name())
+            .show_ui(ui, |ui| {
+                for method in Interpolation::ALL {
+                    ui.selectable_value(&mut form.interpolation, method, method.name());
+                }
+            });
+
+        let enough_sources = if form.operation.is_binary() {
+            sources.len() == 2
+        } else {
+            sources.len() >= 2
+        };
+        if ui
+            .add_enabled(enough_sources, egui::Button::new("Create derived file"))
+            .on_hover_text("data is interpolated onto the x-values of the first file")
+            .clicked()
+        {
+            let recipe = Recipe {
+                operation: form.operation,
+                sources,
+                interpolation: form.interpolation,
+            };
+            event_queue.queue_event(Box::new(AddDerivedFile::new(recipe, gid)));
+        }
     }
     pub fn file_settings(
         &mut self,
@@ -171,6 +303,16 @@ impl FileHandler {
         ui: &mut egui::Ui,
         _ctx: &egui::Context,
     ) {
+        let recipe_label = self
+            .registry
+            .get(&fid)
+            .and_then(|file| file.recipe.as_ref())
+            .map(|recipe| recipe.label(&self.registry));
+        let palette_color = self
+            .palette_color_buffer
+            .get(&fid)
+            .copied()
+            .unwrap_or_default();
         let file = match self.registry.get_mut(&fid) {
             Some(file) => file,
             None => {
@@ -202,10 +344,20 @@ impl FileHandler {
             })
         });
 
+        // Display the recipe of derived files.
+        if let Some(label) = recipe_label {
+            ui.horizontal(|ui| {
+                ui.label(format!("Derived: {label}"));
+                if ui.small_button("Recompute").clicked() {
+                    event_queue.queue_event(Box::new(EvaluateRecipe(fid)));
+                }
+            });
+        }
+
         // Display error if csv could not be parsed.
         if let Err(error) = file.data.value() {
             ui.label(error).highlight();
-            if ui.small_button("Try to located").clicked() {
+            if file.recipe.is_none() && ui.small_button("Try to located").clicked() {
                 event_queue.queue_event(Box::new(LocateFile::new(
                     file.file_name().to_owned(),
                     fid,
@@ -214,7 +366,7 @@ impl FileHandler {
             }
         };
 
-        file.render_property_settings(ui, fid, event_queue);
+        file.render_property_settings(ui, fid, palette_color, event_queue);
 
         ui.separator();
         ui.heading("Preview File Contents");
@@ -324,6 +476,7 @@ impl File {
         &mut self,
         ui: &mut egui::Ui,
         file_id: FileID,
+        palette_color: egui::Color32,
         event_queue: &mut EventQueue<EguiApp>,
     ) {
         ui.horizontal(|ui| {
@@ -332,7 +485,8 @@ impl File {
                 self.shorten_alias('_');
             }
             ui.text_edit_singleline(&mut self.properties.alias)
-                .labelled_by(label.id);
+                .labelled_by(label.id)
+                .on_hover_text("^{...} raises, _{...} lowers text, \\alpha inserts α");
         });
 
         ui.separator();
@@ -361,25 +515,49 @@ impl File {
             y_changed = ui.add(select_y_column).changed();
         });
         if x_changed || y_changed {
-            if let Ok(val) = self.data.value_mut() {
-                val.regenerate_cache(
-                    self.properties.selected_x_column,
-                    self.properties.selected_y_column,
-                );
-            }
+            event_queue.queue_event(Box::new(RefreshCache(file_id)));
         };
 
+        ui.separator();
+        let (data_x, data_y) = self.data_units();
+        let units = &mut self.properties.units;
+        let mut units_changed = false;
+        egui::Grid::new((file_id, "units")).show(ui, |ui| {
+            ui.label("");
+            ui.label("X-Unit");
+            ui.label("Y-Unit");
+            ui.end_row();
+            ui.label("Data: ");
+            units_changed |= unit_combobox(ui, (file_id, "data_x"), &mut units.data_x, data_x);
+            units_changed |= unit_combobox(ui, (file_id, "data_y"), &mut units.data_y, data_y);
+            ui.end_row();
+            ui.label("Display: ");
+            units_changed |=
+                unit_combobox(ui, (file_id, "display_x"), &mut units.display_x, data_x);
+            units_changed |=
+                unit_combobox(ui, (file_id, "display_y"), &mut units.display_y, data_y);
+            ui.end_row();
+        });
+        if units_changed {
+            event_queue.queue_event(Box::new(RefreshCache(file_id)));
+        }
+        if let Err(msg) = self.xy_data() {
+            ui.colored_label(egui::Color32::RED, msg);
+        }
+
         ui.separator();
         let mut scaling_changed = false;
         ui.label("X-Offset: ");
         let dragv = egui::DragValue::new(&mut self.properties.xoffset);
-        scaling_changed = scaling_changed || ui.add(dragv).changed();
+        // `|=` does not short-circuit, so that all fields are drawn after one
+        // of them changed.
+        scaling_changed |= ui.add(dragv).changed();
         ui.label("Y-Offset: ");
         let dragv = egui::DragValue::new(&mut self.properties.yoffset);
-        scaling_changed = scaling_changed || ui.add(dragv).changed();
+        scaling_changed |= ui.add(dragv).changed();
         ui.label("Y-Scale: ");
         let dragv = egui::DragValue::new(&mut self.properties.yscale);
-        scaling_changed = scaling_changed || ui.add(dragv).changed();
+        scaling_changed |= ui.add(dragv).changed();
         if scaling_changed {
             event_queue.queue_event(Box::new(RefreshCache(file_id)));
         }
@@ -388,15 +566,48 @@ impl File {
         }
 
         ui.separator();
+        ui.label("Normalisation:");
+        let xrange = self
+            .get_cache()
+            .into_iter()
+            .flatten()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(xmin, xmax), point| {
+                (xmin.min(point.x), xmax.max(point.x))
+            });
+        if self.properties.normalisation.render(ui, file_id, xrange) {
+            event_queue.queue_event(Box::new(RefreshCache(file_id)));
+        }
+        if let Err(msg) = self.normalisation_coefficients() {
+            ui.colored_label(egui::Color32::RED, msg);
+        }
+
+        ui.separator();
+        ui.checkbox(&mut self.properties.secondary_y, "Plot on right y-axis");
+        ui.horizontal(|ui| {
+            egui::ComboBox::new((file_id, "line_style"), "Line")
+                .selected_text(self.properties.line_style.name())
+                .show_ui(ui, |ui| {
+                    for style in LineStyle::ALL {
+                        ui.selectable_value(&mut self.properties.line_style, style, style.name());
+                    }
+                });
+            egui::ComboBox::new((file_id, "marker"), "Marker")
+                .selected_text(self.properties.marker.name())
+                .show_ui(ui, |ui| {
+                    for marker in Marker::ALL {
+                        ui.selectable_value(&mut self.properties.marker, marker, marker.name());
+                    }
+                });
+        });
         ui.horizontal(|ui| {
             ui.label("Custom Color: ");
-            if let Some(color) = self.properties.color.as_mut() {
-                ui.color_edit_button_srgba(color);
-            } else {
-                let mut color = egui::Color32::RED;
-                if ui.color_edit_button_srgba(&mut color).clicked() {
-                    self.properties.color = Some(color);
-                };
+            // Starts from the color of the palette of the group.
+            let mut color = self.properties.color.unwrap_or(palette_color);
+            if ui.color_edit_button_srgba(&mut color).changed() {
+                self.properties.color = Some(color);
+            }
+            if self.properties.color.is_some() && ui.small_button("Reset").clicked() {
+                self.properties.color = None;
             }
         });
 
@@ -420,6 +631,38 @@ impl File {
     }
 }
 
+/// Combobox to choose a unit. If `unit` is not set, `fallback` is used.
+/// Returns true if the unit was changed.
+pub fn unit_combobox<U: Unit>(
+    ui: &mut egui::Ui,
+    id_salt: impl std::hash::Hash,
+    unit: &mut Option<U>,
+    fallback: U,
+) -> bool {
+    let mut changed = false;
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(unit.unwrap_or(fallback).name())
+        .show_ui(ui, |ui| {
+            for option in U::ALL {
+                if ui
+                    .selectable_label(unit.unwrap_or(fallback) == *option, option.name())
+                    .clicked()
+                {
+                    *unit = Some(*option);
+                    changed = true;
+                }
+            }
+        });
+    changed
+}
+
+fn display_name<'a>(registry: &'a HashMap<FileID, File>, fid: &FileID) -> &'a str {
+    registry
+        .get(fid)
+        .map(|file| file.display_name())
+        .unwrap_or("<missing>")
+}
+
 fn file_name_layout(file: &mut File) -> Option<LayoutJob> {
     let file_label_txt = if let Some(name) = file.path.file_name().and_then(|name| name.to_str()) {
         if file.data.value().is_ok() {
@@ -437,3 +680,48 @@ fn file_name_layout(file: &mut File) -> Option<LayoutJob> {
     };
     Some(file_label_txt)
 }
+
+impl Normalisation {
+    /// Render the settings of the normalisation. Returns true if they were
+    /// changed. A newly restricted window starts out as `default_window`.
+    pub fn render(
+        &mut self,
+        ui: &mut egui::Ui,
+        id_salt: impl std::hash::Hash,
+        default_window: (f64, f64),
+    ) -> bool {
+        let mut changed = false;
+        egui::ComboBox::new((&id_salt, "normalisation"), "Mode")
+            .selected_text(self.mode.name())
+            .show_ui(ui, |ui| {
+                for mode in NormalisationMode::ALL {
+                    changed |= ui
+                        .selectable_value(&mut self.mode, mode, mode.name())
+                        .changed();
+                }
+            });
+
+        if self.mode == NormalisationMode::ReferencePoint {
+            ui.horizontal(|ui| {
+                ui.label("Reference x: ");
+                changed |= ui
+                    .add(egui::DragValue::new(&mut self.reference_x))
+                    .changed();
+            });
+        } else if self.mode.uses_window() {
+            let mut restrict = self.window.is_some();
+            if ui.checkbox(&mut restrict, "Restrict to window").changed() {
+                self.window = restrict.then_some(default_window);
+                changed = true;
+            }
+            if let Some((left, right)) = self.window.as_mut() {
+                ui.horizontal(|ui| {
+                    changed |= ui.add(egui::DragValue::new(left)).changed();
+                    ui.label("to");
+                    changed |= ui.add(egui::DragValue::new(right)).changed();
+                });
+            }
+        }
+        changed
+    }
+}
@@ -2,7 +2,14 @@ mod file_handling;
 mod plotter;
 mod search;
 
-pub use file_handling::{parse_csv, File, FileHandler};
-pub(in crate::app) use file_handling::{FileID, FileProperties, Group};
-pub use plotter::{save_svg, Plotter, PlotterMode};
+pub use file_handling::{parse_csv, File, FileHandler, Palette};
+pub(in crate::app) use file_handling::{
+    resample, uniform_grid, unit_combobox, Baseline, BaselineData, FileID, FileProperties, Group,
+    IntegrationError, IntegrationMethod, IntegrationRule, Interpolation, Normalisation, Operation,
+    Recipe, WindowStatistics,
+};
+pub use plotter::{
+    save_plot, AxisScale, Inset, IntegrationWindow, PanelLayout, PlotScales, Plotter, PlotterMode,
+    SecondaryAxis, StackLayout,
+};
 pub use search::{ParsedData, Search};
@@ -0,0 +1,122 @@
+//! Inset of exported figures: a region of the plot is magnified in a smaller
+//! axis inside of the main axis, and marked on the main axis.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InsetCorner {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl InsetCorner {
+    pub const ALL: [InsetCorner; 4] = [
+        InsetCorner::TopLeft,
+        InsetCorner::TopRight,
+        InsetCorner::BottomLeft,
+        InsetCorner::BottomRight,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InsetCorner::TopLeft => "Top Left",
+            InsetCorner::TopRight => "Top Right",
+            InsetCorner::BottomLeft => "Bottom Left",
+            InsetCorner::BottomRight => "Bottom Right",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Inset {
+    /// The magnified region `[xmin, xmax, ymin, ymax]`, in data coordinates.
+    pub region: [f64; 4],
+    /// The panel in which the region was selected, see `PanelLayout`.
+    pub panel: Option<usize>,
+    /// The corner of the main axis in which the inset is placed.
+    pub corner: InsetCorner,
+    /// Width and height of the inset, relative to the main axis.
+    pub size: f64,
+}
+
+impl Inset {
+    /// Distance of the inset from the edges of the main axis, in axis
+    /// coordinates.
+    const MARGIN: f64 = 0.04;
+
+    /// The inset magnifying the rectangle spanned by the points `a` and `b`.
+    pub fn new(a: [f64; 2], b: [f64; 2], panel: Option<usize>) -> Self {
+        Self {
+            region: [
+                a[0].min(b[0]),
+                a[0].max(b[0]),
+                a[1].min(b[1]),
+                a[1].max(b[1]),
+            ],
+            panel,
+            corner: InsetCorner::default(),
+            size: 0.4,
+        }
+    }
+
+    /// Placement `[u, v, width, height]` of the inset in axis coordinates,
+    /// with `u`, `v` the lower left corner.
+    pub fn placement(&self) -> [f64; 4] {
+        let size = self.size.clamp(0.1, 1.0 - 2.0 * Self::MARGIN);
+        let (low, high) = (Self::MARGIN, 1.0 - Self::MARGIN - size);
+        let (u, v) = match self.corner {
+            InsetCorner::TopLeft => (low, high),
+            InsetCorner::TopRight => (high, high),
+            InsetCorner::BottomLeft => (low, low),
+            InsetCorner::BottomRight => (high, low),
+        };
+        [u, v, size, size]
+    }
+
+    /// The panel, out of `panels`, in which the inset is shown: the one in
+    /// which its region was selected, or the first one, if the layout of the
+    /// panels has changed since.
+    pub fn shown_in(&self, panels: &[Option<usize>]) -> Option<usize> {
+        if panels.contains(&self.panel) {
+            self.panel
+        } else {
+            panels.first().copied().flatten()
+        }
+    }
+
+    /// The corners of the region, as a closed polygon.
+    pub fn outline(&self) -> [[f64; 2]; 5] {
+        let [xmin, xmax, ymin, ymax] = self.region;
+        [
+            [xmin, ymin],
+            [xmax, ymin],
+            [xmax, ymax],
+            [xmin, ymax],
+            [xmin, ymin],
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inset() {
+        let mut inset = Inset::new([2.0, 1.0], [0.0, 3.0], None);
+        assert_eq!(inset.region, [0.0, 2.0, 1.0, 3.0]);
+        assert_eq!(inset.outline()[2], [2.0, 3.0]);
+        inset.size = 0.5;
+        inset.corner = InsetCorner::BottomRight;
+        let [u, v, width, height] = inset.placement();
+        assert!((u - 0.46).abs() < 1e-12);
+        assert_eq!((v, width, height), (0.04, 0.5, 0.5));
+        assert_eq!(inset.shown_in(&[None]), None);
+        inset.panel = Some(2);
+        assert_eq!(inset.shown_in(&[Some(1), Some(2)]), Some(2));
+        assert_eq!(inset.shown_in(&[Some(1), Some(3)]), Some(1));
+    }
+}
@@ -0,0 +1,185 @@
+//! Named integration windows, which are evaluated on all plotted files.
+
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::components::{FileHandler, IntegrationError, IntegrationMethod, WindowStatistics};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IntegrationWindow {
+    pub name: String,
+    pub left: f64,
+    pub right: f64,
+}
+
+/// Results of the integration of all plotted files (rows) in all windows
+/// (columns).
+pub struct IntegrationTable {
+    pub window_names: Vec<String>,
+    /// Description of the integration method used for all results.
+    pub method: String,
+    pub rows: Vec<IntegrationRow>,
+}
+
+pub struct IntegrationRow {
+    pub name: String,
+    pub results: Vec<Result<WindowStatistics, IntegrationError>>,
+}
+
+impl IntegrationTable {
+    pub fn new(
+        file_handler: &FileHandler,
+        windows: &[IntegrationWindow],
+        method: &IntegrationMethod,
+    ) -> Self {
+        let baseline = file_handler.baseline_data(method);
+        let rows = file_handler
+            .groups
+            .iter()
+            .filter_map(|g| g.as_ref().filter(|g| g.is_plotted))
+            .flat_map(|g| g.file_ids.iter().map(|fid| (fid, &g.name)))
+            .filter_map(|(fid, group_name)| {
+                let file = file_handler.registry.get(fid)?;
+                let results = windows
+                    .iter()
+                    .map(|w| match &baseline {
+                        Ok(baseline) => {
+                            file.window_statistics(w.left, w.right, method, baseline.as_ref())
+                        }
+                        Err(err) => Err(err.clone()),
+                    })
+                    .collect();
+                Some(IntegrationRow {
+                    name: format!("{} ({})", file.display_name(), group_name),
+                    results,
+                })
+            })
+            .collect();
+        Self {
+            window_names: windows.iter().map(|w| w.name.clone()).collect(),
+            method: file_handler.describe_method(method),
+            rows,
+        }
+    }
+
+    /// Pairs of window indices `(i, j)` for which the ratio of the area of
+    /// window i to the area of window j is shown.
+    pub fn ratio_pairs(&self) -> Vec<(usize, usize)> {
+        let n = self.window_names.len();
+        (0..n)
+            .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+            .collect()
+    }
+
+    /// Export the results as CSV. The integration method is recorded next to
+    /// each area, so that the numbers can be traced.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("file");
+        for name in self.window_names.iter() {
+            for quantity in ["area", "method", "peak max", "centroid"] {
+                let _ = write!(csv, ",{}", csv_field(&format!("{name} {quantity}")));
+            }
+        }
+        for (i, j) in self.ratio_pairs() {
+            let _ = write!(
+                csv,
+                ",{}",
+                csv_field(&format!(
+                    "{} / {}",
+                    self.window_names[i], self.window_names[j]
+                ))
+            );
+        }
+        csv.push('\n');
+
+        let method = csv_field(&self.method);
+        for row in self.rows.iter() {
+            csv.push_str(&csv_field(&row.name));
+            for result in row.results.iter() {
+                match result {
+                    Ok(stats) => {
+                        let _ = write!(
+                            csv,
+                            ",{},{method},{},{}",
+                            stats.area, stats.peak_max, stats.centroid
+                        );
+                    }
+                    Err(_) => csv.push_str(",,,,"),
+                }
+            }
+            for (i, j) in self.ratio_pairs() {
+                match row.ratio(i, j) {
+                    Some(ratio) => {
+                        let _ = write!(csv, ",{ratio}");
+                    }
+                    None => csv.push(','),
+                }
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+impl IntegrationRow {
+    /// Ratio of the areas of window i and window j.
+    pub fn ratio(&self, i: usize, j: usize) -> Option<f64> {
+        match (self.results.get(i)?, self.results.get(j)?) {
+            (Ok(a), Ok(b)) => Some(a.area / b.area),
+            _ => None,
+        }
+    }
+}
+
+/// Quote a field for CSV, if necessary.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(area: f64) -> Result<WindowStatistics, IntegrationError> {
+        Ok(WindowStatistics {
+            area,
+            peak_max: 1.0,
+            centroid: 2.0,
+        })
+    }
+
+    #[test]
+    fn test_csv() {
+        let table = IntegrationTable {
+            window_names: vec!["OH".into(), "CH, sym".into(), "CO".into()],
+            method: "Trapezoid rule, chord baseline, signed area".into(),
+            rows: vec![IntegrationRow {
+                name: "a.csv (G0)".into(),
+                results: vec![
+                    stats(4.0),
+                    stats(2.0),
+                    Err(IntegrationError::WindowOutOfBounds),
+                ],
+            }],
+        };
+        assert_eq!(table.ratio_pairs(), vec![(0, 1), (0, 2), (1, 2)]);
+        let csv = table.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "file,OH area,OH method,OH peak max,OH centroid,\"CH, sym area\",\"CH, sym method\",\
+             \"CH, sym peak max\",\"CH, sym centroid\",CO area,CO method,CO peak max,CO centroid,\
+             \"OH / CH, sym\",OH / CO,\"CH, sym / CO\""
+        );
+        let method = "\"Trapezoid rule, chord baseline, signed area\"";
+        assert_eq!(
+            lines.next().unwrap(),
+            format!("a.csv (G0),4,{method},1,2,2,{method},1,2,,,,,2,,")
+        );
+    }
+}
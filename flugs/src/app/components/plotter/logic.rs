@@ -1,68 +1,356 @@
-use std::io::Write;
+use std::{collections::HashMap, io::Write};
 
 use egui_plot::{PlotBounds, PlotPoint};
 
-use crate::EguiApp;
+use super::{IntegrationTable, secondary::YTransform, stack::label_position};
+
+use crate::{
+    EguiApp,
+    app::components::{
+        BaselineData, File, FileHandler, FileID, FileProperties, Interpolation, resample,
+        uniform_grid,
+    },
+    app::config::ExportFormat,
+    app::styles::StylePreset,
+};
 
 impl super::Plotter {
     pub fn apply_bounds(&mut self, bounds: [f64; 4]) {
         self.request_plot_bounds = Some(bounds);
     }
 
+    /// Integrate all plotted files in the named integration windows.
+    pub fn integration_table(&self, file_handler: &FileHandler) -> IntegrationTable {
+        IntegrationTable::new(
+            file_handler,
+            &self.integration_windows,
+            &self.integration_method,
+        )
+    }
+
     pub fn get_current_plot_bounds(&self) -> PlotBounds {
         self.current_plot_bounds
     }
+
+    /// The samples of `file` and of the baseline in the x-window
+    /// `(left, right)` as `(xs, ys, baselines)`, transformed in the same way
+    /// as the cache of the file.
+    pub(super) fn integral_band(
+        &self,
+        file: &File,
+        (left, right): (f64, f64),
+        baseline: Option<&BaselineData>,
+    ) -> Option<(Vec<f64>, Vec<f64>, Vec<f64>)> {
+        let samples = file
+            .window_samples(left, right, &self.integration_method, baseline)
+            .ok()?;
+        let (nscale, nshift) = file.normalisation_coefficients().unwrap_or((1.0, 0.0));
+        let FileProperties {
+            xoffset,
+            yoffset,
+            yscale,
+            ..
+        } = file.properties;
+        let transform = |ys: &[f64]| -> Vec<f64> {
+            ys.iter()
+                .map(|y| (y * nscale + nshift) * yscale + yoffset)
+                .collect()
+        };
+        Some((
+            samples.x.iter().map(|x| x + xoffset).collect(),
+            transform(&samples.y),
+            transform(&samples.baseline),
+        ))
+    }
+
+    /// Outline of the area between `file` and the baseline in the x-window
+    /// `(left, right)`, transformed in the same way as the cache of the file.
+    pub(super) fn integral_outline(
+        &self,
+        file: &File,
+        window: (f64, f64),
+        baseline: Option<&BaselineData>,
+    ) -> Option<Vec<[f64; 2]>> {
+        let (xs, ys, baselines) = self.integral_band(file, window, baseline)?;
+        Some(
+            xs.iter()
+                .zip(&ys)
+                .chain(xs.iter().zip(&baselines).rev())
+                .map(|(x, y)| [*x, *y])
+                .collect(),
+        )
+    }
 }
 
-pub fn save_svg(app: &EguiApp, path: &std::path::Path) {
-    use svg_export::{self, Axis, Figure, LinePlot};
+/// Export the plot to `path`, in the format chosen in the config.
+pub fn save_plot(app: &EguiApp, path: &std::path::Path) {
+    let format = app.config.export_format;
+    log::debug!("requested to save {} at '{:?}'", format.name(), path);
 
-    log::debug!("requested to save svg at '{:?}'", path);
+    let fig = figure(app);
+    let data = match format {
+        ExportFormat::Svg => fig.render().into_bytes(),
+        ExportFormat::Png => match fig.render_png(app.config.export_preset().dpi) {
+            Ok(data) => data,
+            Err(err) => {
+                log::error!("unable to render png: {err}");
+                return;
+            }
+        },
+        ExportFormat::Pdf => fig.render_pdf(),
+    };
 
     let mut file = match std::fs::File::create(path) {
         Ok(file) => file,
         Err(err) => {
-            log::error!("unable to create file for saving svg: {:?}", err);
+            log::error!("unable to create file for saving plot: {:?}", err);
             return;
         }
     };
+    if let Err(err) = file.write_all(&data) {
+        log::error!("could not write plot file {:?}: {:?}", path, err)
+    }
+}
+
+/// The figure showing the plot as in the plotter, in the export style.
+fn figure(app: &EguiApp) -> svg_export::Figure {
+    use svg_export::{self, Axis, Figure};
 
     let ([xmin, ymin], [xmax, ymax]) = (
         app.plotter.current_plot_bounds.min(),
         app.plotter.current_plot_bounds.max(),
     );
 
-    let mut fig = Figure::empty(app.config.svg_width, app.config.svg_height);
-    let mut ax = Axis::default()
-        .with_xlim(xmin, xmax)
-        .with_ylim(ymin, ymax)
-        .with_xlabel(&app.config.x_label)
-        .with_ylabel(&app.config.y_label)
-        .with_legend(true)
-        .draw_xaxis(app.config.draw_xaxis)
-        .draw_yaxis(app.config.draw_yaxis)
-        .with_x_minor_ticks(app.config.num_x_minorticks)
-        .with_y_minor_ticks(app.config.num_y_minorticks);
-
-    // Overwrite axis ticks with ticks from config, if available.
-    if !app.config.x_ticks.pos.is_empty() {
-        app.config.x_ticks.pos.clone_into(&mut ax.ticks.xpos);
+    // Without a custom label, the axes are labelled by the units of the data.
+    let (x_unit, y_unit) = app.file_handler.plotted_units();
+    let x_label = match (app.config.x_label.is_empty(), x_unit) {
+        (true, Some(unit)) => unit.axis_label(),
+        _ => app.config.x_label.clone(),
+    };
+    let y_label = match (app.config.y_label.is_empty(), y_unit) {
+        (true, Some(unit)) => unit.axis_label(),
+        _ => app.config.y_label.clone(),
+    };
+
+    // Stacked files are offset on top of their own offsets.
+    let offsets = app.plotter.stack.offsets(&app.file_handler);
+    let secondary = app
+        .plotter
+        .secondary_axis
+        .transform(&app.file_handler, &offsets);
+    let y2_label = match (
+        app.plotter.secondary_axis.label.is_empty(),
+        app.file_handler.secondary_y_unit(),
+    ) {
+        (true, Some(unit)) => unit.axis_label(),
+        _ => app.plotter.secondary_axis.label.clone(),
+    };
+
+    // One axis for all groups, or one axis per group sharing the x-axis, as
+    // in the plotter. The margins of the axes are fitted to their labels.
+    let panels = app.plotter.panels.panels(&app.file_handler);
+    let style = app.config.export_preset();
+    let theme = style.theme();
+    let (width, height) = style.size().pixels();
+    let mut fig = Figure::empty(width, height);
+    let mut insets = Vec::new();
+    for (i, (panel, ax)) in panels
+        .iter()
+        .zip(Axis::stacked_in([0.0, 0.0, 1.0, 1.0], panels.len(), 0.0))
+        .enumerate()
+    {
+        let is_lowest = i + 1 == panels.len();
+        let (ymin, ymax) = match panel.and_then(|gid| app.plotter.panel_bounds.get(&gid)) {
+            Some(bounds) => (bounds.min()[1], bounds.max()[1]),
+            None => (ymin, ymax),
+        };
+        let mut ax = ax
+            .with_auto_margins(true)
+            .with_xlim(xmin, xmax)
+            .with_ylim(ymin, ymax)
+            .with_xlabel(if is_lowest { &x_label } else { "" })
+            .with_xtick_labels(is_lowest)
+            .with_ylabel(&y_label)
+            .with_theme(&theme)
+            .with_legend(true)
+            .draw_xaxis(app.config.draw_xaxis)
+            .draw_yaxis(app.config.draw_yaxis)
+            .with_x_minor_ticks(app.config.num_x_minorticks)
+            .with_y_minor_ticks(app.config.num_y_minorticks)
+            .with_xscale(app.config.scales.x.into())
+            .with_yscale(app.config.scales.y.into());
+
+        // The secondary axis shows the same range as the primary one in the
+        // plotter.
+        if let Some(secondary) = secondary {
+            ax = ax
+                .with_y2lim(secondary.invert(ymin), secondary.invert(ymax))
+                .with_y2label(&y2_label);
+        }
+
+        // Overwrite axis ticks with ticks from config, if available.
+        if !app.config.x_ticks.pos.is_empty() {
+            app.config.x_ticks.pos.clone_into(&mut ax.ticks.xpos);
+        }
+        if !app.config.y_ticks.pos.is_empty() {
+            app.config.y_ticks.pos.clone_into(&mut ax.ticks.ypos);
+        }
+
+        add_lines(
+            app,
+            &mut ax,
+            *panel,
+            &offsets,
+            secondary,
+            [xmin, xmax, ymin, ymax],
+            true,
+        );
+
+        // The inset is placed once the main axis, which marks its region,
+        // is laid out.
+        if let Some(inset) = app
+            .plotter
+            .inset
+            .filter(|inset| inset.shown_in(&panels) == *panel)
+        {
+            ax.add_marker_box(inset.region);
+            insets.push((fig.axes().len(), *panel, inset));
+        }
+        ax.insert_into(&mut fig);
+    }
+
+    // Insets are drawn on top of all main axes.
+    for (index, panel, inset) in insets {
+        let [x0, x1, y0, y1] = inset.region;
+        let [u, v, width, height] = inset.placement();
+        let mut inset_ax = fig.axes()[index]
+            .inset(u, v, width, height)
+            .with_xlim(x0, x1)
+            .with_ylim(y0, y1)
+            .draw_xaxis(app.config.draw_xaxis)
+            .draw_yaxis(app.config.draw_yaxis)
+            .with_xscale(app.config.scales.x.into())
+            .with_yscale(app.config.scales.y.into());
+        if let Some(secondary) = secondary {
+            inset_ax = inset_ax.with_y2lim(secondary.invert(y0), secondary.invert(y1));
+        }
+        add_lines(
+            app,
+            &mut inset_ax,
+            panel,
+            &offsets,
+            secondary,
+            [x0, x1, y0, y1],
+            false,
+        );
+        inset_ax.insert_into(&mut fig);
     }
-    if !app.config.y_ticks.pos.is_empty() {
-        app.config.y_ticks.pos.clone_into(&mut ax.ticks.ypos);
+
+    fig
+}
+
+/// The colors of the files in exported figures, as in the plotter. Files
+/// without a custom color in groups with the default palette take the colors
+/// of the cycle of `style` instead, at the same position in the cycle as in
+/// the default palette.
+fn export_colors(app: &EguiApp, style: &StylePreset) -> HashMap<FileID, String> {
+    let theme = style.theme();
+    let file_colors = app.file_handler.file_colors();
+    let mut colors = HashMap::new();
+    for (grp, fid, index) in app.file_handler.palette_indices() {
+        let custom = app
+            .file_handler
+            .registry
+            .get(&fid)
+            .and_then(|file| file.properties.color);
+        colors
+            .entry(fid)
+            .or_insert_with(|| match theme.cycle_color(index) {
+                Some(color) if custom.is_none() && grp.palette.is_none() => color.to_string(),
+                _ => {
+                    let color = file_colors.get(&fid).copied().unwrap_or_default();
+                    color.to_hex().chars().take(7).collect()
+                }
+            });
     }
+    colors
+}
+
+/// Add the plotted files of the group `panel` (or of all groups) to `ax`.
+/// Stacked files are labelled if requested and `labels` is set.
+fn add_lines(
+    app: &EguiApp,
+    ax: &mut svg_export::Axis,
+    panel: Option<usize>,
+    offsets: &HashMap<FileID, f64>,
+    secondary: Option<YTransform>,
+    [xmin, xmax, ymin, ymax]: [f64; 4],
+    labels: bool,
+) {
+    use svg_export::{FillBetween, LinePlot, RefLine, Span, Text};
+
+    // Labels of stacked files are placed in axis coordinates, which are
+    // linear in plot coordinates.
+    let scales = app.config.scales;
+    let bounds = scales.bounds_to_plot(PlotBounds::from_min_max([xmin, ymin], [xmax, ymax]));
+    let ([left, bottom], [right, top]) = (bounds.min(), bounds.max());
+    let to_axis = |point: [f64; 2]| {
+        let [x, y] = scales.to_plot(point);
+        ((x - left) / (right - left), (y - bottom) / (top - bottom))
+    };
+    let right_edge = if scales.x.reversed { xmin } else { xmax };
+    let style = app.config.export_preset();
+    let colors = export_colors(app, &style);
+    // Trace labels and annotations are set like the axis labels.
+    let font_size = format!("{}pt", style.label_size);
+
+    // The current integral and the named integration windows are shaded on
+    // request.
+    let integrals: Vec<(f64, f64)> = if app.config.export_integrals {
+        app.plotter
+            .current_integral
+            .into_iter()
+            .chain(
+                app.plotter
+                    .integration_windows
+                    .iter()
+                    .map(|w| (w.left, w.right)),
+            )
+            .collect()
+    } else {
+        Vec::new()
+    };
+    // Named windows are marked over the whole height of the axis, as in the
+    // plotter.
+    if app.config.export_integrals {
+        for window in app.plotter.integration_windows.iter() {
+            ax.add_span(Span::vertical(window.left, window.right).with_label(&window.name));
+        }
+    }
+    let baseline = if integrals.is_empty() {
+        None
+    } else {
+        app.file_handler
+            .baseline_data(&app.plotter.integration_method)
+            .ok()
+            .flatten()
+    };
 
-    for (_, grp) in app
+    for (gid, grp) in app
         .file_handler
         .groups
         .iter()
         .enumerate()
         .filter_map(|(id, x)| Some(id).zip(x.as_ref()))
     {
-        if !grp.is_plotted {
+        if !grp.is_plotted || panel.is_some_and(|panel| panel != gid) {
             continue;
         }
-        for fid in grp.file_ids.iter() {
+        for fid in grp
+            .file_ids
+            .iter()
+            .filter(|fid| !app.plotter.hidden_fids.contains(fid))
+        {
             // I'll need `file` later for labels.
             if let Some((cached_data, plot_file)) = app
                 .file_handler
@@ -70,37 +358,63 @@ pub fn save_svg(app: &EguiApp, path: &std::path::Path) {
                 .get(fid)
                 .and_then(|file| file.get_cache().map(|cache| (cache, file)))
             {
-                // Color for current file.
-                let color: String = {
-                    let color_id: i32 = (*fid).into();
-                    super::ui::auto_color(color_id)
-                        .to_hex()
-                        .chars()
-                        .take(7)
-                        .collect()
-                };
+                let color = colors.get(fid).map_or("black", String::as_str);
 
-                let label = if !plot_file.properties.alias.is_empty() {
-                    format!("{} ({})", &plot_file.properties.alias, grp.name)
+                let name = if !plot_file.properties.alias.is_empty() {
+                    plot_file.properties.alias.as_str()
                 } else {
-                    format!("{} ({})", plot_file.file_name(), grp.name)
+                    plot_file.file_name()
                 };
+                let label = format!("{} ({})", name, grp.name);
                 log::debug!("plotting line with label {}", label);
 
-                // Downsample to a maximum of 1000 points.
-                // TODO: Make this a number of points a global option.
-                // let window_size = cached_data.len() / 1000;
-                // let xs: Vec<_> = cached_data
-                //     .chunks(window_size)
-                //     .map(|vals| vals.iter().map(|[x, _]| x + x0).sum::<f64>() / vals.len() as f64)
-                //     .collect();
-                // let ys: Vec<_> = cached_data
-                //     .chunks(window_size)
-                //     .map(|vals| {
-                //         vals.iter().map(|[_, y]| (y * scale) + y0).sum::<f64>() / window_size as f64
-                //     })
-                //     .collect();
-                //
+                let offset = offsets.get(fid).copied().unwrap_or(0.0);
+                // Lines on the secondary axis are drawn in their own units,
+                // but labels are placed on the primary axis.
+                let on_secondary = secondary.filter(|_| plot_file.properties.secondary_y);
+                if labels
+                    && app.plotter.stack.labels
+                    && let Some([x, y]) = label_position(cached_data, right_edge)
+                {
+                    let y = on_secondary
+                        .unwrap_or(YTransform::IDENTITY)
+                        .apply(y + offset);
+                    let (u, v) = to_axis([x, y]);
+                    ax.add_label(
+                        Text::new(&label, u, v)
+                            .with_color(color)
+                            .with_anchor("end")
+                            .with_font_size(&font_size),
+                    );
+                }
+
+                // Annotations are placed in the coordinates of the data of
+                // the file.
+                for anno in plot_file.properties.annotations.iter() {
+                    let y = on_secondary
+                        .unwrap_or(YTransform::IDENTITY)
+                        .apply(anno.y as f64 + offset);
+                    ax.add_label(Text::at(&anno.text, anno.x as f64, y).with_font_size(&font_size));
+                    if anno.line {
+                        ax.add_ref_line(RefLine::vertical(anno.x as f64));
+                    }
+                }
+
+                for window in integrals.iter() {
+                    if let Some((xs, ys, baselines)) =
+                        app.plotter
+                            .integral_band(plot_file, *window, baseline.as_ref())
+                    {
+                        let shift = |ys: Vec<f64>| -> Vec<f64> {
+                            ys.into_iter().map(|y| y + offset).collect()
+                        };
+                        ax.add_fill(
+                            FillBetween::new(&xs, &shift(ys), &shift(baselines))
+                                .with_color(color)
+                                .with_secondary_y(on_secondary.is_some()),
+                        );
+                    }
+                }
 
                 let mut xs = Vec::with_capacity(cached_data.len());
                 let mut ys = Vec::with_capacity(cached_data.len());
@@ -108,7 +422,7 @@ pub fn save_svg(app: &EguiApp, path: &std::path::Path) {
 
                 for PlotPoint { x, y } in cached_data.iter() {
                     xs.push(*x);
-                    ys.push(*y);
+                    ys.push(*y + offset);
                     match ymin {
                         Some(ymin_prev) if y < ymin_prev => ymin = Some(y),
                         None => ymin = Some(y),
@@ -116,18 +430,37 @@ pub fn save_svg(app: &EguiApp, path: &std::path::Path) {
                     }
                 }
 
+                // Resample lines with many points onto a uniform grid to keep
+                // the size of the exported file small.
+                let max_points = app.config.export_max_points;
+                if max_points > 1
+                    && xs.len() > max_points
+                    && let (Some(first), Some(last)) = (xs.first(), xs.last())
+                {
+                    let grid = uniform_grid(*first, *last, max_points);
+                    ys = resample(&xs, &ys, &grid, Interpolation::Linear);
+                    xs = grid;
+                }
+
+                // The selected file is highlighted, as in the plotter.
+                let linewidth = if app.plotter.selected_fid == Some(*fid) {
+                    2.5 * style.linewidth
+                } else {
+                    style.linewidth
+                };
+                let properties = &plot_file.properties;
                 let line = LinePlot::new(&xs, &ys)
-                    .with_color(&color)
-                    .with_linewidth(app.config.plot_linewidth)
-                    .with_name(&label);
+                    .with_color(color)
+                    .with_linewidth(linewidth)
+                    .with_line_style(properties.line_style.export())
+                    .with_marker(properties.marker.drawn_with(properties.line_style).export())
+                    // The group is given by the header, if shown.
+                    .with_name(if style.legend_groups { name } else { &label })
+                    .with_group(&grp.name)
+                    .with_secondary_y(on_secondary.is_some());
 
                 ax.add_line(line);
             }
         }
     }
-    ax.insert_into(&mut fig);
-
-    if let Err(err) = file.write_all(&fig.render().into_bytes()) {
-        log::error!("could not write svg file {:?}: {:?}", path, err)
-    }
 }
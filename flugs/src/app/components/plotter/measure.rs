@@ -0,0 +1,141 @@
+//! Measurement tool: a crosshair that snaps to the data of the selected file,
+//! readouts of all plotted files at the crosshair and a two-point ruler.
+
+use std::fmt::Write;
+
+use egui_plot::PlotPoint;
+
+use crate::app::components::{FileHandler, Interpolation, resample};
+
+/// State of the measurement tool in `PlotterMode::Measure`.
+#[derive(Default)]
+pub struct Measurement {
+    /// Position of the crosshair, in plot coordinates.
+    pub cursor: Option<[f64; 2]>,
+    /// Points of the ruler, placed by consecutive clicks.
+    pub ruler: [Option<[f64; 2]>; 2],
+}
+
+/// The y-value of a plotted file at the x-value of the crosshair.
+pub struct Readout {
+    pub name: String,
+    /// NaN, if the crosshair is outside of the data of the file.
+    pub y: f64,
+}
+
+impl Measurement {
+    /// Place the next point of the ruler. A click on a complete ruler starts
+    /// a new one.
+    pub fn click(&mut self, point: [f64; 2]) {
+        match self.ruler {
+            [Some(_), None] => self.ruler[1] = Some(point),
+            _ => self.ruler = [Some(point), None],
+        }
+    }
+
+    /// Distance `(Δx, Δy)` from the first to the second point of the ruler.
+    pub fn delta(&self) -> Option<(f64, f64)> {
+        let [Some(a), Some(b)] = self.ruler else {
+            return None;
+        };
+        Some((b[0] - a[0], b[1] - a[1]))
+    }
+
+    /// The y-values of all plotted files at the x-value of the crosshair.
+    pub fn readouts(&self, file_handler: &FileHandler) -> Vec<Readout> {
+        let Some([x, _]) = self.cursor else {
+            return Vec::new();
+        };
+        file_handler
+            .groups
+            .iter()
+            .filter_map(|g| g.as_ref().filter(|g| g.is_plotted))
+            .flat_map(|g| g.file_ids.iter().map(|fid| (fid, &g.name)))
+            .filter_map(|(fid, group_name)| {
+                let file = file_handler.registry.get(fid)?;
+                let (xs, ys): (Vec<f64>, Vec<f64>) =
+                    file.get_cache()?.iter().map(|p| (p.x, p.y)).unzip();
+                Some(Readout {
+                    name: format!("{} ({})", file.display_name(), group_name),
+                    y: resample(&xs, &ys, &[x], Interpolation::Linear)[0],
+                })
+            })
+            .collect()
+    }
+
+    /// The cursor, ruler and readouts as tab separated text, e.g. for copying
+    /// to the clipboard.
+    pub fn to_text(&self, readouts: &[Readout]) -> String {
+        let mut text = String::new();
+        if let Some([x, y]) = self.cursor {
+            let _ = writeln!(text, "cursor\t{x}\t{y}");
+        }
+        for (name, point) in ["P1", "P2"].iter().zip(self.ruler) {
+            if let Some([x, y]) = point {
+                let _ = writeln!(text, "{name}\t{x}\t{y}");
+            }
+        }
+        if let Some((dx, dy)) = self.delta() {
+            let _ = writeln!(text, "Δx\t{dx}");
+            let _ = writeln!(text, "Δy\t{dy}");
+        }
+        for readout in readouts.iter() {
+            let _ = writeln!(text, "{}\t{}", readout.name, readout.y);
+        }
+        text
+    }
+}
+
+/// The sample of `points` with the x-value closest to `x`.
+pub fn nearest_sample(points: &[PlotPoint], x: f64) -> Option<PlotPoint> {
+    points
+        .iter()
+        .filter(|p| p.x.is_finite() && p.y.is_finite())
+        .min_by(|a, b| (a.x - x).abs().total_cmp(&(b.x - x).abs()))
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nearest_sample() {
+        let points: Vec<PlotPoint> = [[3.0, 1.0], [1.0, 2.0], [2.0, f64::NAN], [0.0, 4.0]]
+            .into_iter()
+            .map(PlotPoint::from)
+            .collect();
+        assert_eq!(nearest_sample(&points, 0.9), Some(PlotPoint::new(1.0, 2.0)));
+        // Samples without a finite value are skipped.
+        assert_eq!(nearest_sample(&points, 2.1), Some(PlotPoint::new(3.0, 1.0)));
+        assert_eq!(nearest_sample(&[], 0.0), None);
+    }
+
+    #[test]
+    fn test_ruler() {
+        let mut measurement = Measurement::default();
+        measurement.click([1.0, 2.0]);
+        assert_eq!(measurement.delta(), None);
+        measurement.click([4.0, 0.5]);
+        assert_eq!(measurement.delta(), Some((3.0, -1.5)));
+        // A third click starts a new ruler.
+        measurement.click([0.0, 0.0]);
+        assert_eq!(measurement.ruler, [Some([0.0, 0.0]), None]);
+    }
+
+    #[test]
+    fn test_text() {
+        let measurement = Measurement {
+            cursor: Some([1.5, 2.0]),
+            ruler: [Some([1.0, 2.0]), Some([2.0, 4.0])],
+        };
+        let readouts = [Readout {
+            name: "a.csv (G0)".to_string(),
+            y: 0.25,
+        }];
+        assert_eq!(
+            measurement.to_text(&readouts),
+            "cursor\t1.5\t2\nP1\t1\t2\nP2\t2\t4\nΔx\t1\nΔy\t2\na.csv (G0)\t0.25\n"
+        );
+    }
+}
@@ -1,21 +1,66 @@
+mod inset;
+mod integration;
 mod logic;
+mod measure;
+mod panels;
+mod scale;
+mod secondary;
+mod stack;
 mod ui;
 
-pub use logic::save_svg;
+pub use inset::{Inset, InsetCorner};
+pub use integration::{IntegrationTable, IntegrationWindow};
+pub use logic::save_plot;
+pub use panels::PanelLayout;
+pub use scale::{AxisScale, PlotScales};
+pub use secondary::SecondaryAxis;
+pub use stack::{StackLayout, StackMode};
 
-use std::collections::HashMap;
+use measure::Measurement;
 
-use super::{FileID, file_handling::Annotation};
+use std::collections::{HashMap, HashSet};
+
+use super::{FileID, IntegrationMethod, Normalisation, file_handling::Annotation};
+use crate::backend_state::{XUnit, YUnit};
 
 pub struct Plotter {
     /// We use this as a buffer to store egui IDs to correlate them with file
     /// IDs. We need this to detect interactions with plotted files.
     files_plot_ids: HashMap<egui::Id, FileID>,
+    /// The colors of the files in the current frame, see
+    /// `FileHandler::file_colors`.
+    file_colors: HashMap<FileID, egui::Color32>,
+    /// The colors of the palettes of the files in the current frame, see
+    /// `FileHandler::palette_colors`.
+    palette_colors: HashMap<FileID, egui::Color32>,
     selected_fid: Option<FileID>,
+    /// Files hidden by clicking their legend entry.
+    hidden_fids: HashSet<FileID>,
     current_plot_bounds: egui_plot::PlotBounds,
+    /// Bounds of the panels of each group, if the groups are shown in
+    /// separate panels.
+    panel_bounds: HashMap<usize, egui_plot::PlotBounds>,
     current_integral: Option<(f64, f64)>,
+    /// Named windows that are integrated for all plotted files.
+    pub integration_windows: Vec<IntegrationWindow>,
+    show_integration_table: bool,
     current_annotation: Annotation,
-    integrate_with_local_baseline: bool,
+    subtraction: Subtraction,
+    measurement: Measurement,
+    /// Automatic vertical offsets of the plotted files.
+    pub stack: StackLayout,
+    pub panels: PanelLayout,
+    /// The right y-axis of files plotted against it.
+    pub secondary_axis: SecondaryAxis,
+    /// Magnified region shown as an inset in exported figures.
+    pub inset: Option<Inset>,
+    /// The region of the inset is being selected by dragging.
+    selecting_inset: bool,
+    /// Normalisation that is applied to all plotted files on request.
+    normalisation: Normalisation,
+    /// Units in which all plotted files are displayed on request.
+    display_units: (Option<XUnit>, Option<YUnit>),
+    integration_method: IntegrationMethod,
     auto_shift_after_scaling: bool,
     request_plot_bounds: Option<[f64; 4]>,
     pub mode: PlotterMode,
@@ -25,24 +70,52 @@ impl Plotter {
     pub fn new() -> Self {
         Self {
             files_plot_ids: HashMap::with_capacity(10),
+            file_colors: HashMap::new(),
+            palette_colors: HashMap::new(),
             selected_fid: None,
+            hidden_fids: HashSet::new(),
             current_plot_bounds: egui_plot::PlotBounds::new_symmetrical(0.5),
+            panel_bounds: HashMap::new(),
             current_integral: None,
+            integration_windows: Vec::new(),
+            show_integration_table: false,
             current_annotation: Annotation::default(),
+            subtraction: Subtraction::default(),
+            measurement: Measurement::default(),
+            stack: StackLayout::default(),
+            panels: PanelLayout::default(),
+            secondary_axis: SecondaryAxis::default(),
+            inset: None,
+            selecting_inset: false,
+            normalisation: Normalisation::default(),
+            display_units: (None, None),
             mode: PlotterMode::Display,
-            // TODO: make this a global option
-            integrate_with_local_baseline: true,
+            integration_method: IntegrationMethod::default(),
             request_plot_bounds: None,
             auto_shift_after_scaling: false,
         }
     }
 }
 
+/// State of the interactive subtraction in `PlotterMode::Subtract`.
+#[derive(Default)]
+struct Subtraction {
+    minuend: Option<FileID>,
+    subtrahend: Option<FileID>,
+    /// The derived file holding the difference spectrum.
+    result: Option<FileID>,
+    /// The x-window in which the factor is fitted automatically.
+    window: Option<(f64, f64)>,
+    fit_error: Option<String>,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum PlotterMode {
     Display,
     Integrate,
     Annotage,
+    Subtract,
+    Measure,
 }
 
 impl PlotterMode {
@@ -50,7 +123,9 @@ impl PlotterMode {
         match self {
             PlotterMode::Display => PlotterMode::Integrate,
             PlotterMode::Integrate => PlotterMode::Annotage,
-            PlotterMode::Annotage => PlotterMode::Display,
+            PlotterMode::Annotage => PlotterMode::Subtract,
+            PlotterMode::Subtract => PlotterMode::Measure,
+            PlotterMode::Measure => PlotterMode::Display,
         }
     }
 }
@@ -0,0 +1,40 @@
+//! Multi-panel display: each plotted group is shown in its own panel, the
+//! panels are stacked vertically and share the x-axis.
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::components::FileHandler;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PanelLayout {
+    /// Show each plotted group in its own panel.
+    pub per_group: bool,
+    /// Let each panel choose its y-range, instead of sharing it.
+    pub independent_y: bool,
+}
+
+impl PanelLayout {
+    /// The panels to draw, given by the index of the group they show. `None`
+    /// is a single panel showing all plotted groups.
+    pub fn panels(&self, file_handler: &FileHandler) -> Vec<Option<usize>> {
+        let groups: Vec<Option<usize>> = file_handler
+            .groups
+            .iter()
+            .enumerate()
+            .filter(|(_, g)| g.as_ref().is_some_and(|g| g.is_plotted))
+            .filter(|(_, g)| {
+                // Panels of groups without data would stay empty.
+                g.iter()
+                    .flat_map(|g| g.file_ids.iter())
+                    .filter_map(|fid| file_handler.registry.get(fid))
+                    .any(|file| file.get_cache().is_some())
+            })
+            .map(|(gid, _)| Some(gid))
+            .collect();
+        if !self.per_group || groups.is_empty() {
+            vec![None]
+        } else {
+            groups
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Logarithmic and reversed axes. egui_plot only draws linear axes, so the
+//! data is mapped into plot coordinates for drawing, and the grid marks are
+//! mapped back for their labels.
+
+use egui_plot::{GridInput, GridMark, PlotBounds};
+use serde::{Deserialize, Serialize};
+
+use super::secondary::YTransform;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AxisScale {
+    /// Logarithmic (base 10) axis. Non-positive values are not drawn.
+    pub log: bool,
+    /// The axis decreases to the right (or to the top).
+    pub reversed: bool,
+}
+
+impl AxisScale {
+    pub fn is_linear(&self) -> bool {
+        !self.log && !self.reversed
+    }
+
+    /// Map the data value `z` into plot coordinates.
+    pub fn to_plot(self, z: f64) -> f64 {
+        let z = if self.log { z.log10() } else { z };
+        if self.reversed { -z } else { z }
+    }
+
+    /// Map the plot coordinate `z` back to a data value.
+    pub fn to_data(self, z: f64) -> f64 {
+        let z = if self.reversed { -z } else { z };
+        if self.log { 10f64.powf(z) } else { z }
+    }
+
+    /// The label of the grid mark at the plot coordinate `value`, which are
+    /// spaced by `step` (in plot coordinates).
+    pub fn format_tick(&self, value: f64, step: f64) -> String {
+        let z = self.to_data(value);
+        if self.log {
+            svg_export::format_log_tick(z)
+        } else {
+            format_tick(z, step)
+        }
+    }
+
+    /// Grid marks of a logarithmic axis: one for each decade and, if only a
+    /// few decades are visible, for the multiples in between.
+    pub fn log_grid_marks(&self, input: GridInput) -> Vec<GridMark> {
+        // The visible decades, rounded outwards.
+        let (lo, hi) = {
+            let (a, b) = if self.reversed {
+                (-input.bounds.1, -input.bounds.0)
+            } else {
+                input.bounds
+            };
+            (a.floor(), b.ceil())
+        };
+        // Avoid drawing an excessive number of lines when zoomed out.
+        if !(lo.is_finite() && hi.is_finite()) || hi - lo > 300.0 {
+            return Vec::new();
+        }
+        let sign = if self.reversed { -1.0 } else { 1.0 };
+        let mut marks = Vec::new();
+        let mut decade = lo;
+        while decade <= hi {
+            marks.push(GridMark {
+                value: sign * decade,
+                step_size: 1.0,
+            });
+            if hi - lo <= 6.0 {
+                for multiple in 2..=9 {
+                    marks.push(GridMark {
+                        value: sign * (decade + (multiple as f64).log10()),
+                        step_size: 0.1,
+                    });
+                }
+            }
+            decade += 1.0;
+        }
+        marks
+    }
+}
+
+impl From<AxisScale> for svg_export::Scale {
+    fn from(scale: AxisScale) -> Self {
+        Self {
+            log: scale.log,
+            reversed: scale.reversed,
+        }
+    }
+}
+
+/// Scales of both axes of the plot.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlotScales {
+    pub x: AxisScale,
+    pub y: AxisScale,
+}
+
+impl PlotScales {
+    pub fn is_linear(&self) -> bool {
+        self.x.is_linear() && self.y.is_linear()
+    }
+
+    pub fn to_plot(self, [x, y]: [f64; 2]) -> [f64; 2] {
+        [self.x.to_plot(x), self.y.to_plot(y)]
+    }
+
+    pub fn to_data(self, [x, y]: [f64; 2]) -> [f64; 2] {
+        [self.x.to_data(x), self.y.to_data(y)]
+    }
+
+    /// The bounds of the plot in data coordinates.
+    pub fn bounds_to_data(self, bounds: PlotBounds) -> PlotBounds {
+        ordered_bounds(self.to_data(bounds.min()), self.to_data(bounds.max()))
+    }
+
+    /// The bounds in data coordinates `bounds` in plot coordinates.
+    pub fn bounds_to_plot(self, bounds: PlotBounds) -> PlotBounds {
+        ordered_bounds(self.to_plot(bounds.min()), self.to_plot(bounds.max()))
+    }
+}
+
+/// Mapping of the data of a file into plot coordinates: its y-transform
+/// (stacking offsets, or the mapping onto the secondary axis), followed by
+/// the scales of the axes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FileTransform {
+    pub y: YTransform,
+    pub scales: PlotScales,
+}
+
+impl FileTransform {
+    pub fn is_identity(&self) -> bool {
+        self.y == YTransform::IDENTITY && self.scales.is_linear()
+    }
+
+    pub fn to_plot(self, [x, y]: [f64; 2]) -> [f64; 2] {
+        self.scales.to_plot([x, self.y.apply(y)])
+    }
+}
+
+fn ordered_bounds([x0, y0]: [f64; 2], [x1, y1]: [f64; 2]) -> PlotBounds {
+    PlotBounds::from_min_max([x0.min(x1), y0.min(y1)], [x0.max(x1), y0.max(y1)])
+}
+
+/// Format the label of a tick at `value`, with a precision suitable for
+/// ticks spaced by `step`.
+pub fn format_tick(value: f64, step: f64) -> String {
+    let decimals = (-step.abs().log10().floor()).clamp(0.0, 15.0) as usize;
+    format!("{value:.decimals$}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_axis_scale() {
+        let scale = AxisScale {
+            log: true,
+            reversed: true,
+        };
+        assert_eq!(scale.to_plot(100.0), -2.0);
+        assert_eq!(scale.to_data(-2.0), 100.0);
+        assert!(scale.to_plot(-1.0).is_nan());
+        let scales = PlotScales {
+            x: AxisScale {
+                log: false,
+                reversed: true,
+            },
+            y: AxisScale::default(),
+        };
+        let bounds = scales.bounds_to_plot(PlotBounds::from_min_max([400.0, 0.0], [4000.0, 1.0]));
+        assert_eq!(bounds.min(), [-4000.0, 0.0]);
+        assert_eq!(scales.bounds_to_data(bounds).max(), [4000.0, 1.0]);
+    }
+
+    #[test]
+    fn test_format_tick() {
+        assert_eq!(format_tick(250.0, 50.0), "250");
+        assert_eq!(format_tick(0.25, 0.05), "0.25");
+        assert_eq!(format_tick(1.0, 0.5), "1.0");
+    }
+
+    #[test]
+    fn test_log_grid_marks() {
+        let scale = AxisScale {
+            log: true,
+            reversed: false,
+        };
+        let input = |bounds| GridInput {
+            bounds,
+            base_step_size: 0.01,
+        };
+        let marks = scale.log_grid_marks(input((-0.5, 1.5)));
+        let decades: Vec<f64> = marks
+            .iter()
+            .filter(|mark| mark.step_size == 1.0)
+            .map(|mark| mark.value)
+            .collect();
+        assert_eq!(decades, vec![-1.0, 0.0, 1.0, 2.0]);
+        assert_eq!(marks.len(), 4 * 9);
+        // Only decades are marked when zoomed out.
+        assert_eq!(scale.log_grid_marks(input((0.0, 20.0))).len(), 21);
+    }
+}
@@ -0,0 +1,119 @@
+//! Secondary (right) y-axis: files assigned to it are drawn with their own
+//! y-range, by mapping their values onto the primary y-axis.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::components::{FileHandler, FileID};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SecondaryAxis {
+    /// Limits of the secondary y-axis. If not set, the y-range of the files
+    /// on the secondary axis is used.
+    pub limits: Option<[f64; 2]>,
+    /// Label of the secondary y-axis. If empty, the axis is labelled by the
+    /// units of its files.
+    pub label: String,
+}
+
+/// Affine map `y ↦ scale * y + shift` of y-values into plot coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct YTransform {
+    pub scale: f64,
+    pub shift: f64,
+}
+
+impl YTransform {
+    pub const IDENTITY: YTransform = YTransform {
+        scale: 1.0,
+        shift: 0.0,
+    };
+
+    /// The transform mapping `from[0]` onto `to[0]` and `from[1]` onto
+    /// `to[1]`. `None`, if `from` is empty or not finite.
+    pub fn between(from: [f64; 2], to: [f64; 2]) -> Option<Self> {
+        let scale = (to[1] - to[0]) / (from[1] - from[0]);
+        let shift = to[0] - scale * from[0];
+        (scale.is_finite() && scale != 0.0 && shift.is_finite()).then_some(Self { scale, shift })
+    }
+
+    pub fn apply(&self, y: f64) -> f64 {
+        self.scale * y + self.shift
+    }
+
+    pub fn invert(&self, y: f64) -> f64 {
+        (y - self.shift) / self.scale
+    }
+
+    /// This transform applied after shifting by `offset`.
+    pub fn with_offset(&self, offset: f64) -> Self {
+        Self {
+            scale: self.scale,
+            shift: self.shift + self.scale * offset,
+        }
+    }
+}
+
+impl SecondaryAxis {
+    /// The transform of the files on the secondary y-axis onto the primary
+    /// y-axis: the limits of the secondary axis are mapped onto the y-range
+    /// of the files on the primary axis. `None`, if no file with data is
+    /// plotted on the secondary axis.
+    pub fn transform(
+        &self,
+        file_handler: &FileHandler,
+        offsets: &HashMap<FileID, f64>,
+    ) -> Option<YTransform> {
+        let range = |secondary: bool| {
+            file_handler
+                .groups
+                .iter()
+                .filter_map(|g| g.as_ref().filter(|g| g.is_plotted))
+                .flat_map(|g| g.file_ids.iter())
+                .filter_map(|fid| Some((fid, file_handler.registry.get(fid)?)))
+                .filter(|(_, file)| file.properties.secondary_y == secondary)
+                .filter_map(|(fid, file)| {
+                    let offset = offsets.get(fid).copied().unwrap_or(0.0);
+                    Some(file.get_cache()?.iter().map(move |p| p.y + offset))
+                })
+                .flatten()
+                .filter(|y| y.is_finite())
+                .fold(None, |range: Option<[f64; 2]>, y| match range {
+                    Some([min, max]) => Some([min.min(y), max.max(y)]),
+                    None => Some([y, y]),
+                })
+        };
+        let data_range = range(true)?;
+        let limits = self.limits.unwrap_or(data_range);
+        // Without files on the primary axis, both axes are the same.
+        let primary = range(false).unwrap_or(limits);
+        Some(
+            YTransform::between(limits, primary)
+                .or_else(|| {
+                    // Flat data is centered on the primary axis.
+                    let center = (primary[0] + primary[1]) / 2.0;
+                    YTransform::between(
+                        [limits[0] - 0.5, limits[0] + 0.5],
+                        [center - 0.5, center + 0.5],
+                    )
+                })
+                .unwrap_or(YTransform::IDENTITY),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transform() {
+        let transform = YTransform::between([0.0, 10.0], [1.0, 2.0]).unwrap();
+        assert_eq!(transform.apply(5.0), 1.5);
+        assert_eq!(transform.invert(1.5), 5.0);
+        assert_eq!(transform.with_offset(10.0).apply(0.0), 2.0);
+        assert_eq!(YTransform::between([1.0, 1.0], [0.0, 1.0]), None);
+        assert_eq!(YTransform::IDENTITY.apply(3.0), 3.0);
+    }
+}
@@ -0,0 +1,179 @@
+//! Stacked (waterfall) display: the plotted files are offset vertically, so
+//! that they can be compared without overlapping.
+
+use std::collections::HashMap;
+
+use egui_plot::PlotPoint;
+use serde::{Deserialize, Serialize};
+
+use crate::app::components::{FileHandler, FileID, Interpolation, resample};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StackMode {
+    /// All files are drawn on top of each other.
+    #[default]
+    Overlay,
+    /// Each file is offset from the previous one.
+    Files,
+    /// The files of each group are offset from the previous group.
+    Groups,
+}
+
+impl StackMode {
+    pub const ALL: [StackMode; 3] = [StackMode::Overlay, StackMode::Files, StackMode::Groups];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StackMode::Overlay => "Overlay",
+            StackMode::Files => "Stack Files",
+            StackMode::Groups => "Stack Groups",
+        }
+    }
+}
+
+/// Vertical layout of the plotted files, which is applied on top of the
+/// offsets set for each file.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StackLayout {
+    pub mode: StackMode,
+    /// Distance between neighbouring traces, relative to the largest y-range
+    /// of the plotted files.
+    pub spacing: f64,
+    /// Draw the name of each trace at the right edge of the plot.
+    pub labels: bool,
+}
+
+impl Default for StackLayout {
+    fn default() -> Self {
+        Self {
+            mode: StackMode::Overlay,
+            spacing: 1.0,
+            labels: false,
+        }
+    }
+}
+
+impl StackLayout {
+    /// Vertical offsets of all plotted files. The first file (or group) stays
+    /// in place, the following ones are stacked on top of it.
+    pub fn offsets(&self, file_handler: &FileHandler) -> HashMap<FileID, f64> {
+        if self.mode == StackMode::Overlay {
+            return HashMap::new();
+        }
+        let traces: Vec<(FileID, usize, f64)> = file_handler
+            .groups
+            .iter()
+            .filter_map(|g| g.as_ref().filter(|g| g.is_plotted))
+            .enumerate()
+            .flat_map(|(group_index, g)| g.file_ids.iter().map(move |fid| (fid, group_index)))
+            .filter_map(|(fid, group_index)| {
+                let cache = file_handler.registry.get(fid)?.get_cache()?;
+                Some((*fid, group_index, y_range(cache)))
+            })
+            .collect();
+        let keys: Vec<(usize, f64)> = traces.iter().map(|(_, g, range)| (*g, *range)).collect();
+        traces
+            .iter()
+            .map(|(fid, _, _)| *fid)
+            .zip(stack_offsets(&keys, self.mode, self.spacing))
+            .collect()
+    }
+}
+
+/// Offsets of traces, given by their group index and y-range, in plotting
+/// order.
+fn stack_offsets(traces: &[(usize, f64)], mode: StackMode, spacing: f64) -> Vec<f64> {
+    let step = spacing
+        * traces
+            .iter()
+            .map(|(_, range)| *range)
+            .filter(|range| range.is_finite())
+            .fold(0.0, f64::max);
+    let mut level = 0;
+    traces
+        .iter()
+        .enumerate()
+        .map(|(i, (group, _))| {
+            match mode {
+                StackMode::Overlay => return 0.0,
+                StackMode::Files => level = i,
+                // Only groups with plotted files count.
+                StackMode::Groups => {
+                    if i > 0 && traces[i - 1].0 != *group {
+                        level += 1;
+                    }
+                }
+            }
+            level as f64 * step
+        })
+        .collect()
+}
+
+/// Difference of the largest and smallest finite y-value.
+fn y_range(points: &[PlotPoint]) -> f64 {
+    let (min, max) = points
+        .iter()
+        .map(|p| p.y)
+        .filter(|y| y.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), y| {
+            (min.min(y), max.max(y))
+        });
+    max - min
+}
+
+/// Position of the label of a trace at the right edge `xmax` of the plot: on
+/// the trace at `xmax`, or at its end if it does not reach that far.
+pub fn label_position(points: &[PlotPoint], xmax: f64) -> Option<[f64; 2]> {
+    let (xs, ys): (Vec<f64>, Vec<f64>) = points
+        .iter()
+        .filter(|p| p.x.is_finite() && p.y.is_finite())
+        .map(|p| (p.x, p.y))
+        .unzip();
+    if xs.is_empty() {
+        return None;
+    }
+    let (xmin_data, xmax_data) = xs
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+            (min.min(*x), max.max(*x))
+        });
+    let x = xmax.clamp(xmin_data, xmax_data);
+    let y = match xs.len() {
+        1 => ys[0],
+        _ => resample(&xs, &ys, &[x], Interpolation::Linear)[0],
+    };
+    Some([x, y])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stack_offsets() {
+        let traces = [(0, 1.0), (0, 2.0), (2, 0.5), (3, f64::NAN)];
+        assert_eq!(
+            stack_offsets(&traces, StackMode::Files, 1.5),
+            vec![0.0, 3.0, 6.0, 9.0]
+        );
+        assert_eq!(
+            stack_offsets(&traces, StackMode::Groups, 1.0),
+            vec![0.0, 0.0, 2.0, 4.0]
+        );
+        assert_eq!(
+            stack_offsets(&traces, StackMode::Overlay, 1.0),
+            vec![0.0; 4]
+        );
+    }
+
+    #[test]
+    fn test_label_position() {
+        let points: Vec<PlotPoint> = [[0.0, 0.0], [2.0, 4.0], [1.0, 1.0]]
+            .into_iter()
+            .map(PlotPoint::from)
+            .collect();
+        assert_eq!(label_position(&points, 1.5), Some([1.5, 2.5]));
+        assert_eq!(label_position(&points, 5.0), Some([2.0, 4.0]));
+        assert_eq!(label_position(&[], 5.0), None);
+    }
+}
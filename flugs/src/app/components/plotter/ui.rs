@@ -1,15 +1,34 @@
 use egui::Vec2;
-use egui_plot::{Legend, PlotBounds, PlotPoint, PlotPoints};
+use egui_plot::{AxisHints, HPlacement, Legend, PlotBounds, PlotPoint, PlotPoints};
+use svg_export::markup::to_unicode;
 
-use crate::app::{
-    components::{File, FileHandler, FileID},
-    events::{EventQueue, ManipulateFile, RefreshCache},
+use crate::{
+    app::{
+        components::{
+            Baseline, BaselineData, File, FileHandler, FileID, IntegrationRule, Operation, Recipe,
+            unit_combobox,
+        },
+        events::{
+            EvaluateRecipe, EventQueue, ExportIntegrationTable, ManipulateFile, RefreshCache,
+            TuneDifferenceFactor,
+        },
+    },
+    backend_state::{XUnit, YUnit},
+};
+
+use super::{
+    Inset, InsetCorner, IntegrationWindow, StackMode,
+    measure::{Measurement, nearest_sample},
+    scale::{AxisScale, FileTransform, PlotScales, format_tick},
+    secondary::YTransform,
+    stack::label_position,
 };
 
 impl super::Plotter {
     pub fn render(
         &mut self,
         file_handler: &mut FileHandler,
+        axis_scales: &mut PlotScales,
         event_queue: &mut EventQueue<crate::EguiApp>,
         ui: &mut egui::Ui,
         ctx: &egui::Context,
@@ -21,23 +40,124 @@ impl super::Plotter {
             }
         });
 
+        if self.mode == super::PlotterMode::Measure {
+            self.measurement_panel(file_handler, ui);
+        }
+
         // These are needed to apply modifications to the selected file.
         let mut spans = (0.0, 0.0);
         let mut drag = Vec2::default();
 
-        let allow_drag = self.selected_fid.is_none() && self.mode != super::PlotterMode::Integrate;
+        let allow_drag = self.selected_fid.is_none()
+            && !self.selecting_inset
+            && !matches!(
+                self.mode,
+                super::PlotterMode::Integrate | super::PlotterMode::Subtract
+            );
+
+        let (x_unit, y_unit) = file_handler.plotted_units();
+        self.palette_colors = file_handler.palette_colors();
+        self.file_colors = file_handler.with_custom_colors(self.palette_colors.clone());
+        // The data is drawn in plot coordinates, which differ from the data
+        // coordinates for logarithmic or reversed axes.
+        let scales = *axis_scales;
+        let offsets = self.stack.offsets(file_handler);
+        let secondary = self.secondary_axis.transform(file_handler, &offsets);
+        // Transforms of the y-values of each file onto the primary y-axis.
+        let transform = |fid: &FileID, file: &File| {
+            let offset = offsets.get(fid).copied().unwrap_or(0.0);
+            match secondary {
+                Some(secondary) if file.properties.secondary_y => secondary.with_offset(offset),
+                _ => YTransform::IDENTITY.with_offset(offset),
+            }
+        };
+        let y2_label = match (
+            self.secondary_axis.label.is_empty(),
+            file_handler.secondary_y_unit(),
+        ) {
+            (true, Some(unit)) => unit.axis_label(),
+            _ => self.secondary_axis.label.clone(),
+        };
+
+        // One panel for all groups, or one panel per group sharing the
+        // x-axis.
+        let panels = self.panels.panels(file_handler);
+        let panel_height =
+            ui.available_height() / panels.len() as f32 - ui.spacing().item_spacing.y;
+        let requested_bounds = self.request_plot_bounds.take();
+        let inset_panel = self.inset.map(|inset| inset.shown_in(&panels));
+        let mut plot_clicked = false;
+        let mut hovered_plot_item = None;
 
         self.files_plot_ids.drain();
-        let response = egui_plot::Plot::new("Plot")
-            .allow_drag(allow_drag)
-            .auto_bounds([true, true])
-            .legend(Legend::default())
-            .show(ui, |plot_ui| {
-                if let Some(bounds) = self.request_plot_bounds.take() {
-                    plot_ui.set_plot_bounds(PlotBounds::from_min_max(
+        self.panel_bounds.clear();
+        for (i, panel) in panels.iter().copied().enumerate() {
+            // Only the lowest panel is labelled with the x-unit.
+            let x_label = match (i + 1 == panels.len(), x_unit) {
+                (true, Some(unit)) => unit.axis_label(),
+                _ => String::new(),
+            };
+            let y_label = y_unit.map(|unit| unit.axis_label()).unwrap_or_default();
+            let mut plot = egui_plot::Plot::new(("Plot", panel))
+                .id(plot_id(panel))
+                .x_axis_label(to_unicode(&x_label))
+                .y_axis_label(to_unicode(&y_label))
+                .allow_drag(allow_drag)
+                .auto_bounds([true, true])
+                .legend(Legend::default());
+            if panel.is_some() {
+                plot = plot
+                    .height(panel_height)
+                    .link_axis("plot_panels", [true, !self.panels.independent_y])
+                    .link_cursor("plot_panels", [true, false]);
+            }
+            if !scales.x.is_linear() {
+                plot = plot.x_axis_formatter(move |mark, _| {
+                    scales.x.format_tick(mark.value, mark.step_size)
+                });
+            }
+            if !scales.y.is_linear() {
+                plot = plot.y_axis_formatter(move |mark, _| {
+                    scales.y.format_tick(mark.value, mark.step_size)
+                });
+            }
+            if scales.x.log {
+                plot = plot.x_grid_spacer(move |input| scales.x.log_grid_marks(input));
+            }
+            if scales.y.log {
+                plot = plot.y_grid_spacer(move |input| scales.y.log_grid_marks(input));
+            }
+            // The right axis shows the values of the secondary files at the
+            // grid marks of the primary axis.
+            if let Some(secondary) = secondary {
+                let mut primary = AxisHints::new_y().label(to_unicode(&y_label));
+                if !scales.y.is_linear() {
+                    primary = primary
+                        .formatter(move |mark, _| scales.y.format_tick(mark.value, mark.step_size));
+                }
+                plot = plot.custom_y_axes(vec![
+                    primary,
+                    AxisHints::new_y()
+                        .label(to_unicode(&y2_label))
+                        .placement(HPlacement::Right)
+                        .formatter(move |mark, _| {
+                            let y2 = secondary.invert(scales.y.to_data(mark.value));
+                            // About three significant digits on log axes.
+                            let step = if scales.y.log {
+                                y2.abs() / 100.0
+                            } else {
+                                mark.step_size / secondary.scale
+                            };
+                            format_tick(y2, step)
+                        }),
+                ]);
+            }
+            let response = plot.show(ui, |plot_ui| {
+                if let Some(bounds) = requested_bounds {
+                    plot_ui.set_plot_bounds(scales.bounds_to_plot(PlotBounds::from_min_max(
                         [bounds[0], bounds[2]],
                         [bounds[1], bounds[3]],
-                    ));
+                    )));
                 }
 
                 // Context menu, based on current mode.
@@ -47,13 +167,15 @@ impl super::Plotter {
                         if let Some((fid, file)) = self.selected_fid.and_then(|fid| {
                             file_handler.registry.get_mut(&fid).map(|file| (fid, file))
                         }) {
+                            let palette_color =
+                                self.palette_colors.get(&fid).copied().unwrap_or_default();
                             plot_ui.response().context_menu(|ui| {
-                                file.render_property_settings(ui, fid, event_queue)
+                                file.render_property_settings(ui, fid, palette_color, event_queue)
                             });
                         } else {
-                            plot_ui
-                                .response()
-                                .context_menu(|ui| self.display_menu(file_handler, ui));
+                            plot_ui.response().context_menu(|ui| {
+                                self.display_menu(file_handler, axis_scales, ui)
+                            });
                         }
                     }
                     // In integrate mode, we show the integrate menu.
@@ -66,57 +188,123 @@ impl super::Plotter {
                         let resp = plot_ui.response();
                         if resp.secondary_clicked() {
                             if let Some(pos) = ctx.input(|i| i.pointer.latest_pos()) {
-                                let [x, y] = plot_ui.plot_from_screen(pos).to_vec2().into();
-                                self.current_annotation.x = x;
-                                self.current_annotation.y = y;
+                                let pos = plot_ui.plot_from_screen(pos);
+                                let [x, y] = scales.to_data([pos.x, pos.y]);
+                                // Annotations are placed in the coordinates of
+                                // the data of the selected file.
+                                let y = match self
+                                    .selected_fid
+                                    .and_then(|fid| Some((fid, file_handler.registry.get(&fid)?)))
+                                {
+                                    Some((fid, file)) => transform(&fid, file).invert(y),
+                                    None => y,
+                                };
+                                self.current_annotation.x = x as f32;
+                                self.current_annotation.y = y as f32;
                             }
                         }
                         resp.context_menu(|ui| self.annotate_menu(file_handler, ui));
                     }
+                    super::PlotterMode::Subtract => {
+                        plot_ui
+                            .response()
+                            .context_menu(|ui| self.subtract_menu(file_handler, event_queue, ui));
+                    }
+                    super::PlotterMode::Measure => {
+                        plot_ui.response().context_menu(|ui| {
+                            if ui.button("Clear Ruler").clicked() {
+                                self.measurement.ruler = [None, None];
+                            }
+                        });
+                    }
                 }
 
                 // Plot integration region, if intgrate mode is active.
                 if let super::PlotterMode::Integrate = self.mode {
-                    if let Some((xmin, xmax)) = self.current_integral {
-                        let y = plot_ui.plot_bounds().center().y;
-                        plot_ui.line(
-                            egui_plot::Line::new("".to_string(), vec![[xmin, y], [xmax, y]])
-                                .color(egui::Color32::RED)
-                                .width(3.0),
-                        );
+                    if let Some(window) = self.current_integral {
+                        draw_x_window(plot_ui, window, scales.x);
+                    }
+                    for window in self.integration_windows.iter() {
+                        draw_named_x_window(plot_ui, window, scales.x);
                     }
-
                     // Handle mouse clicks (draging integral area).
-                    //
-                    // Reading this before the if statement is required to avoid a dead lock.
-                    let inside_plot = pointer_inside_plot(plot_ui);
-                    plot_ui.ctx().input(|i| {
-                        if i.pointer.button_down(egui::PointerButton::Primary)
-                            && plot_ui.response().contains_pointer()
-                            && inside_plot
-                        {
-                            if let (Some(origin), Some(current_position)) =
-                                (i.pointer.press_origin(), i.pointer.latest_pos())
-                            {
-                                // Pointer positions are in screen coordinates and must be translated into
-                                // the coordinate system of the plot.
-                                let origin = plot_ui.transform().value_from_position(origin).x;
-                                let current_position =
-                                    plot_ui.transform().value_from_position(current_position).x;
-                                self.current_integral = Some((origin, current_position))
-                            }
+                    if let Some(window) = dragged_x_window(plot_ui, false, scales.x) {
+                        self.current_integral = Some(window);
+                    }
+                }
+
+                // Plot the window for fitting the subtraction factor, if
+                // subtract mode is active. Dragging with a modifier key held
+                // tunes the factor instead.
+                if let super::PlotterMode::Subtract = self.mode {
+                    if let Some(window) = self.subtraction.window {
+                        draw_x_window(plot_ui, window, scales.x);
+                    }
+                    if let Some(window) = dragged_x_window(plot_ui, true, scales.x) {
+                        self.subtraction.window = Some(window);
+                    }
+                }
+
+                // Move the crosshair, snapping to the samples of the selected
+                // file, and place the points of the ruler by clicking.
+                if let super::PlotterMode::Measure = self.mode {
+                    if let Some(pointer) = plot_ui.pointer_coordinate() {
+                        let pointer = PlotPoint::from(scales.to_data([pointer.x, pointer.y]));
+                        let snapped = self
+                            .selected_fid
+                            .and_then(|fid| Some((fid, file_handler.registry.get(&fid)?)))
+                            .and_then(|(fid, file)| {
+                                let p = nearest_sample(file.get_cache()?, pointer.x)?;
+                                Some(PlotPoint::new(p.x, transform(&fid, file).apply(p.y)))
+                            })
+                            .unwrap_or(pointer);
+                        self.measurement.cursor = Some([snapped.x, snapped.y]);
+                        if plot_ui.response().clicked() {
+                            self.measurement.click([snapped.x, snapped.y]);
                         }
-                    });
+                    }
+                    draw_measurement(plot_ui, &self.measurement, scales);
+                }
+
+                // Select the region of the inset by dragging a rectangle,
+                // keeping the placement of a previous inset.
+                if self.selecting_inset {
+                    if let Some((a, b)) = dragged_region(plot_ui, scales) {
+                        let region = Inset::new(a, b, panel);
+                        self.inset = Some(match self.inset {
+                            Some(inset) => Inset {
+                                region: region.region,
+                                panel,
+                                ..inset
+                            },
+                            None => region,
+                        });
+                    }
+                    if plot_ui.response().drag_stopped() {
+                        self.selecting_inset = false;
+                    }
+                }
+                if let Some(inset) = self.inset
+                    && inset_panel == Some(panel)
+                {
+                    draw_inset_region(plot_ui, &inset, scales);
                 }
 
                 // Plot files of currently active groups.
-                for (_, grp) in file_handler
+                let baseline = match self.mode {
+                    super::PlotterMode::Integrate => file_handler
+                        .baseline_data(&self.integration_method)
+                        .ok()
+                        .flatten(),
+                    _ => None,
+                };
+                for (gid, grp) in file_handler
                     .groups
                     .iter_mut()
                     .enumerate()
                     .filter_map(|(id, x)| Some(id).zip(x.as_mut()))
                 {
-                    if !grp.is_plotted {
+                    if !grp.is_plotted || panel.is_some_and(|panel| panel != gid) {
                         continue;
                     }
                     for fid in grp.file_ids.iter() {
@@ -125,35 +313,61 @@ impl super::Plotter {
                             .get(fid)
                             .filter(|file| file.get_cache().is_some())
                         {
-                            let egui_id = self.plot(fid, file, &grp.name, plot_ui);
+                            let transform = transform(fid, file);
+                            let egui_id = self.plot(
+                                fid,
+                                file,
+                                &grp.name,
+                                FileTransform {
+                                    y: transform,
+                                    scales,
+                                },
+                                baseline.as_ref(),
+                                plot_ui,
+                            );
                             self.files_plot_ids.insert(egui_id, *fid);
                         }
                     }
                 }
-                drag = plot_ui.pointer_coordinate_drag_delta();
+                // Only the dragged panel has a drag delta.
+                drag += plot_ui.pointer_coordinate_drag_delta();
                 spans = {
                     let bounds = plot_ui.plot_bounds();
                     let xspan = (bounds.max()[0] - bounds.min()[0]).abs();
                     let yspan = (bounds.max()[1] - bounds.min()[1]).abs();
                     (xspan, yspan)
                 };
-                self.current_plot_bounds = plot_ui.plot_bounds();
+                self.current_plot_bounds = scales.bounds_to_data(plot_ui.plot_bounds());
+                if let Some(gid) = panel {
+                    self.panel_bounds.insert(gid, self.current_plot_bounds);
+                }
 
                 // We need to "exfiltrate" the current plot bounds
                 // and whether the plot was clicked from this closure.
                 plot_ui.response().clicked()
             });
+            plot_clicked |= response.inner;
+            hovered_plot_item = hovered_plot_item.or(response.hovered_plot_item);
+        }
+
+        // Files hidden by clicking their legend entry are not exported.
+        self.hidden_fids = panels
+            .iter()
+            .filter_map(|panel| egui_plot::PlotMemory::load(ctx, plot_id(*panel)))
+            .flat_map(|memory| memory.hidden_items)
+            .filter_map(|id| self.files_plot_ids.get(&id).copied())
+            .collect();
+
+        if self.show_integration_table {
+            self.integration_table_window(file_handler, event_queue, ctx);
+        }
 
         // Get modifier input (we need this here already, to disallow the plot
         // to be panned).
         let modifiers = ctx.input(|i| i.modifiers);
         let modifier_down = modifiers.any();
-        let plot_clicked = response.inner;
 
-        if let Some(hovered_fid) = response
-            .hovered_plot_item
-            .and_then(|id| self.files_plot_ids.get(&id))
-        {
+        if let Some(hovered_fid) = hovered_plot_item.and_then(|id| self.files_plot_ids.get(&id)) {
             // Select file, if its plot was clicked this frame.
             if plot_clicked {
                 self.selected_fid = Some(*hovered_fid);
@@ -162,13 +376,27 @@ impl super::Plotter {
             // FIXME: The context menu for file property settings disappears due to this
             // when user tries to click the UI elements
             // If we clicked somewhere and no modifier was pressed, we deselect
-            // the currently selected file.
-            if plot_clicked && !modifier_down {
+            // the currently selected file. When measuring, clicks place the
+            // ruler instead.
+            if plot_clicked && !modifier_down && self.mode != super::PlotterMode::Measure {
                 self.selected_fid = None;
             }
         }
+        // Dragging to the right (or up) decreases the values of a reversed
+        // axis.
+        if scales.x.reversed {
+            drag.x = -drag.x;
+        }
+        if scales.y.reversed {
+            drag.y = -drag.y;
+        }
         let should_modify = modifier_down && drag.length() > 0.0;
-        if let Some(fid) = self.selected_fid
+        if let (super::PlotterMode::Subtract, Some(fid)) = (&self.mode, self.subtraction.result) {
+            // In subtract mode, dragging changes the subtraction factor.
+            if should_modify {
+                event_queue.queue_event(Box::new(TuneDifferenceFactor::new(fid, drag)));
+            }
+        } else if let Some(fid) = self.selected_fid
             && should_modify
         {
             event_queue.queue_event(Box::new(ManipulateFile::new(fid, drag, modifiers)));
@@ -180,6 +408,8 @@ impl super::Plotter {
         fid: &FileID,
         file: &'a File,
         group_name: &str,
+        transform: FileTransform,
+        baseline: Option<&BaselineData>,
         plot_iu: &mut egui_plot::PlotUi<'a>,
     ) -> egui::Id {
         if let Some(data) = file.get_cache() {
@@ -196,72 +426,105 @@ impl super::Plotter {
             //     .collect();
 
             // Plot the data.
-            let color = if let Some(color) = file.properties.color {
-                color
-            } else {
-                auto_color(Into::<i32>::into(*fid))
-            };
+            let color = self.file_colors.get(fid).copied().unwrap_or_default();
             let width = if self.selected_fid.is_some_and(|sfid| sfid == *fid) {
                 2.5
             } else {
                 1.0
             };
-            let name = if file.properties.alias.is_empty() {
+            // Names are in the markup of the export, e.g. for subscripts.
+            let name = to_unicode(&if file.properties.alias.is_empty() {
                 format!("{} ({})", file.file_name(), group_name)
             } else {
                 format!("{} ({})", file.properties.alias, group_name)
-            };
+            });
             let egui_id = name.clone().into();
-            plot_iu.line(
-                egui_plot::Line::new("".to_string(), PlotPoints::Borrowed(data))
+            // Stacked files are offset on top of their own offsets, files on
+            // the secondary axis are mapped onto the primary one.
+            let points = if transform.is_identity() {
+                PlotPoints::Borrowed(data)
+            } else {
+                PlotPoints::Owned(
+                    data.iter()
+                        .map(|p| transform.to_plot([p.x, p.y]).into())
+                        .collect(),
+                )
+            };
+            let right_edge = if transform.scales.x.reversed {
+                self.current_plot_bounds.min()[0]
+            } else {
+                self.current_plot_bounds.max()[0]
+            };
+            if self.stack.labels
+                && let Some([x, y]) = label_position(data, right_edge)
+            {
+                plot_iu.text(
+                    egui_plot::Text::new("", transform.to_plot([x, y]).into(), name.clone())
+                        .color(color)
+                        .anchor(egui::Align2::RIGHT_BOTTOM),
+                );
+            }
+            // Markers share the name and id of the line, and with that its
+            // entry in the legend.
+            let properties = &file.properties;
+            let marker = properties.marker.drawn_with(properties.line_style);
+            if let Some(shape) = marker.egui() {
+                plot_iu.points(
+                    egui_plot::Points::new(
+                        name.clone(),
+                        PlotPoints::Owned(points.points().to_vec()),
+                    )
+                    .shape(shape)
                     .color(color)
-                    .width(width)
-                    .name(name)
+                    .filled(true)
+                    .radius(2.0 + width)
                     .id(egui_id),
-            );
+                );
+            }
+            if let Some(style) = properties.line_style.egui(width) {
+                plot_iu.line(
+                    egui_plot::Line::new("".to_string(), points)
+                        .color(color)
+                        .width(width)
+                        .style(style)
+                        .name(name)
+                        .id(egui_id),
+                );
+            }
 
-            if self.mode == super::PlotterMode::Integrate {
-                if let Some((xmin, xmax)) = self.current_integral {
-                    let (xmin, xmax) = (xmin.min(xmax), xmin.max(xmax));
-                    // Plot area under curve.
-                    let mut plot_data = data
-                        .iter()
-                        .filter_map(|PlotPoint { x, y }| {
-                            if x >= &xmin && x <= &xmax {
-                                Some([*x, *y])
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<[f64; 2]>>();
-                    // TODO: how to plot area under curve down to a local baseline.
-                    if let (Some([x0, y0]), Some([x1, y1])) = (plot_data.first(), plot_data.last())
-                    {
-                        // Local baseline.
-                        let line_data: Vec<_> = plot_data
-                            .iter()
-                            .rev()
-                            .map(|[x, _]| [*x, (y1 * (x - x0) + y0 * (x1 - x)) / (x1 - x0)])
-                            .collect();
-                        plot_data.extend(line_data);
-                    }
-                    plot_iu.line(
-                        egui_plot::Line::new("".to_string(), plot_data)
-                            .color(egui::Color32::WHITE)
-                            .width(width)
-                            .id(egui_id),
-                    );
-                }
+            if self.mode == super::PlotterMode::Integrate
+                && let Some(window) = self.current_integral
+                && let Some(outline) = self.integral_outline(file, window, baseline)
+            {
+                // Plot the area between the curve and the baseline.
+                let outline: Vec<[f64; 2]> = outline
+                    .into_iter()
+                    .map(|point| transform.to_plot(point))
+                    .collect();
+                plot_iu.line(
+                    egui_plot::Line::new("".to_string(), outline)
+                        .color(egui::Color32::WHITE)
+                        .width(width)
+                        .id(egui_id),
+                );
             }
 
             for anno in file.properties.annotations.iter() {
                 let txt = egui::RichText::new(&anno.text);
                 plot_iu.add(egui_plot::Text::new(
                     "",
-                    [anno.x as f64, anno.y as f64].into(),
+                    transform.to_plot([anno.x as f64, anno.y as f64]).into(),
                     // TODO: make this a global option
                     txt.size(14.0),
-                ))
+                ));
+                if anno.line {
+                    let [x, _] = transform.to_plot([anno.x as f64, anno.y as f64]);
+                    plot_iu.vline(
+                        egui_plot::VLine::new("", x)
+                            .color(egui::Color32::GRAY)
+                            .style(egui_plot::LineStyle::dashed_loose()),
+                    );
+                }
             }
 
             egui_id
@@ -275,7 +538,12 @@ impl super::Plotter {
         }
     }
 
-    pub fn display_menu(&mut self, file_handler: &mut FileHandler, ui: &mut egui::Ui) {
+    pub fn display_menu(
+        &mut self,
+        file_handler: &mut FileHandler,
+        scales: &mut PlotScales,
+        ui: &mut egui::Ui,
+    ) {
         ui.set_min_width(200.0);
         if ui.button("Reset all").clicked() {
             for fid in file_handler
@@ -294,6 +562,184 @@ impl super::Plotter {
                 };
             }
         }
+
+        ui.separator();
+        ui.heading("Layout");
+        egui::ComboBox::from_id_salt("stack_mode")
+            .selected_text(self.stack.mode.name())
+            .show_ui(ui, |ui| {
+                for mode in StackMode::ALL {
+                    ui.selectable_value(&mut self.stack.mode, mode, mode.name());
+                }
+            });
+        ui.add_enabled_ui(self.stack.mode != StackMode::Overlay, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Spacing");
+                ui.add(
+                    egui::DragValue::new(&mut self.stack.spacing)
+                        .speed(0.01)
+                        .range(0.0..=f64::INFINITY),
+                )
+                .on_hover_text("relative to the largest y-range of the plotted files");
+            });
+        });
+        ui.checkbox(&mut self.stack.labels, "Label traces");
+        ui.checkbox(&mut self.panels.per_group, "One panel per group");
+        ui.add_enabled(
+            self.panels.per_group,
+            egui::Checkbox::new(&mut self.panels.independent_y, "Independent y-ranges"),
+        );
+
+        ui.separator();
+        ui.heading("Axes");
+        let mut changed = false;
+        egui::Grid::new("plotter_axis_scales").show(ui, |ui| {
+            for (name, scale) in [("X", &mut scales.x), ("Y", &mut scales.y)] {
+                ui.label(name);
+                changed |= ui.checkbox(&mut scale.log, "Logarithmic").changed();
+                changed |= ui.checkbox(&mut scale.reversed, "Reversed").changed();
+                ui.end_row();
+            }
+        });
+        if changed {
+            // Keep showing the same data range.
+            let ([xmin, ymin], [xmax, ymax]) = (
+                self.current_plot_bounds.min(),
+                self.current_plot_bounds.max(),
+            );
+            self.apply_bounds([xmin, xmax, ymin, ymax]);
+        }
+
+        ui.separator();
+        ui.heading("Inset");
+        if self.selecting_inset {
+            ui.label("Drag a rectangle in the plot");
+        } else if ui
+            .button(if self.inset.is_some() {
+                "Select New Region"
+            } else {
+                "Select Region"
+            })
+            .on_hover_text("the region is magnified in an inset of exported figures")
+            .clicked()
+        {
+            self.selecting_inset = true;
+            ui.close_menu();
+        }
+        if let Some(inset) = self.inset.as_mut() {
+            egui::ComboBox::from_id_salt("inset_corner")
+                .selected_text(inset.corner.name())
+                .show_ui(ui, |ui| {
+                    for corner in InsetCorner::ALL {
+                        ui.selectable_value(&mut inset.corner, corner, corner.name());
+                    }
+                });
+            ui.horizontal(|ui| {
+                ui.label("Size");
+                ui.add(
+                    egui::DragValue::new(&mut inset.size)
+                        .speed(0.01)
+                        .range(0.1..=0.9),
+                )
+                .on_hover_text("relative to the main axis");
+            });
+            if ui.button("Remove Inset").clicked() {
+                self.inset = None;
+            }
+        }
+
+        ui.separator();
+        ui.heading("Right Y-Axis");
+        ui.horizontal(|ui| {
+            let label = ui.label("Label");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.secondary_axis.label).hint_text("from units"),
+            )
+            .labelled_by(label.id)
+            .on_hover_text("^{...} raises, _{...} lowers text, \\alpha inserts α");
+        });
+        let mut fixed_limits = self.secondary_axis.limits.is_some();
+        if ui.checkbox(&mut fixed_limits, "Fixed limits").changed() {
+            // Start from the currently visible range of the axis.
+            self.secondary_axis.limits = fixed_limits.then(|| {
+                let transform = self
+                    .secondary_axis
+                    .transform(file_handler, &self.stack.offsets(file_handler))
+                    .unwrap_or(YTransform::IDENTITY);
+                let bounds = self.current_plot_bounds;
+                [
+                    transform.invert(bounds.min()[1]),
+                    transform.invert(bounds.max()[1]),
+                ]
+            });
+        }
+        if let Some([min, max]) = self.secondary_axis.limits.as_mut() {
+            ui.horizontal(|ui| {
+                ui.label("from");
+                ui.add(egui::DragValue::new(min).speed(0.01));
+                ui.label("to");
+                ui.add(egui::DragValue::new(max).speed(0.01));
+            });
+        }
+
+        ui.separator();
+        ui.heading("Units");
+        let (x_unit, y_unit) = &mut self.display_units;
+        egui::Grid::new("plotter_units").show(ui, |ui| {
+            ui.label("X-Unit");
+            unit_combobox(ui, "plotter_x_unit", x_unit, XUnit::Unknown);
+            ui.end_row();
+            ui.label("Y-Unit");
+            unit_combobox(ui, "plotter_y_unit", y_unit, YUnit::Unknown);
+            ui.end_row();
+        });
+        if ui
+            .button("Convert all")
+            .on_hover_ui(|ui| {
+                ui.label("display all currently plotted datasets in these units");
+            })
+            .clicked()
+        {
+            for fid in file_handler
+                .groups
+                .iter()
+                .filter_map(|g| g.as_ref().filter(|g| g.is_plotted))
+                .flat_map(|g| g.file_ids.iter())
+            {
+                if let Some(file) = file_handler.registry.get_mut(fid) {
+                    file.properties.units.display_x = self.display_units.0;
+                    file.properties.units.display_y = self.display_units.1;
+                    file.refresh_cache();
+                };
+            }
+        }
+
+        ui.separator();
+        ui.heading("Normalise");
+        let ([xmin, _], [xmax, _]) = (
+            self.current_plot_bounds.min(),
+            self.current_plot_bounds.max(),
+        );
+        self.normalisation.render(ui, "plotter", (xmin, xmax));
+        if ui
+            .button("Apply to all")
+            .on_hover_ui(|ui| {
+                ui.label("normalise all currently plotted datasets");
+            })
+            .clicked()
+        {
+            for fid in file_handler
+                .groups
+                .iter()
+                .filter_map(|g| g.as_ref().filter(|g| g.is_plotted))
+                .flat_map(|g| g.file_ids.iter())
+            {
+                if let Some(file) = file_handler.registry.get_mut(fid) {
+                    file.properties.normalisation = self.normalisation;
+                    file.refresh_cache();
+                };
+            }
+        }
     }
 
     pub fn integrate_menu(
@@ -311,11 +757,8 @@ impl super::Plotter {
         if ui.button("Reset").clicked() {
             self.current_integral = None
         }
-        // Baseline handling.
-        ui.checkbox(
-            &mut self.integrate_with_local_baseline,
-            "Use local baseline?",
-        );
+        // Integration method and baseline handling.
+        self.integration_method_settings(file_handler, ui);
         ui.checkbox(
             &mut self.auto_shift_after_scaling,
             "Align curves after scaling?",
@@ -338,6 +781,10 @@ impl super::Plotter {
             // UI to trigger scaling by integral.
             ui.heading("Scale by Integral");
 
+            let method = self.integration_method;
+            let baseline = file_handler.baseline_data(&method);
+            let method_description = file_handler.describe_method(&method);
+
             // Button to scale all currently shown spectra on their integrals.
             let scale_all = ui
                 .button("All")
@@ -369,20 +816,33 @@ impl super::Plotter {
                         };
                         ui.horizontal(|ui| {
                             let scale_button = egui::Button::new(label_text.clone()).truncate();
-                            let area = file.integrate(*a, *b, self.integrate_with_local_baseline);
-                            if !area.is_finite() {
-                                return;
-                            }
+                            // Refuse to scale if the area cannot be inverted.
+                            let area = match &baseline {
+                                Ok(baseline) => file.integrate(*a, *b, &method, baseline.as_ref()),
+                                Err(err) => Err(err.clone()),
+                            };
+                            let area = match area {
+                                Ok(area) => area,
+                                Err(err) => {
+                                    ui.add_enabled(false, scale_button);
+                                    ui.colored_label(egui::Color32::RED, err.to_string());
+                                    return;
+                                }
+                            };
                             if scale_all
                                 || ui
                                     .add(scale_button)
                                     .on_hover_ui(|ui| {
                                         ui.label(label_text);
                                         ui.label(format!("(area = {area})"));
+                                        ui.weak(&method_description);
                                     })
                                     .clicked()
                             {
-                                // Scale by inverse of area.
+                                // Scale by inverse of area. The area is
+                                // that of the raw data, so a normalisation
+                                // would be applied on top of the scaling.
+                                file.properties.normalisation = Default::default();
                                 file.properties.yscale = 1.0 / area;
                                 // Optionally shift curve to make all plots align automatically.
                                 if self.auto_shift_after_scaling {
@@ -409,7 +869,227 @@ impl super::Plotter {
         } else if ui.button("New Region").clicked() {
             self.current_integral = Some((0.0, 0.0));
         }
+
+        ui.separator();
+        self.integration_windows_menu(ui);
+    }
+
+    /// Choose the integration rule, the baseline and whether the signed or
+    /// absolute area is calculated.
+    fn integration_method_settings(&mut self, file_handler: &FileHandler, ui: &mut egui::Ui) {
+        let method = &mut self.integration_method;
+        egui::Grid::new("integration_method").show(ui, |ui| {
+            ui.label("Rule");
+            egui::ComboBox::from_id_salt("integration_rule")
+                .selected_text(method.rule.name())
+                .show_ui(ui, |ui| {
+                    for rule in IntegrationRule::ALL {
+                        ui.selectable_value(&mut method.rule, rule, rule.name());
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Baseline");
+            let selected_text = match method.baseline {
+                Baseline::External(fid) => file_handler
+                    .registry
+                    .get(&fid)
+                    .map(|file| file.display_name())
+                    .unwrap_or("<missing>"),
+                other => other.name(),
+            };
+            egui::ComboBox::from_id_salt("integration_baseline")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    for baseline in [Baseline::None, Baseline::Chord, Baseline::WindowMinimum] {
+                        ui.selectable_value(&mut method.baseline, baseline, baseline.name());
+                    }
+                    ui.separator();
+                    ui.weak("External, from file");
+                    let mut files: Vec<_> = file_handler.registry.iter().collect();
+                    files.sort_by_key(|(fid, _)| **fid);
+                    for (fid, file) in files {
+                        ui.selectable_value(
+                            &mut method.baseline,
+                            Baseline::External(*fid),
+                            file.display_name(),
+                        );
+                    }
+                });
+            ui.end_row();
+        });
+        ui.checkbox(&mut method.absolute, "Absolute area")
+            .on_hover_text("Areas below the baseline add to the area instead of cancelling it");
+    }
+
+    /// Manage the named integration windows.
+    fn integration_windows_menu(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Named Windows");
+        let ([xmin, _], [xmax, _]) = (
+            self.current_plot_bounds.min(),
+            self.current_plot_bounds.max(),
+        );
+        let speed = (xmax - xmin).abs() / 500.0;
+        let mut remove = None;
+        for (i, window) in self.integration_windows.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut window.name).desired_width(100.0));
+                ui.add(egui::DragValue::new(&mut window.left).speed(speed));
+                ui.add(egui::DragValue::new(&mut window.right).speed(speed));
+                if ui.small_button("🗑").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            self.integration_windows.remove(i);
+        }
+        if let Some((left, right)) = self.current_integral
+            && ui.button("Add Current Region").clicked()
+        {
+            self.integration_windows.push(IntegrationWindow {
+                name: format!("Window {}", self.integration_windows.len() + 1),
+                left: left.min(right),
+                right: left.max(right),
+            });
+        }
+        ui.toggle_value(&mut self.show_integration_table, "Results Table");
     }
+
+    /// Window with the table of integration results of all plotted files.
+    fn integration_table_window(
+        &mut self,
+        file_handler: &FileHandler,
+        event_queue: &mut EventQueue<crate::EguiApp>,
+        ctx: &egui::Context,
+    ) {
+        let table = self.integration_table(file_handler);
+        let ratio_pairs = table.ratio_pairs();
+        egui::Window::new("Integration Results")
+            .open(&mut self.show_integration_table)
+            .show(ctx, |ui| {
+                ui.label(format!("Method: {}", table.method));
+                if ui.button("Export CSV").clicked() {
+                    log::debug!("open dialog to select csv path");
+                    let handle = std::thread::spawn(|| {
+                        rfd::FileDialog::new()
+                            .set_file_name("integrals.csv")
+                            .save_file()
+                    });
+                    event_queue.queue_event(Box::new(ExportIntegrationTable::new(Some(handle))));
+                }
+                egui::ScrollArea::both().show(ui, |ui| {
+                    egui::Grid::new("integration_table")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong("File");
+                            for name in table.window_names.iter() {
+                                ui.strong(format!("{name}: area"));
+                                ui.strong(format!("{name}: peak max"));
+                                ui.strong(format!("{name}: centroid"));
+                            }
+                            for (i, j) in ratio_pairs.iter() {
+                                ui.strong(format!(
+                                    "{} / {}",
+                                    table.window_names[*i], table.window_names[*j]
+                                ));
+                            }
+                            ui.end_row();
+
+                            for row in table.rows.iter() {
+                                ui.label(&row.name);
+                                for result in row.results.iter() {
+                                    match result {
+                                        Ok(stats) => {
+                                            ui.label(format!("{:.4}", stats.area));
+                                            ui.label(format!("{:.4}", stats.peak_max));
+                                            ui.label(format!("{:.4}", stats.centroid));
+                                        }
+                                        Err(err) => {
+                                            ui.label("–").on_hover_text(err.to_string());
+                                            ui.label("–");
+                                            ui.label("–");
+                                        }
+                                    }
+                                }
+                                for (i, j) in ratio_pairs.iter() {
+                                    match row.ratio(*i, *j) {
+                                        Some(ratio) => ui.label(format!("{ratio:.4}")),
+                                        None => ui.label("–"),
+                                    };
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+    }
+
+    /// Side panel with the readouts of the measurement tool.
+    fn measurement_panel(&mut self, file_handler: &FileHandler, ui: &mut egui::Ui) {
+        let readouts = self.measurement.readouts(file_handler);
+        egui::SidePanel::right("measurement_panel")
+            .min_width(200.0)
+            .show_inside(ui, |ui| {
+                ui.heading("Measurement");
+                ui.weak("The crosshair snaps to the selected file, click to place the ruler.");
+                ui.horizontal(|ui| {
+                    if ui.button("📋 Copy").clicked() {
+                        ui.ctx().copy_text(self.measurement.to_text(&readouts));
+                    }
+                    if ui.button("Clear Ruler").clicked() {
+                        self.measurement.ruler = [None, None];
+                    }
+                });
+                ui.separator();
+
+                egui::Grid::new("measurement_points")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        let points = std::iter::once(("Cursor", self.measurement.cursor))
+                            .chain(["P1", "P2"].into_iter().zip(self.measurement.ruler));
+                        for (name, point) in points {
+                            ui.strong(name);
+                            match point {
+                                Some([x, y]) => {
+                                    ui.label(format!("x = {x:.4}"));
+                                    ui.label(format!("y = {y:.4}"));
+                                }
+                                None => {
+                                    ui.label("–");
+                                    ui.label("–");
+                                }
+                            }
+                            ui.end_row();
+                        }
+                        if let Some((dx, dy)) = self.measurement.delta() {
+                            ui.strong("Ruler");
+                            ui.label(format!("Δx = {dx:.4}"));
+                            ui.label(format!("Δy = {dy:.4}"));
+                            ui.end_row();
+                        }
+                    });
+                ui.separator();
+
+                ui.strong("y at cursor");
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("measurement_readouts")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for readout in readouts.iter() {
+                                ui.label(&readout.name);
+                                if readout.y.is_nan() {
+                                    ui.label("–");
+                                } else {
+                                    ui.label(format!("{:.4}", readout.y));
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+    }
+
     pub fn annotate_menu(&mut self, file_handler: &mut FileHandler, ui: &mut egui::Ui) {
         ui.heading("Edit annotations");
         // New Label Ui
@@ -426,6 +1106,8 @@ impl super::Plotter {
                 ui.label("y");
                 ui.add(egui::DragValue::new(&mut self.current_annotation.y).speed(0.0));
             });
+            ui.checkbox(&mut self.current_annotation.line, "Vertical line")
+                .on_hover_text("Draw a dashed line at the x-position of the label.");
             if ui.button("Add Label").clicked() {
                 file.properties
                     .annotations
@@ -450,6 +1132,7 @@ impl super::Plotter {
             {
                 ui.label("Remove/Edit Annotation");
                 ui.text_edit_singleline(&mut annotation.text);
+                ui.checkbox(&mut annotation.line, "Vertical line");
                 if ui.button("Remove").clicked() {
                     file.properties.annotations.remove(idx);
                 }
@@ -460,11 +1143,296 @@ impl super::Plotter {
     }
 }
 
-pub fn auto_color(color_idx: i32) -> egui::Color32 {
-    // analog to egui_plot
-    let golden_ratio = (5.0_f32.sqrt() - 1.0) / 2.0; // 0.61803398875
-    let h = color_idx as f32 * golden_ratio;
-    egui::epaint::Hsva::new(h, 0.85, 0.5, 1.0).into()
+impl super::Plotter {
+    pub fn subtract_menu(
+        &mut self,
+        file_handler: &mut FileHandler,
+        event_queue: &mut EventQueue<crate::EguiApp>,
+        ui: &mut egui::Ui,
+    ) {
+        ui.set_min_width(200.0);
+        ui.heading("Subtraction");
+
+        // Forget the result if it was removed in the meantime.
+        let sub = &mut self.subtraction;
+        if sub
+            .result
+            .is_some_and(|fid| !file_handler.registry.contains_key(&fid))
+        {
+            sub.result = None;
+        }
+
+        let name = |fid: Option<FileID>| {
+            fid.and_then(|fid| file_handler.registry.get(&fid))
+                .map(|file| file.display_name().to_owned())
+                .unwrap_or_default()
+        };
+
+        let Some(fid) = sub.result else {
+            // Select minuend and subtrahend from the currently plotted files.
+            let plotted: Vec<(usize, FileID)> = file_handler
+                .groups
+                .iter()
+                .enumerate()
+                .filter_map(|(gid, grp)| Some(gid).zip(grp.as_ref().filter(|g| g.is_plotted)))
+                .flat_map(|(gid, grp)| grp.file_ids.iter().map(move |fid| (gid, *fid)))
+                .collect();
+            for (label, slot) in [
+                ("Minuend (A)", &mut sub.minuend),
+                ("Subtrahend (B)", &mut sub.subtrahend),
+            ] {
+                egui::ComboBox::from_label(label)
+                    .selected_text(name(*slot))
+                    .show_ui(ui, |ui| {
+                        for (_, fid) in plotted.iter() {
+                            ui.selectable_value(slot, Some(*fid), name(Some(*fid)));
+                        }
+                    });
+            }
+            let target_group = plotted
+                .iter()
+                .find(|(_, fid)| Some(*fid) == sub.minuend)
+                .map(|(gid, _)| *gid);
+            let can_create =
+                sub.minuend.is_some() && sub.subtrahend.is_some() && sub.minuend != sub.subtrahend;
+            if ui
+                .add_enabled(can_create, egui::Button::new("Create difference"))
+                .clicked()
+                && let (Some(a), Some(b), Some(gid)) = (sub.minuend, sub.subtrahend, target_group)
+            {
+                let recipe = Recipe {
+                    operation: Operation::Difference { factor: 1.0 },
+                    sources: vec![a, b],
+                    interpolation: Default::default(),
+                };
+                let fid = file_handler.add_derived_file(recipe, gid);
+                event_queue.queue_event(Box::new(EvaluateRecipe(fid)));
+                sub.result = Some(fid);
+                sub.fit_error = None;
+            }
+            return;
+        };
+
+        ui.label(format!("A: {}", name(sub.minuend)));
+        ui.label(format!("B: {}", name(sub.subtrahend)));
+
+        let Some(recipe) = file_handler
+            .registry
+            .get_mut(&fid)
+            .and_then(|file| file.recipe.as_mut())
+        else {
+            return;
+        };
+        let Operation::Difference { factor } = &mut recipe.operation else {
+            return;
+        };
+
+        ui.separator();
+        let slider = egui::Slider::new(factor, 0.0..=2.0)
+            .clamping(egui::SliderClamping::Never)
+            .text("k");
+        if ui
+            .add(slider)
+            .on_hover_text("hold a modifier key and drag in the plot to change k")
+            .changed()
+        {
+            event_queue.queue_event(Box::new(EvaluateRecipe(fid)));
+        }
+
+        ui.separator();
+        ui.label("Fit Window");
+        if let Some((a, b)) = sub.window.as_mut() {
+            let ([xmin, _], [xmax, _]) = (
+                self.current_plot_bounds.min(),
+                self.current_plot_bounds.max(),
+            );
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(a).speed((xmax - xmin).abs() / 500.0));
+                ui.add(egui::DragValue::new(b).speed((xmax - xmin).abs() / 500.0));
+            });
+        } else {
+            ui.label("drag in the plot to select a window");
+        }
+        if ui
+            .add_enabled(sub.window.is_some(), egui::Button::new("Auto"))
+            .on_hover_text("minimise the residual within the fit window")
+            .clicked()
+            && let Some((left, right)) = sub.window
+        {
+            let recipe = recipe.clone();
+            match recipe.fit_difference_factor(&file_handler.registry, left, right) {
+                Ok(Some(k)) => {
+                    if let Some(Recipe {
+                        operation: Operation::Difference { factor },
+                        ..
+                    }) = file_handler
+                        .registry
+                        .get_mut(&fid)
+                        .and_then(|file| file.recipe.as_mut())
+                    {
+                        *factor = k;
+                    }
+                    event_queue.queue_event(Box::new(EvaluateRecipe(fid)));
+                    sub.fit_error = None;
+                }
+                Ok(None) => (),
+                Err(err) => sub.fit_error = Some(err),
+            }
+        }
+        if let Some(err) = &sub.fit_error {
+            ui.label(egui::RichText::new(err).color(egui::Color32::RED));
+        }
+
+        ui.separator();
+        if ui.button("New Subtraction").clicked() {
+            sub.result = None;
+            sub.fit_error = None;
+        }
+    }
+}
+
+/// The id of the plot of `panel`, to access its memory.
+fn plot_id(panel: Option<usize>) -> egui::Id {
+    egui::Id::new(("Plot", panel))
+}
+
+/// Draw the crosshair and the ruler of the measurement tool.
+fn draw_measurement(
+    plot_ui: &mut egui_plot::PlotUi<'_>,
+    measurement: &Measurement,
+    scales: PlotScales,
+) {
+    let color = egui::Color32::GRAY;
+    if let Some(cursor) = measurement.cursor {
+        let [x, y] = scales.to_plot(cursor);
+        plot_ui.vline(egui_plot::VLine::new("", x).color(color));
+        plot_ui.hline(egui_plot::HLine::new("", y).color(color));
+    }
+    let points: Vec<[f64; 2]> = measurement
+        .ruler
+        .iter()
+        .flatten()
+        .map(|point| scales.to_plot(*point))
+        .collect();
+    if points.len() == 2 {
+        plot_ui.line(
+            egui_plot::Line::new("".to_string(), points.clone())
+                .color(egui::Color32::YELLOW)
+                .style(egui_plot::LineStyle::dashed_loose()),
+        );
+    }
+    plot_ui.points(
+        egui_plot::Points::new("", points)
+            .color(egui::Color32::YELLOW)
+            .radius(4.0),
+    );
+}
+
+/// Draw a horizontal bar marking an x-window (e.g. an integration window).
+fn draw_x_window(
+    plot_ui: &mut egui_plot::PlotUi<'_>,
+    (xmin, xmax): (f64, f64),
+    x_scale: AxisScale,
+) {
+    let (xmin, xmax) = (x_scale.to_plot(xmin), x_scale.to_plot(xmax));
+    let y = plot_ui.plot_bounds().center().y;
+    plot_ui.line(
+        egui_plot::Line::new("".to_string(), vec![[xmin, y], [xmax, y]])
+            .color(egui::Color32::RED)
+            .width(3.0),
+    );
+}
+
+fn draw_named_x_window(
+    plot_ui: &mut egui_plot::PlotUi<'_>,
+    window: &IntegrationWindow,
+    x_scale: AxisScale,
+) {
+    let (left, right) = (x_scale.to_plot(window.left), x_scale.to_plot(window.right));
+    let bounds = plot_ui.plot_bounds();
+    let (y, ytop) = (bounds.center().y, bounds.max()[1]);
+    let color = egui::Color32::DARK_GRAY;
+    plot_ui.line(
+        egui_plot::Line::new("".to_string(), vec![[left, y], [right, y]])
+            .color(color)
+            .width(2.0),
+    );
+    for x in [left, right] {
+        plot_ui.vline(egui_plot::VLine::new("", x).color(color));
+    }
+    plot_ui.text(
+        egui_plot::Text::new("", [0.5 * (left + right), ytop].into(), &window.name)
+            .anchor(egui::Align2::CENTER_TOP)
+            .color(color),
+    );
+}
+
+/// Draw the region magnified in the inset as a dashed rectangle.
+fn draw_inset_region(plot_ui: &mut egui_plot::PlotUi<'_>, inset: &Inset, scales: PlotScales) {
+    let points: Vec<[f64; 2]> = inset
+        .outline()
+        .iter()
+        .map(|point| scales.to_plot(*point))
+        .collect();
+    plot_ui.line(
+        egui_plot::Line::new("".to_string(), points)
+            .color(egui::Color32::GRAY)
+            .style(egui_plot::LineStyle::dashed_loose()),
+    );
+}
+
+/// Returns the x-window (in data coordinates) spanned by the primary pointer
+/// button being dragged inside the plot, if any. If `unmodified` is set,
+/// dragging while a modifier key is held is ignored.
+fn dragged_x_window(
+    plot_ui: &egui_plot::PlotUi<'_>,
+    unmodified: bool,
+    x_scale: AxisScale,
+) -> Option<(f64, f64)> {
+    let (origin, current_position) = dragged_points(plot_ui, unmodified)?;
+    Some((
+        x_scale.to_data(origin.x),
+        x_scale.to_data(current_position.x),
+    ))
+}
+
+/// Returns the corners (in data coordinates) of the rectangle spanned by the
+/// primary pointer button being dragged inside the plot, if any.
+fn dragged_region(
+    plot_ui: &egui_plot::PlotUi<'_>,
+    scales: PlotScales,
+) -> Option<([f64; 2], [f64; 2])> {
+    let (origin, current_position) = dragged_points(plot_ui, false)?;
+    Some((
+        scales.to_data([origin.x, origin.y]),
+        scales.to_data([current_position.x, current_position.y]),
+    ))
+}
+
+/// Returns the press origin and the current position (in plot coordinates)
+/// of the primary pointer button being dragged inside the plot, if any.
+fn dragged_points(
+    plot_ui: &egui_plot::PlotUi<'_>,
+    unmodified: bool,
+) -> Option<(PlotPoint, PlotPoint)> {
+    // Reading this before the input closure is required to avoid a dead lock.
+    let inside_plot = pointer_inside_plot(plot_ui);
+    plot_ui.ctx().input(|i| {
+        if !(i.pointer.button_down(egui::PointerButton::Primary)
+            && plot_ui.response().contains_pointer()
+            && inside_plot)
+            || (unmodified && i.modifiers.any())
+        {
+            return None;
+        }
+        let (origin, current_position) = (i.pointer.press_origin()?, i.pointer.latest_pos()?);
+        // Pointer positions are in screen coordinates and must be translated into
+        // the coordinate system of the plot.
+        Some((
+            plot_ui.transform().value_from_position(origin),
+            plot_ui.transform().value_from_position(current_position),
+        ))
+    })
 }
 
 fn pointer_inside_plot<'a>(plot_ui: &egui_plot::PlotUi<'a>) -> bool {
@@ -1,9 +1,13 @@
 use app_core::string_error::ErrorStringExt;
+
+use crate::app::components::{AxisScale, PlotScales};
+use crate::app::styles::{self, StylePreset};
 use std::{
     io::{Read, Write},
     path::PathBuf,
     str::FromStr,
 };
+use svg_export::{Frame, LegendPosition, SizeUnit, TickDirection};
 
 #[derive(Debug)]
 pub struct Config {
@@ -19,6 +23,61 @@ pub struct Config {
     pub num_y_minorticks: usize,
     pub draw_xaxis: bool,
     pub draw_yaxis: bool,
+    /// Logarithmic or reversed axes, both in the plotter and the export.
+    pub scales: PlotScales,
+    // Zero disables resampling of exported lines.
+    pub export_max_points: usize,
+    /// Shade the current integral and the integration windows in exported
+    /// figures.
+    pub export_integrals: bool,
+    /// The format of the last export.
+    pub export_format: ExportFormat,
+    /// Resolution of exported PNG images.
+    pub png_dpi: f64,
+    /// Placement of the legend in exported figures.
+    pub legend_position: LegendPosition,
+    /// Number of columns of the legend, zero to choose it automatically.
+    pub legend_columns: usize,
+    pub legend_frame: bool,
+    /// List the files in the legend under headers with their group names.
+    pub legend_groups: bool,
+    /// The file of the style presets, which may be shared with others.
+    pub style_file: PathBuf,
+    /// Name of the style preset of exported figures, empty to use the
+    /// preferences.
+    pub export_style: String,
+    pub style_presets: Vec<StylePreset>,
+    /// The name of the selected preset, and the text of its name field,
+    /// which is only applied if it is a valid name.
+    style_name_edit: (String, String),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Svg,
+    Png,
+    Pdf,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Svg, ExportFormat::Png, ExportFormat::Pdf];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Svg => "SVG",
+            ExportFormat::Png => "PNG",
+            ExportFormat::Pdf => "PDF",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Svg => "svg",
+            ExportFormat::Png => "png",
+            ExportFormat::Pdf => "pdf",
+        }
+    }
 }
 
 impl Default for Config {
@@ -38,14 +97,35 @@ impl Default for Config {
             y_label,
             draw_xaxis: true,
             draw_yaxis: true,
+            scales: PlotScales::default(),
             x_ticks: Default::default(),
             y_ticks: Default::default(),
             num_x_minorticks: 0,
             num_y_minorticks: 0,
+            export_max_points: 0,
+            export_integrals: false,
+            export_format: ExportFormat::default(),
+            png_dpi: 300.0,
+            legend_position: LegendPosition::default(),
+            legend_columns: 0,
+            legend_frame: false,
+            legend_groups: false,
+            style_file: default_style_file(),
+            export_style: String::new(),
+            style_presets: Vec::new(),
+            style_name_edit: Default::default(),
         }
     }
 }
 
+/// `.flugs_styles.json` in the home directory, next to the config file.
+fn default_style_file() -> PathBuf {
+    #[allow(deprecated)]
+    std::env::home_dir()
+        .unwrap_or_default()
+        .join(".flugs_styles.json")
+}
+
 impl Config {
     pub fn render(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
         ui.heading("Preferences");
@@ -68,21 +148,54 @@ impl Config {
         );
         ui.checkbox(&mut self.draw_xaxis, "Draw X-Axis");
         ui.label("X-Label");
-        ui.text_edit_singleline(&mut self.x_label);
+        // An empty label is replaced by the units of the plotted data.
+        ui.add(egui::TextEdit::singleline(&mut self.x_label).hint_text("from units"));
         ui.label("Number of Minor X-Ticks");
         ui.add(egui::DragValue::new(&mut self.num_x_minorticks).range(0..=9));
+        axis_scale_settings(ui, &mut self.scales.x);
         ui.checkbox(&mut self.draw_yaxis, "Draw Y-Axis");
         ui.label("Y-Label");
-        ui.text_edit_singleline(&mut self.y_label);
+        ui.add(egui::TextEdit::singleline(&mut self.y_label).hint_text("from units"));
         ui.label("Number of Minor Y-Ticks");
         ui.add(egui::DragValue::new(&mut self.num_y_minorticks).range(0..=9));
+        axis_scale_settings(ui, &mut self.scales.y);
+        ui.label("Maximum Number of Points per Exported Line (0 = all)");
+        ui.add(egui::DragValue::new(&mut self.export_max_points).speed(10));
+        ui.checkbox(
+            &mut self.export_integrals,
+            "Shade Integrals in Exported Plots",
+        );
+        ui.label("Resolution of Exported PNG (DPI)");
+        let dpi = ui.add(
+            egui::DragValue::new(&mut self.png_dpi)
+                .range(10.0..=1200.0)
+                .speed(10),
+        );
+        // Remembered like the export format, once dragging ends.
+        if dpi.drag_stopped() || (dpi.changed() && !dpi.dragged()) {
+            self.save_to_config_file();
+        }
+        ui.label("Legend of Exported Plots");
+        egui::ComboBox::new("legend_position", "Position")
+            .selected_text(self.legend_position.name())
+            .show_ui(ui, |ui| {
+                for position in LegendPosition::ALL {
+                    ui.selectable_value(&mut self.legend_position, position, position.name());
+                }
+            });
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut self.legend_columns).range(0..=10));
+            ui.label("Columns (0 = automatic)");
+        });
+        ui.checkbox(&mut self.legend_frame, "Frame");
+        ui.checkbox(&mut self.legend_groups, "Group Files under Group Names");
 
+        ui.separator();
+        self.style_settings(ui);
         ui.separator();
 
         if ui.button("Save to Config File").clicked() {
-            if let Err(e) = self.to_config_file() {
-                log::error!("{e}");
-            };
+            self.save_to_config_file();
         }
 
         ui.separator();
@@ -188,12 +301,84 @@ impl Config {
                         config.num_y_minorticks = num;
                     }
                 }
+                (Some("export_max_points"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<usize>() {
+                        config.export_max_points = num;
+                    }
+                }
+                (Some("export_integrals"), Some(flag)) => {
+                    config.export_integrals = flag == "true";
+                }
+                (Some("export_format"), Some(extension)) => {
+                    match ExportFormat::ALL
+                        .into_iter()
+                        .find(|format| format.extension() == extension)
+                    {
+                        Some(format) => config.export_format = format,
+                        None => log::warn!("unknown 'export_format' {extension}"),
+                    }
+                }
+                (Some("png_dpi"), Some(dpi_str)) => {
+                    if let Ok(dpi) = dpi_str.parse::<f64>() {
+                        config.png_dpi = dpi;
+                    } else {
+                        log::warn!("could not parse 'png_dpi' as number")
+                    }
+                }
+                (Some("legend_position"), Some(name)) => {
+                    match LegendPosition::ALL
+                        .into_iter()
+                        .find(|position| position.name() == name)
+                    {
+                        Some(position) => config.legend_position = position,
+                        None => log::warn!("unknown 'legend_position' {name}"),
+                    }
+                }
+                (Some("legend_columns"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<usize>() {
+                        config.legend_columns = num;
+                    } else {
+                        log::warn!("could not parse 'legend_columns' as number")
+                    }
+                }
+                (Some("legend_frame"), Some(flag)) => {
+                    config.legend_frame = flag == "true";
+                }
+                (Some("legend_groups"), Some(flag)) => {
+                    config.legend_groups = flag == "true";
+                }
+                (Some("style_file"), Some(path)) => {
+                    config.style_file = PathBuf::from(path);
+                }
+                (Some("export_style"), Some(name)) => {
+                    config.export_style = name.to_string();
+                }
+                (Some("x_log"), Some(flag)) => {
+                    config.scales.x.log = flag == "true";
+                }
+                (Some("x_reversed"), Some(flag)) => {
+                    config.scales.x.reversed = flag == "true";
+                }
+                (Some("y_log"), Some(flag)) => {
+                    config.scales.y.log = flag == "true";
+                }
+                (Some("y_reversed"), Some(flag)) => {
+                    config.scales.y.reversed = flag == "true";
+                }
                 _ => continue,
             }
         }
         Ok(config)
     }
 
+    /// Write the config file, logging errors. Used for settings that are
+    /// remembered between sessions as soon as they change.
+    pub fn save_to_config_file(&self) {
+        if let Err(e) = self.to_config_file() {
+            log::error!("{e}");
+        }
+    }
+
     fn to_config_file(&self) -> Result<(), String> {
         #[allow(deprecated)]
         let Some(config_file_path) =
@@ -234,6 +419,10 @@ impl Config {
             config_file
                 .write_all(&format!("num_y_minorticks={}\n", self.num_y_minorticks).into_bytes()),
         );
+        wrt_results
+            .push(config_file.write_all(
+                &format!("export_max_points={}\n", self.export_max_points).into_bytes(),
+            ));
         wrt_results.push(
             config_file.write_all(
                 &format!(
@@ -252,6 +441,42 @@ impl Config {
                 .into_bytes(),
             ),
         );
+        wrt_results.push(
+            config_file
+                .write_all(&format!("export_integrals={}\n", self.export_integrals).into_bytes()),
+        );
+        wrt_results.push(config_file.write_all(
+            &format!("export_format={}\n", self.export_format.extension()).into_bytes(),
+        ));
+        wrt_results
+            .push(config_file.write_all(&format!("png_dpi={}\n", self.png_dpi).into_bytes()));
+        wrt_results.push(
+            config_file.write_all(
+                &format!(
+                    "legend_position={}\nlegend_columns={}\nlegend_frame={}\nlegend_groups={}\n",
+                    self.legend_position.name(),
+                    self.legend_columns,
+                    self.legend_frame,
+                    self.legend_groups
+                )
+                .into_bytes(),
+            ),
+        );
+        wrt_results.push(
+            config_file.write_all(
+                &format!(
+                    "style_file={}\nexport_style={}\n",
+                    self.style_file.to_string_lossy(),
+                    self.export_style
+                )
+                .into_bytes(),
+            ),
+        );
+        for (axis, AxisScale { log, reversed }) in [("x", self.scales.x), ("y", self.scales.y)] {
+            wrt_results.push(config_file.write_all(
+                &format!("{axis}_log={log}\n{axis}_reversed={reversed}\n").into_bytes(),
+            ));
+        }
 
         for res in wrt_results {
             if let Err(e) = res {
@@ -263,6 +488,223 @@ impl Config {
     }
 }
 
+impl Config {
+    /// The style of exported figures: the selected preset, or the
+    /// preferences if none is selected.
+    pub fn export_preset(&self) -> StylePreset {
+        self.style_presets
+            .iter()
+            .find(|preset| preset.name == self.export_style)
+            .cloned()
+            .unwrap_or_else(|| StylePreset::from_preferences(self))
+    }
+
+    /// Load the presets of the style file, or offer the examples if there is
+    /// none yet.
+    pub fn load_style_presets(&mut self) {
+        if !self.style_file.exists() {
+            self.style_presets = StylePreset::examples();
+            return;
+        }
+        match styles::load_presets(&self.style_file) {
+            Ok(presets) => self.style_presets = presets,
+            Err(err) => log::error!("{err}"),
+        }
+    }
+
+    /// Whether `name` can be given to a style preset: it must not be empty,
+    /// the name of the preferences in menus, or the name of another preset.
+    fn is_style_name_available(&self, name: &str) -> bool {
+        !name.trim().is_empty()
+            && name != style_name("")
+            && self.style_presets.iter().all(|preset| preset.name != name)
+    }
+
+    /// The first of "Style 1", "Style 2", ... that is not taken.
+    fn new_style_name(&self) -> String {
+        (1..)
+            .map(|i| format!("Style {i}"))
+            .find(|name| self.is_style_name_available(name))
+            .unwrap_or_default()
+    }
+
+    /// Settings of the style presets, and of the selected one.
+    fn style_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label("Export Styles");
+        ui.horizontal(|ui| {
+            let mut path = self.style_file.to_string_lossy();
+            ui.text_edit_singleline(&mut path)
+                .on_hover_text("A shared file keeps the figures of a group alike.");
+            self.style_file = path.to_string().into();
+            if ui.button("Load").clicked() {
+                self.load_style_presets();
+            }
+        });
+        egui::ComboBox::new("export_style", "Style")
+            .selected_text(style_name(&self.export_style))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.export_style, String::new(), style_name(""));
+                for preset in self.style_presets.iter() {
+                    ui.selectable_value(&mut self.export_style, preset.name.clone(), &preset.name);
+                }
+            });
+        ui.horizontal(|ui| {
+            if ui
+                .button("New from Preferences")
+                .on_hover_text("Add a style with the size and legend set above.")
+                .clicked()
+            {
+                let mut preset = StylePreset::from_preferences(self);
+                preset.name = self.new_style_name();
+                self.export_style = preset.name.clone();
+                self.style_presets.push(preset);
+            }
+            if ui.button("Remove").clicked() {
+                if let Some(i) = self
+                    .style_presets
+                    .iter()
+                    .position(|preset| preset.name == self.export_style)
+                {
+                    self.style_presets.remove(i);
+                }
+                self.export_style.clear();
+            }
+            if ui.button("Save Styles").clicked()
+                && let Err(err) = styles::save_presets(&self.style_file, &self.style_presets)
+            {
+                log::error!("{err}");
+            }
+        });
+
+        let Some(index) = self
+            .style_presets
+            .iter()
+            .position(|preset| preset.name == self.export_style)
+        else {
+            return;
+        };
+        if self.style_name_edit.0 != self.export_style {
+            self.style_name_edit = (self.export_style.clone(), self.export_style.clone());
+        }
+        let mut name = std::mem::take(&mut self.style_name_edit.1);
+        let name_changed = ui
+            .horizontal(|ui| {
+                ui.label("Name");
+                let changed = ui.text_edit_singleline(&mut name).changed();
+                if name != self.export_style && !self.is_style_name_available(&name) {
+                    ui.colored_label(egui::Color32::RED, "name is empty or taken");
+                }
+                changed
+            })
+            .inner;
+        // Presets are selected by their names, which must be unique.
+        if name_changed && self.is_style_name_available(&name) {
+            self.style_presets[index].name = name.clone();
+            self.export_style = name.clone();
+            self.style_name_edit.0 = name.clone();
+        }
+        self.style_name_edit.1 = name;
+
+        let preset = &mut self.style_presets[index];
+        ui.horizontal(|ui| {
+            ui.label("Size");
+            ui.add(egui::DragValue::new(&mut preset.width).range(1.0..=10000.0));
+            ui.label("x");
+            ui.add(egui::DragValue::new(&mut preset.height).range(1.0..=10000.0));
+            egui::ComboBox::from_id_salt("style_unit")
+                .width(40.0)
+                .selected_text(preset.unit.name())
+                .show_ui(ui, |ui| {
+                    for unit in SizeUnit::ALL {
+                        ui.selectable_value(&mut preset.unit, unit, unit.name());
+                    }
+                });
+            ui.add(
+                egui::DragValue::new(&mut preset.dpi)
+                    .range(10.0..=1200.0)
+                    .suffix(" DPI"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Font (SVG only)");
+            ui.text_edit_singleline(&mut preset.font_family)
+                .on_hover_text("PDF exports use Helvetica and PNG exports Ubuntu Light.");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Font Sizes (pt): Labels");
+            ui.add(egui::DragValue::new(&mut preset.label_size).range(4.0..=48.0));
+            ui.label("Ticks");
+            ui.add(egui::DragValue::new(&mut preset.tick_label_size).range(4.0..=48.0));
+            ui.label("Legend");
+            ui.add(egui::DragValue::new(&mut preset.legend_size).range(4.0..=48.0));
+        });
+        ui.horizontal(|ui| {
+            egui::ComboBox::new("style_ticks", "Ticks")
+                .selected_text(preset.tick_direction.name())
+                .show_ui(ui, |ui| {
+                    for direction in TickDirection::ALL {
+                        ui.selectable_value(
+                            &mut preset.tick_direction,
+                            direction,
+                            direction.name(),
+                        );
+                    }
+                });
+            ui.add(egui::DragValue::new(&mut preset.tick_length).range(0.0..=20.0))
+                .on_hover_text("Length of the ticks in pixels, 0 to scale them with the axis.");
+        });
+        egui::ComboBox::new("style_frame", "Frame")
+            .selected_text(preset.frame.name())
+            .show_ui(ui, |ui| {
+                for frame in Frame::ALL {
+                    ui.selectable_value(&mut preset.frame, frame, frame.name());
+                }
+            });
+        ui.horizontal(|ui| {
+            ui.label("Width of Plot Lines");
+            ui.add(
+                egui::DragValue::new(&mut preset.linewidth)
+                    .range(0.25..=5.0)
+                    .speed(0.25),
+            );
+        });
+        ui.label("Color Cycle");
+        let mut colors = preset.color_cycle.join(" ");
+        if ui
+            .add(egui::TextEdit::singleline(&mut colors).hint_text("colors of the plotter"))
+            .on_hover_text("Colors like #0072b2, separated by spaces.")
+            .changed()
+        {
+            preset.color_cycle = colors.split_whitespace().map(str::to_string).collect();
+        }
+        egui::ComboBox::new("style_legend_position", "Legend")
+            .selected_text(preset.legend_position.name())
+            .show_ui(ui, |ui| {
+                for position in LegendPosition::ALL {
+                    ui.selectable_value(&mut preset.legend_position, position, position.name());
+                }
+            });
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut preset.legend_columns).range(0..=10));
+            ui.label("Columns (0 = automatic)");
+        });
+        ui.checkbox(&mut preset.legend_frame, "Legend Frame");
+        ui.checkbox(&mut preset.legend_groups, "Group Files under Group Names");
+    }
+}
+
+/// The name of the style preset `name` in menus.
+pub fn style_name(name: &str) -> &str {
+    if name.is_empty() { "Preferences" } else { name }
+}
+
+fn axis_scale_settings(ui: &mut egui::Ui, scale: &mut AxisScale) {
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut scale.log, "Logarithmic");
+        ui.checkbox(&mut scale.reversed, "Reversed");
+    });
+}
+
 #[derive(Default, Debug)]
 pub struct TicksInput {
     pub pos: Vec<f64>,
@@ -329,4 +771,23 @@ mod tests {
         let res = Config::from_config_file();
         // dbg!(res);
     }
+
+    #[test]
+    fn test_style_names() {
+        let mut config = Config::default();
+        for name in ["Style 2", "Style 3"] {
+            config.style_presets.push(StylePreset {
+                name: name.to_string(),
+                ..Default::default()
+            });
+        }
+        assert_eq!(config.new_style_name(), "Style 1");
+        config.style_presets[0].name = "Style 1".to_string();
+        assert_eq!(config.new_style_name(), "Style 2");
+        // Names must be unique, and distinct from the preferences.
+        assert!(!config.is_style_name_available("Style 3"));
+        assert!(!config.is_style_name_available(" "));
+        assert!(!config.is_style_name_available("Preferences"));
+        assert!(config.is_style_name_available("Poster"));
+    }
 }
@@ -10,7 +10,7 @@ use crate::app::{
 
 use super::{
     EguiApp,
-    components::{FileID, Group},
+    components::{FileID, Group, Operation, Recipe},
 };
 use app_core::{
     event::{AppEvent, EventState},
@@ -136,6 +136,12 @@ pub struct SavePlotRequested {
     thread_handle: Option<JoinHandle<Option<PathBuf>>>,
 }
 
+/// Export the table of integration results as CSV
+#[derive(new)]
+pub struct ExportIntegrationTable {
+    thread_handle: Option<JoinHandle<Option<PathBuf>>>,
+}
+
 /// Save all files in a single folder
 #[derive(new)]
 pub struct ConsolidateRequest {
@@ -164,6 +170,23 @@ pub struct RefreshCache(pub FileID);
 /// Reset scaling for file
 pub struct ResetScaling(pub FileID);
 
+/// Add a file derived from other files by spectral arithmetic to a group
+#[derive(new)]
+pub struct AddDerivedFile {
+    recipe: Recipe,
+    to_group: usize,
+}
+
+/// (Re-)compute the data of a derived file from its sources
+pub struct EvaluateRecipe(pub FileID);
+
+/// Change the factor of a difference spectrum interactively
+#[derive(new)]
+pub struct TuneDifferenceFactor {
+    fid: FileID,
+    drag: egui::Vec2,
+}
+
 // ---------------------------------------------------------------------------
 //
 //
@@ -305,7 +328,7 @@ impl AppEvent for SavePlotRequested {
         if let Some(handle) = self.thread_handle.take_if(|handle| handle.is_finished()) {
             match handle.join() {
                 Ok(Some(path)) => {
-                    super::components::save_svg(app, &path);
+                    super::components::save_plot(app, &path);
                 }
                 Ok(None) => (),
                 Err(err) => {
@@ -319,6 +342,30 @@ impl AppEvent for SavePlotRequested {
     }
 }
 
+impl AppEvent for ExportIntegrationTable {
+    type App = EguiApp;
+
+    fn apply(&mut self, app: &mut Self::App) -> Result<EventState, String> {
+        if let Some(handle) = self.thread_handle.take_if(|handle| handle.is_finished()) {
+            match handle.join() {
+                Ok(Some(path)) => {
+                    let table = app.plotter.integration_table(&app.file_handler);
+                    if let Err(err) = std::fs::write(&path, table.to_csv()) {
+                        log::error!("could not write csv file {:?}: {:?}", path, err)
+                    }
+                }
+                Ok(None) => (),
+                Err(err) => {
+                    log::error!("unable to export integration results: {:?}", err)
+                }
+            };
+            Ok(EventState::Finished)
+        } else {
+            Ok(EventState::Busy)
+        }
+    }
+}
+
 impl AppEvent for ConsolidateRequest {
     type App = EguiApp;
 
@@ -375,6 +422,7 @@ impl AppEvent for LocateFile {
             }
             None => *fdata = Err(format!("File not found in current search path!")),
         }
+        file.refresh_cache();
         Ok(EventState::Finished)
     }
 }
@@ -493,3 +541,83 @@ impl AppEvent for ResetScaling {
         }
     }
 }
+
+impl AppEvent for AddDerivedFile {
+    type App = EguiApp;
+
+    fn apply(&mut self, app: &mut Self::App) -> Result<EventState, String> {
+        let fid = app
+            .file_handler
+            .add_derived_file(self.recipe.clone(), self.to_group);
+        app.event_queue.queue_event(Box::new(EvaluateRecipe(fid)));
+        Ok(EventState::Finished)
+    }
+}
+
+impl AppEvent for EvaluateRecipe {
+    type App = EguiApp;
+
+    fn apply(&mut self, app: &mut Self::App) -> Result<EventState, String> {
+        let Some(recipe) = app
+            .file_handler
+            .registry
+            .get(&self.0)
+            .and_then(|file| file.recipe.clone())
+        else {
+            return Err(format!(
+                "Could not evaluate file with ID {:?}, not found or not derived.",
+                self.0
+            ));
+        };
+        let result = recipe.evaluate(&app.file_handler.registry);
+        let file_name = recipe.file_name(&app.file_handler.registry);
+        let Some(file) = app.file_handler.registry.get_mut(&self.0) else {
+            unreachable!("file was present in registry just before")
+        };
+        match result {
+            // We are still waiting on the sources to load their data.
+            Ok(None) => Ok(EventState::Busy),
+            Ok(Some(data)) => {
+                // Keep the name in sync with the recipe, e.g. after the
+                // factor of a difference was changed.
+                file.path = file_name;
+                file.data = UIParameter::new(Ok(data));
+                file.refresh_cache();
+                Ok(EventState::Finished)
+            }
+            Err(msg) => {
+                file.data = UIParameter::new(Err(msg.clone()));
+                Err(msg)
+            }
+        }
+    }
+}
+
+impl AppEvent for TuneDifferenceFactor {
+    type App = EguiApp;
+
+    fn apply(&mut self, app: &mut Self::App) -> Result<EventState, String> {
+        let yspan = app.plotter.get_current_plot_bounds().height();
+
+        let Some(Recipe {
+            operation: Operation::Difference { factor },
+            ..
+        }) = app
+            .file_handler
+            .registry
+            .get_mut(&self.fid)
+            .and_then(|file| file.recipe.as_mut())
+        else {
+            return Err(format!(
+                "Cannot tune factor of file with ID {:?}: not found or not a difference!",
+                self.fid
+            ));
+        };
+
+        // Dragging across the full height of the plot changes the factor by one.
+        *factor += self.drag.y as f64 / yspan;
+        app.event_queue
+            .queue_event(Box::new(EvaluateRecipe(self.fid)));
+        Ok(EventState::Finished)
+    }
+}
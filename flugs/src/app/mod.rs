@@ -2,13 +2,14 @@ mod components;
 pub mod config;
 mod events;
 pub mod storage;
+mod styles;
 
 use self::components::{Plotter, Search};
 use crate::BackendAppState;
 use crate::app::events::ConsolidateRequest;
 use crate::app::events::EventQueue;
 use app_core::backend::BackendRequest;
-use config::Config;
+use config::{Config, ExportFormat, style_name};
 use events::{SaveLoadRequested, SavePlotRequested};
 use storage::{load_json, save_json};
 
@@ -52,10 +53,11 @@ impl UISelection {
 impl EguiApp {
     pub fn new(
         _cc: &eframe::CreationContext<'_>,
-        config: Config,
+        mut config: Config,
         request_tx: Sender<Box<dyn BackendRequest<BackendAppState>>>,
         backend_thread_handle: JoinHandle<()>,
     ) -> Self {
+        config.load_style_presets();
         let mut search = Search::new(request_tx.clone());
         search.set_search_path(&config.search_path);
 
@@ -150,10 +152,7 @@ impl eframe::App for EguiApp {
                 self.event_queue.queue_event(Box::new(event));
             }
             if i.key_pressed(egui::Key::P) && i.modifiers.ctrl {
-                log::debug!("open dialog to select svg plot path");
-                let handle = std::thread::spawn(|| rfd::FileDialog::new().save_file());
-                let event = SavePlotRequested::new(Some(handle));
-                self.event_queue.queue_event(Box::new(event));
+                self.request_plot_export();
             }
         });
 
@@ -188,9 +187,13 @@ impl EguiApp {
 
         use UISelection as U;
         match self.ui_selection {
-            U::Plot => self
-                .plotter
-                .render(&mut self.file_handler, &mut self.event_queue, ui, ctx),
+            U::Plot => self.plotter.render(
+                &mut self.file_handler,
+                &mut self.config.scales,
+                &mut self.event_queue,
+                ui,
+                ctx,
+            ),
             U::FileSettings => {
                 self.file_handler
                     .render(&mut self.request_tx, &mut self.event_queue, ui, ctx)
@@ -263,6 +266,8 @@ impl EguiApp {
                         PlotterMode::Display => "D",
                         PlotterMode::Integrate => "I",
                         PlotterMode::Annotage => "A",
+                        PlotterMode::Subtract => "S",
+                        PlotterMode::Measure => "M",
                     },
                 );
                 ui.menu_button(mode_button_label, |ui| {
@@ -281,16 +286,61 @@ impl EguiApp {
                         crate::app::PlotterMode::Annotage,
                         "Annotate",
                     );
+                    ui.selectable_value(
+                        &mut self.plotter.mode,
+                        crate::app::PlotterMode::Subtract,
+                        "Subtract",
+                    );
+                    ui.selectable_value(
+                        &mut self.plotter.mode,
+                        crate::app::PlotterMode::Measure,
+                        "Measure",
+                    );
                 });
 
                 if ui.button("Export").clicked() {
-                    log::debug!("open dialog to select svg plot path");
-                    let handle = std::thread::spawn(|| {
-                        rfd::FileDialog::new().set_file_name("plot.svg").save_file()
-                    });
-                    let event = SavePlotRequested::new(Some(handle));
-                    self.event_queue.queue_event(Box::new(event));
+                    self.request_plot_export();
                 };
+                let format_changed = egui::ComboBox::from_id_salt("export_format")
+                    .width(50.0)
+                    .selected_text(self.config.export_format.name())
+                    .show_ui(ui, |ui| {
+                        let mut changed = false;
+                        for format in ExportFormat::ALL {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut self.config.export_format,
+                                    format,
+                                    format.name(),
+                                )
+                                .changed();
+                        }
+                        changed
+                    })
+                    .inner
+                    .unwrap_or(false);
+                // The last format is remembered between sessions.
+                if format_changed {
+                    self.config.save_to_config_file();
+                }
+                egui::ComboBox::from_id_salt("export_style")
+                    .selected_text(style_name(&self.config.export_style))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(
+                            &mut self.config.export_style,
+                            String::new(),
+                            style_name(""),
+                        );
+                        for preset in self.config.style_presets.iter() {
+                            ui.selectable_value(
+                                &mut self.config.export_style,
+                                preset.name.clone(),
+                                &preset.name,
+                            );
+                        }
+                    })
+                    .response
+                    .on_hover_text("Style of the exported figure, see Preferences.");
 
                 ui.toggle_value(&mut self.shortcuts_modal_open, "Help (F1)");
 
@@ -301,6 +351,21 @@ impl EguiApp {
         });
     }
 
+    /// Open a dialog to select the path the plot is exported to, in the
+    /// chosen format.
+    fn request_plot_export(&mut self) {
+        let format = self.config.export_format;
+        log::debug!("open dialog to select {} plot path", format.name());
+        let handle = std::thread::spawn(move || {
+            rfd::FileDialog::new()
+                .set_file_name(format!("plot.{}", format.extension()))
+                .add_filter(format.name(), &[format.extension()])
+                .save_file()
+        });
+        let event = SavePlotRequested::new(Some(handle));
+        self.event_queue.queue_event(Box::new(event));
+    }
+
     fn render_shortcut_modal(&mut self, ctx: &egui::Context) {
         if self.shortcuts_modal_open
             && egui::Modal::new("shortcut_modal".into())
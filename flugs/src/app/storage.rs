@@ -6,11 +6,17 @@ use std::{
 use app_core::storage::Storage;
 use serde::{Deserialize, Serialize};
 
-use crate::{EguiApp, app::events::RefreshCache};
+use crate::{
+    EguiApp,
+    app::events::{EvaluateRecipe, RefreshCache},
+};
 
 use super::{
     DynRequestSender, FileHandler,
-    components::{File, FileID, FileProperties, Group},
+    components::{
+        File, FileID, FileProperties, Group, Inset, IntegrationWindow, PanelLayout, PlotScales,
+        Recipe, SecondaryAxis, StackLayout,
+    },
 };
 
 // Currently not used, since the only backend state to safe right now is the
@@ -25,6 +31,18 @@ struct FrontendStorage {
     registry: HashMap<FileID, FileStorage>,
     next_id: FileID,
     plot_bounds: Option<[f64; 4]>,
+    #[serde(default)]
+    integration_windows: Vec<IntegrationWindow>,
+    #[serde(default)]
+    stack: StackLayout,
+    #[serde(default)]
+    panels: PanelLayout,
+    #[serde(default)]
+    secondary_axis: SecondaryAxis,
+    #[serde(default)]
+    scales: PlotScales,
+    #[serde(default)]
+    inset: Option<Inset>,
 }
 
 pub fn save_json(app: &EguiApp, path: Option<&Path>) -> Result<(), String> {
@@ -43,6 +61,7 @@ pub fn save_json(app: &EguiApp, path: Option<&Path>) -> Result<(), String> {
                     FileStorage {
                         path: file.path.clone(),
                         properties: file.properties.clone(),
+                        recipe: file.recipe.clone(),
                     },
                 )
             })
@@ -55,6 +74,12 @@ pub fn save_json(app: &EguiApp, path: Option<&Path>) -> Result<(), String> {
             );
             [xmin, xmax, ymin, ymax]
         }),
+        integration_windows: app.plotter.integration_windows.clone(),
+        stack: app.plotter.stack,
+        panels: app.plotter.panels,
+        secondary_axis: app.plotter.secondary_axis.clone(),
+        scales: app.config.scales,
+        inset: app.plotter.inset,
     };
     let storage = Storage::new(backend_storage, frontend_storage);
     storage.save_json(path)
@@ -70,21 +95,39 @@ pub fn load_json(app: &mut EguiApp, path: Option<&Path>) -> Result<(), String> {
     if let Some(bounds) = frontend_storage.plot_bounds {
         app.plotter.apply_bounds(bounds);
     }
+    app.plotter.integration_windows = frontend_storage.integration_windows.clone();
+    app.plotter.stack = frontend_storage.stack;
+    app.plotter.panels = frontend_storage.panels;
+    app.plotter.secondary_axis = frontend_storage.secondary_axis.clone();
+    app.config.scales = frontend_storage.scales;
+    app.plotter.inset = frontend_storage.inset;
     app.file_handler = frontend_storage.into_file_handler(&mut app.request_tx);
     // Refresh file cache to apply scaling/offsets
     for fid in app.file_handler.registry.keys() {
         app.event_queue.queue_event(Box::new(RefreshCache(*fid)));
     }
+    // Re-create data of derived files from their sources.
+    for (fid, _) in app
+        .file_handler
+        .registry
+        .iter()
+        .filter(|(_, file)| file.recipe.is_some())
+    {
+        app.event_queue.queue_event(Box::new(EvaluateRecipe(*fid)));
+    }
     app.request_redraw();
     Ok(())
 }
 
 // Serializing the files is a special case, because we do not want to store the
 // entire data contained in the csv files (plus it is non-trivial to do this).
+// Derived files are stored as their recipe.
 #[derive(Serialize, Deserialize)]
 struct FileStorage {
     path: PathBuf,
     properties: FileProperties,
+    #[serde(default)]
+    recipe: Option<Recipe>,
 }
 
 impl FrontendStorage {
@@ -94,10 +137,13 @@ impl FrontendStorage {
             .registry
             .into_iter()
             .map(|(fid, file_storage)| {
-                (
-                    fid,
-                    File::new(file_storage.path, file_storage.properties, request_tx),
-                )
+                let file = match file_storage.recipe {
+                    Some(recipe) => {
+                        File::derived(file_storage.path, file_storage.properties, recipe)
+                    }
+                    None => File::new(file_storage.path, file_storage.properties, request_tx),
+                };
+                (fid, file)
             })
             .collect();
 
@@ -0,0 +1,209 @@
+//! Named styles of exported figures. The presets are kept in a JSON file
+//! (see `Config::style_file`), which can be shared, e.g. by a working group,
+//! so that everybody's figures look alike.
+
+use std::{io::Write, path::Path};
+
+use app_core::string_error::ErrorStringExt;
+use serde::{Deserialize, Serialize};
+use svg_export::{FigureSize, Frame, Legend, LegendPosition, SizeUnit, Theme, TickDirection};
+
+use super::{components::Palette, config::Config};
+
+/// A named style of exported figures. Missing entries of a preset in the
+/// style file take their default values.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StylePreset {
+    pub name: String,
+    pub width: f64,
+    pub height: f64,
+    pub unit: SizeUnit,
+    /// Resolution of exported PNG images.
+    pub dpi: f64,
+    /// Font of the SVG export, PDF uses Helvetica and PNG Ubuntu Light.
+    pub font_family: String,
+    /// Font sizes of the axis labels, tick labels and legend, in points.
+    pub label_size: f64,
+    pub tick_label_size: f64,
+    pub legend_size: f64,
+    pub tick_direction: TickDirection,
+    /// Length of the ticks in pixels, zero for ticks scaled with the axis.
+    pub tick_length: f64,
+    pub frame: Frame,
+    pub linewidth: f64,
+    /// Colors (as `#rrggbb`) of the files without a custom color in groups
+    /// with the default palette, replacing the colors of that palette at the
+    /// same positions. The colors of the plotter are used if empty.
+    pub color_cycle: Vec<String>,
+    pub legend_position: LegendPosition,
+    /// Number of columns of the legend, zero to choose it automatically.
+    pub legend_columns: usize,
+    pub legend_frame: bool,
+    pub legend_groups: bool,
+}
+
+impl Default for StylePreset {
+    fn default() -> Self {
+        Self::from_preferences(&Config::default())
+    }
+}
+
+impl StylePreset {
+    /// The style given by the preferences, which is used if no preset is
+    /// selected.
+    pub fn from_preferences(config: &Config) -> Self {
+        let theme = Theme::default();
+        Self {
+            name: String::new(),
+            width: config.svg_width as f64,
+            height: config.svg_height as f64,
+            unit: SizeUnit::Px,
+            dpi: config.png_dpi,
+            font_family: theme.font_family,
+            label_size: theme.label_size,
+            tick_label_size: theme.tick_label_size,
+            legend_size: 10.0,
+            tick_direction: TickDirection::default(),
+            tick_length: 0.0,
+            frame: Frame::default(),
+            linewidth: config.plot_linewidth,
+            color_cycle: Vec::new(),
+            legend_position: config.legend_position,
+            legend_columns: config.legend_columns,
+            legend_frame: config.legend_frame,
+            legend_groups: config.legend_groups,
+        }
+    }
+
+    /// Presets offered before a style file is created.
+    pub fn examples() -> Vec<Self> {
+        let okabe_ito = Palette::OkabeIto.hex_colors();
+        vec![
+            Self {
+                name: "Journal (single column)".to_string(),
+                width: 85.0,
+                height: 65.0,
+                unit: SizeUnit::Mm,
+                dpi: 600.0,
+                font_family: "Arial".to_string(),
+                label_size: 8.0,
+                tick_label_size: 7.0,
+                legend_size: 7.0,
+                tick_direction: TickDirection::In,
+                tick_length: 3.0,
+                frame: Frame::Box,
+                linewidth: 1.0,
+                color_cycle: okabe_ito.clone(),
+                legend_position: LegendPosition::Automatic,
+                legend_columns: 0,
+                legend_frame: false,
+                legend_groups: false,
+            },
+            Self {
+                name: "Presentation".to_string(),
+                width: 10.0,
+                height: 6.0,
+                unit: SizeUnit::In,
+                dpi: 150.0,
+                font_family: "sans".to_string(),
+                label_size: 18.0,
+                tick_label_size: 16.0,
+                legend_size: 14.0,
+                tick_direction: TickDirection::Out,
+                tick_length: 6.0,
+                frame: Frame::Open,
+                linewidth: 3.0,
+                color_cycle: okabe_ito,
+                legend_position: LegendPosition::OutsideRight,
+                legend_columns: 1,
+                legend_frame: false,
+                legend_groups: true,
+            },
+        ]
+    }
+
+    pub fn size(&self) -> FigureSize {
+        FigureSize {
+            width: self.width,
+            height: self.height,
+            unit: self.unit,
+        }
+    }
+
+    /// The theme of the axes of exported figures.
+    pub fn theme(&self) -> Theme {
+        Theme {
+            font_family: self.font_family.clone(),
+            label_size: self.label_size,
+            tick_label_size: self.tick_label_size,
+            tick_direction: self.tick_direction,
+            tick_length: (self.tick_length > 0.0).then_some(self.tick_length),
+            frame: self.frame,
+            color_cycle: self.color_cycle.clone(),
+            legend: Legend::new()
+                .with_position(self.legend_position)
+                .with_columns(self.legend_columns)
+                .with_frame(self.legend_frame)
+                .with_font_size(self.legend_size)
+                .with_group_headers(self.legend_groups),
+        }
+    }
+}
+
+/// The contents of a style file.
+#[derive(Default, Serialize, Deserialize)]
+struct StyleFile {
+    presets: Vec<StylePreset>,
+}
+
+/// Load the presets of the style file at `path`.
+pub fn load_presets(path: &Path) -> Result<Vec<StylePreset>, String> {
+    let raw = std::fs::read_to_string(path).err_to_string("could not read style file")?;
+    let file: StyleFile = serde_json::from_str(&raw).err_to_string("could not parse style file")?;
+    Ok(file.presets)
+}
+
+/// Save `presets` to the style file at `path`.
+pub fn save_presets(path: &Path, presets: &[StylePreset]) -> Result<(), String> {
+    let file = StyleFile {
+        presets: presets.to_vec(),
+    };
+    let raw = serde_json::to_string_pretty(&file).err_to_string("could not serialize styles")?;
+    std::fs::File::create(path)
+        .and_then(|mut f| f.write_all(raw.as_bytes()))
+        .err_to_string("could not write style file")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_round_trip() {
+        let path = std::env::temp_dir().join("flugs_test_styles.json");
+        let presets = StylePreset::examples();
+        save_presets(&path, &presets).unwrap();
+        assert_eq!(load_presets(&path).unwrap(), presets);
+
+        // Missing entries take their default values.
+        std::fs::write(&path, r#"{"presets": [{"name": "small", "width": 300}]}"#).unwrap();
+        let loaded = load_presets(&path).unwrap();
+        assert_eq!(loaded[0].width, 300.0);
+        assert_eq!(loaded[0].height, StylePreset::default().height);
+
+        // Legend positions are stored by their variant, unknown ones are refused.
+        std::fs::write(&path, r#"{"presets": [{"legend_position": "Below"}]}"#).unwrap();
+        let loaded = load_presets(&path).unwrap();
+        assert_eq!(loaded[0].legend_position, LegendPosition::Below);
+        std::fs::write(&path, r#"{"presets": [{"legend_position": "Top"}]}"#).unwrap();
+        assert!(load_presets(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+
+        // The preferences yield the default theme.
+        let theme = StylePreset::default().theme();
+        assert_eq!(theme.frame, Frame::None);
+        assert_eq!(theme.tick_length, None);
+        assert_eq!(StylePreset::examples()[0].size().pixels(), (321, 246));
+    }
+}
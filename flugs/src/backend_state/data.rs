@@ -5,9 +5,14 @@ use std::{collections::HashMap, path::Path};
 use app_core::string_error::ErrorStringExt;
 use egui_plot::PlotPoint;
 
+use super::units::{XUnit, YUnit};
+
 #[derive(Clone, Debug, Default)]
 pub struct PlotData {
     pub columns: Vec<Vec<f64>>,
+    /// Units of the data, if known from the parsed file.
+    pub x_unit: XUnit,
+    pub y_unit: YUnit,
     num_columns: usize,
     comments: String,
     cache: Vec<PlotPoint>,
@@ -23,7 +28,7 @@ struct DelimiterCounter {
 
 impl PlotData {
     pub fn from_path(path: &Path) -> Result<PlotData, String> {
-        let (comments, columns) = if path
+        let (comments, columns, x_unit, y_unit) = if path
             .extension()
             .and_then(|ext| ext.to_str())
             .map(|ext| ext.parse::<u32>().is_ok())
@@ -33,13 +38,22 @@ impl PlotData {
             let bruker_parser::OpusAbsorbanceData {
                 wavenumber,
                 absorbance,
+                x_unit,
+                single_channel,
             } = bruker_parser::OpusAbsorbanceData::from_path(path)?;
-            (String::new(), vec![wavenumber, absorbance])
+            let x_unit = x_unit.as_deref().map(XUnit::from_opus).unwrap_or_default();
+            let y_unit = if single_channel {
+                YUnit::Unknown
+            } else {
+                YUnit::Absorbance
+            };
+            (String::new(), vec![wavenumber, absorbance], x_unit, y_unit)
         } else {
             // Otherwise, we try to parse as CSV.
             let parser =
                 turbo_csv::Parser::from_path(path).err_to_string("unable to initialize parser")?;
-            parser.parse_as_floats()
+            let (comments, columns) = parser.parse_as_floats();
+            (comments, columns, XUnit::Unknown, YUnit::Unknown)
         };
 
         let cache = if let Some(cache) = new_cache(&columns, Some(0), 1) {
@@ -54,26 +68,41 @@ impl PlotData {
 
         Ok(PlotData {
             columns,
+            x_unit,
+            y_unit,
             num_columns,
             comments,
             cache,
         })
     }
 
-    pub fn get_cache(&self) -> &[PlotPoint] {
-        &self.cache
+    /// Create plot data from columns that were computed rather than parsed
+    /// from a file (e.g. by spectral arithmetic).
+    pub fn from_columns(
+        columns: Vec<Vec<f64>>,
+        comments: String,
+        (x_unit, y_unit): (XUnit, YUnit),
+    ) -> Result<PlotData, String> {
+        let cache = new_cache(&columns, Some(0), 1)
+            .ok_or("at least two columns are needed to create plot data".to_string())?;
+        let num_columns = columns.len();
+
+        Ok(PlotData {
+            columns,
+            x_unit,
+            y_unit,
+            num_columns,
+            comments,
+            cache,
+        })
     }
 
-    pub fn regenerate_cache(&mut self, x_col: usize, y_col: usize) {
-        if let Some(cache) = new_cache(&self.columns, Some(x_col), y_col) {
-            self.cache = cache;
-        }
+    pub fn get_cache(&self) -> &[PlotPoint] {
+        &self.cache
     }
 
-    pub fn rescale(&mut self, xoffset: f64, yoffset: f64, yscale: f64) -> Option<()> {
-        // TODO: Selected columns are hard-coded, for now
-        self.cache = scaled_cache(&self.columns, Some(0), 1, xoffset, yoffset, yscale)?;
-        Some(())
+    pub fn set_cache(&mut self, cache: Vec<PlotPoint>) {
+        self.cache = cache;
     }
 
     pub fn ymin(&self, ycol: usize) -> Option<f64> {
@@ -105,34 +134,3 @@ fn new_cache(columns: &[Vec<f64>], xcol: Option<usize>, ycol: usize) -> Option<V
     };
     Some(data)
 }
-
-fn scaled_cache(
-    columns: &[Vec<f64>],
-    xcol: Option<usize>,
-    ycol: usize,
-    xoffset: f64,
-    yoffset: f64,
-    yscale: f64,
-) -> Option<Vec<PlotPoint>> {
-    let ydata = columns.get(ycol)?;
-    let data = if let Some(xdata) = xcol.map(|i| columns.get(i))? {
-        xdata
-            .iter()
-            .zip(ydata)
-            .map(|(&x, &y)| PlotPoint {
-                x: x + xoffset,
-                y: (y * yscale) + yoffset,
-            })
-            .collect()
-    } else {
-        ydata
-            .iter()
-            .zip(0..)
-            .map(|(&y, n)| PlotPoint {
-                x: n as f64,
-                y: (y * yscale) + yoffset,
-            })
-            .collect()
-    };
-    Some(data)
-}
@@ -1,4 +1,5 @@
 mod data;
+mod units;
 
 use app_core::backend::BackendState;
 use std::{
@@ -7,6 +8,7 @@ use std::{
 };
 
 pub use data::PlotData;
+pub use units::{Unit, XUnit, YUnit, convert_units};
 
 #[derive(Default)]
 pub struct BackendAppState {
@@ -0,0 +1,302 @@
+//! Units of the x- and y-axis and conversions between them.
+
+use serde::{Deserialize, Serialize};
+
+/// Wavenumbers (cm⁻¹) per electron volt.
+const WAVENUMBER_PER_EV: f64 = 8065.543937;
+/// Speed of light in cm/s, i.e. Hz per cm⁻¹.
+const SPEED_OF_LIGHT: f64 = 2.99792458e10;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum XUnit {
+    #[default]
+    Unknown,
+    Wavenumber,
+    Nanometre,
+    Micrometre,
+    ElectronVolt,
+    Hertz,
+}
+
+/// Common interface of the units of the x- and y-axis.
+pub trait Unit: Copy + PartialEq + 'static {
+    const ALL: &'static [Self];
+
+    fn name(&self) -> &'static str;
+}
+
+impl Unit for XUnit {
+    const ALL: &'static [XUnit] = &[
+        XUnit::Unknown,
+        XUnit::Wavenumber,
+        XUnit::Nanometre,
+        XUnit::Micrometre,
+        XUnit::ElectronVolt,
+        XUnit::Hertz,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            XUnit::Unknown => "unknown",
+            XUnit::Wavenumber => "cm⁻¹",
+            XUnit::Nanometre => "nm",
+            XUnit::Micrometre => "µm",
+            XUnit::ElectronVolt => "eV",
+            XUnit::Hertz => "Hz",
+        }
+    }
+}
+
+impl XUnit {
+    /// Label of an axis showing data in this unit, in the markup of
+    /// `svg_export::markup`.
+    pub fn axis_label(&self) -> String {
+        let (quantity, unit) = match self {
+            XUnit::Unknown => return String::new(),
+            XUnit::Wavenumber => ("Wavenumber", "cm^{-1}"),
+            XUnit::Nanometre | XUnit::Micrometre => ("Wavelength", self.name()),
+            XUnit::ElectronVolt => ("Energy", self.name()),
+            XUnit::Hertz => ("Frequency", self.name()),
+        };
+        format!("{quantity} / {unit}")
+    }
+
+    /// Map the x-units (parameter DXU) of OPUS files.
+    pub fn from_opus(code: &str) -> Self {
+        match code {
+            "WN" => XUnit::Wavenumber,
+            "MI" => XUnit::Micrometre,
+            "NM" => XUnit::Nanometre,
+            "HZ" => XUnit::Hertz,
+            "EV" => XUnit::ElectronVolt,
+            _ => XUnit::Unknown,
+        }
+    }
+
+    /// Whether the unit is inversely proportional to the wavenumber.
+    fn is_wavelength(&self) -> bool {
+        matches!(self, XUnit::Nanometre | XUnit::Micrometre)
+    }
+
+    fn to_wavenumber(self, x: f64) -> f64 {
+        match self {
+            XUnit::Unknown | XUnit::Wavenumber => x,
+            XUnit::Nanometre => 1e7 / x,
+            XUnit::Micrometre => 1e4 / x,
+            XUnit::ElectronVolt => x * WAVENUMBER_PER_EV,
+            XUnit::Hertz => x / SPEED_OF_LIGHT,
+        }
+    }
+
+    fn convert_wavenumber(self, wavenumber: f64) -> f64 {
+        match self {
+            XUnit::Unknown | XUnit::Wavenumber => wavenumber,
+            XUnit::Nanometre => 1e7 / wavenumber,
+            XUnit::Micrometre => 1e4 / wavenumber,
+            XUnit::ElectronVolt => wavenumber / WAVENUMBER_PER_EV,
+            XUnit::Hertz => wavenumber * SPEED_OF_LIGHT,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum YUnit {
+    #[default]
+    Unknown,
+    Absorbance,
+    Transmittance,
+    PercentTransmittance,
+    Reflectance,
+    PercentReflectance,
+    KubelkaMunk,
+}
+
+impl Unit for YUnit {
+    const ALL: &'static [YUnit] = &[
+        YUnit::Unknown,
+        YUnit::Absorbance,
+        YUnit::Transmittance,
+        YUnit::PercentTransmittance,
+        YUnit::Reflectance,
+        YUnit::PercentReflectance,
+        YUnit::KubelkaMunk,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            YUnit::Unknown => "unknown",
+            YUnit::Absorbance => "Absorbance",
+            YUnit::Transmittance => "Transmittance",
+            YUnit::PercentTransmittance => "Transmittance / %",
+            YUnit::Reflectance => "Reflectance",
+            YUnit::PercentReflectance => "Reflectance / %",
+            YUnit::KubelkaMunk => "Kubelka–Munk",
+        }
+    }
+}
+
+impl YUnit {
+    /// Label of an axis showing data in this unit.
+    pub fn axis_label(&self) -> String {
+        match self {
+            YUnit::Unknown => String::new(),
+            YUnit::KubelkaMunk => "F(R)".to_string(),
+            other => other.name().to_string(),
+        }
+    }
+
+    /// Convert to transmittance or reflectance as a fraction of one. The
+    /// second value tells which of the two it is.
+    fn to_fraction(self, y: f64) -> (f64, Option<bool>) {
+        match self {
+            YUnit::Unknown => (y, None),
+            YUnit::Absorbance => (10f64.powf(-y), Some(false)),
+            YUnit::Transmittance => (y, Some(false)),
+            YUnit::PercentTransmittance => (y / 100.0, Some(false)),
+            YUnit::Reflectance => (y, Some(true)),
+            YUnit::PercentReflectance => (y / 100.0, Some(true)),
+            // Invert F(R) = (1 - R)² / 2R.
+            YUnit::KubelkaMunk => (1.0 + y - (y * y + 2.0 * y).sqrt(), Some(true)),
+        }
+    }
+
+    fn convert_fraction(self, fraction: f64) -> f64 {
+        match self {
+            YUnit::Unknown | YUnit::Transmittance | YUnit::Reflectance => fraction,
+            YUnit::Absorbance => -fraction.log10(),
+            YUnit::PercentTransmittance | YUnit::PercentReflectance => fraction * 100.0,
+            YUnit::KubelkaMunk => (1.0 - fraction).powi(2) / (2.0 * fraction),
+        }
+    }
+}
+
+/// Convert the data `xs`, `ys` between units. Points that cannot be
+/// converted (e.g. a wavelength of zero) are dropped. Conversions between
+/// reciprocal units (e.g. nm and cm⁻¹) reverse the order of the points, so
+/// ascending data stays ascending.
+pub fn convert_units(
+    xs: &[f64],
+    ys: &[f64],
+    (x_from, x_to): (XUnit, XUnit),
+    (y_from, y_to): (YUnit, YUnit),
+) -> Result<(Vec<f64>, Vec<f64>), String> {
+    let convert_x = x_from != x_to;
+    let convert_y = y_from != y_to;
+    if convert_x && (x_from == XUnit::Unknown || x_to == XUnit::Unknown) {
+        return Err("x-unit of the data is unknown, cannot convert".to_string());
+    }
+    if convert_y && (y_from == YUnit::Unknown || y_to == YUnit::Unknown) {
+        return Err("y-unit of the data is unknown, cannot convert".to_string());
+    }
+    if convert_y && y_from.to_fraction(1.0).1 != y_to.to_fraction(1.0).1 {
+        return Err(format!(
+            "cannot convert from {} to {}",
+            y_from.name(),
+            y_to.name()
+        ));
+    }
+
+    let mut points: Vec<(f64, f64)> = xs
+        .iter()
+        .zip(ys)
+        .map(|(x, y)| {
+            let x = if convert_x {
+                x_to.convert_wavenumber(x_from.to_wavenumber(*x))
+            } else {
+                *x
+            };
+            let y = if convert_y {
+                y_to.convert_fraction(y_from.to_fraction(*y).0)
+            } else {
+                *y
+            };
+            (x, y)
+        })
+        .filter(|(x, _)| x.is_finite())
+        .collect();
+    if convert_x && x_from.is_wavelength() != x_to.is_wavelength() {
+        points.reverse();
+    }
+    Ok(points.into_iter().unzip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b) {
+            assert!((a - b).abs() <= 1e-9 * b.abs().max(1.0), "{a} != {b}");
+        }
+    }
+
+    #[test]
+    fn test_x_conversion() {
+        let xs = [500.0, 1000.0, 2000.0];
+        let ys = [1.0, 2.0, 3.0];
+        let units = (YUnit::Unknown, YUnit::Unknown);
+        let (nm, ys_nm) =
+            convert_units(&xs, &ys, (XUnit::Wavenumber, XUnit::Nanometre), units).unwrap();
+        assert_close(&nm, &[5000.0, 10000.0, 20000.0]);
+        assert_close(&ys_nm, &[3.0, 2.0, 1.0]);
+        let (ev, _) =
+            convert_units(&xs, &ys, (XUnit::Wavenumber, XUnit::ElectronVolt), units).unwrap();
+        assert_close(&ev[1..2], &[1000.0 / WAVENUMBER_PER_EV]);
+        // The round trip gives back the original data.
+        for unit in XUnit::ALL.iter().copied().skip(1) {
+            let (x1, y1) = convert_units(&xs, &ys, (XUnit::Wavenumber, unit), units).unwrap();
+            let (x2, y2) = convert_units(&x1, &y1, (unit, XUnit::Wavenumber), units).unwrap();
+            assert_close(&x2, &xs);
+            assert_close(&y2, &ys);
+        }
+    }
+
+    #[test]
+    fn test_y_conversion() {
+        let xs = [1.0, 2.0, 3.0];
+        let x_units = (XUnit::Unknown, XUnit::Unknown);
+        let (_, absorbance) = convert_units(
+            &xs,
+            &[100.0, 10.0, 1.0],
+            x_units,
+            (YUnit::PercentTransmittance, YUnit::Absorbance),
+        )
+        .unwrap();
+        assert_close(&absorbance, &[0.0, 1.0, 2.0]);
+        let (_, km) = convert_units(
+            &xs,
+            &[0.5, 0.25, 1.0],
+            x_units,
+            (YUnit::Reflectance, YUnit::KubelkaMunk),
+        )
+        .unwrap();
+        assert_close(&km, &[0.25, 1.125, 0.0]);
+        let (_, reflectance) =
+            convert_units(&xs, &km, x_units, (YUnit::KubelkaMunk, YUnit::Reflectance)).unwrap();
+        assert_close(&reflectance, &[0.5, 0.25, 1.0]);
+    }
+
+    #[test]
+    fn test_invalid_conversion() {
+        let xs = [1.0, 2.0];
+        assert!(
+            convert_units(
+                &xs,
+                &xs,
+                (XUnit::Unknown, XUnit::Nanometre),
+                (YUnit::Unknown, YUnit::Unknown)
+            )
+            .is_err()
+        );
+        assert!(
+            convert_units(
+                &xs,
+                &xs,
+                (XUnit::Unknown, XUnit::Unknown),
+                (YUnit::Absorbance, YUnit::KubelkaMunk)
+            )
+            .is_err()
+        );
+    }
+}
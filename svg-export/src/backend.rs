@@ -0,0 +1,272 @@
+// Backends draw a `Figure` into other formats than SVG markup, e.g. PDF or
+// PNG. The figure is converted into SVG tags as usual, and each tag draws its
+// shape with the style resolved from its attributes.
+
+use crate::svg::Params;
+
+/// A target for drawing a `Figure`. All coordinates are in pixels of the
+/// figure, with the origin in the upper left corner.
+pub trait Backend {
+    /// Stroke the line through `points`, which is closed if `closed` is set.
+    fn stroke(&mut self, points: &[[f64; 2]], closed: bool, style: &Style);
+
+    /// Fill the polygon with the corners `points`.
+    fn fill(&mut self, points: &[[f64; 2]], style: &Style);
+
+    /// Draw `text`, anchored at `position` on its baseline, rotated clockwise
+    /// by `angle` degrees.
+    fn text(&mut self, text: &str, position: [f64; 2], angle: f64, style: &Style);
+
+    /// Width of `text` drawn with `style`, in pixels. Texts with sub- or
+    /// superscripts are set piecewise with the widths of their spans.
+    fn text_width(&self, text: &str, style: &Style) -> f64 {
+        crate::metrics::text_width(text) * style.font_size
+    }
+}
+
+/// A color with components from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0.0, 0.0, 0.0);
+    pub const WHITE: Color = Color::rgb(1.0, 1.0, 1.0);
+
+    pub const fn rgb(r: f64, g: f64, b: f64) -> Self {
+        Color { r, g, b, a: 1.0 }
+    }
+
+    /// Parse an SVG color: `#rgb`, `#rrggbb`, `#rrggbbaa` or one of the
+    /// common color keywords. `None` for `none` and unknown colors.
+    pub fn parse(color: &str) -> Option<Self> {
+        let color = color.trim();
+        if let Some(hex) = color.strip_prefix('#') {
+            let digits: Vec<f64> = hex
+                .chars()
+                .map(|c| c.to_digit(16).map(f64::from))
+                .collect::<Option<_>>()?;
+            return match digits[..] {
+                [r, g, b] => Some(Color::rgb(r / 15.0, g / 15.0, b / 15.0)),
+                [r1, r0, g1, g0, b1, b0] => Some(Color::rgb(
+                    (16.0 * r1 + r0) / 255.0,
+                    (16.0 * g1 + g0) / 255.0,
+                    (16.0 * b1 + b0) / 255.0,
+                )),
+                [r1, r0, g1, g0, b1, b0, a1, a0] => Some(Color {
+                    a: (16.0 * a1 + a0) / 255.0,
+                    ..Color::rgb(
+                        (16.0 * r1 + r0) / 255.0,
+                        (16.0 * g1 + g0) / 255.0,
+                        (16.0 * b1 + b0) / 255.0,
+                    )
+                }),
+                _ => None,
+            };
+        }
+        let [r, g, b] = match color.to_lowercase().as_str() {
+            "black" => [0, 0, 0],
+            "white" => [255, 255, 255],
+            "gray" | "grey" => [128, 128, 128],
+            "dimgray" | "dimgrey" => [105, 105, 105],
+            "darkgray" | "darkgrey" => [169, 169, 169],
+            "lightgray" | "lightgrey" => [211, 211, 211],
+            "red" => [255, 0, 0],
+            "green" => [0, 128, 0],
+            "blue" => [0, 0, 255],
+            "yellow" => [255, 255, 0],
+            "orange" => [255, 165, 0],
+            "purple" => [128, 0, 128],
+            "cyan" => [0, 255, 255],
+            "magenta" => [255, 0, 255],
+            "brown" => [165, 42, 42],
+            _ => return None,
+        };
+        Some(Color::rgb(
+            r as f64 / 255.0,
+            g as f64 / 255.0,
+            b as f64 / 255.0,
+        ))
+    }
+
+    fn with_opacity(self, opacity: f64) -> Self {
+        Color {
+            a: self.a * opacity.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+}
+
+/// Horizontal alignment of a text relative to its position.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Anchor {
+    #[default]
+    Start,
+    Middle,
+    End,
+}
+
+impl Anchor {
+    /// The fraction of the width of a text left of its position.
+    pub fn offset(&self) -> f64 {
+        match self {
+            Anchor::Start => 0.0,
+            Anchor::Middle => 0.5,
+            Anchor::End => 1.0,
+        }
+    }
+}
+
+/// The style of a tag, resolved from its attributes and style properties.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Style {
+    pub fill: Option<Color>,
+    pub stroke: Option<Color>,
+    pub stroke_width: f64,
+    /// Lengths of alternating dashes and gaps, empty for solid lines.
+    pub dasharray: Vec<f64>,
+    /// The font size, in pixels.
+    pub font_size: f64,
+    pub anchor: Anchor,
+}
+
+impl Style {
+    /// Resolve the style of a tag with the same defaults as SVG, where the
+    /// `style` properties take precedence over the `attributes`.
+    pub(crate) fn from_params(attributes: &Params, style: &Params) -> Self {
+        let get = |key: &str| style.get(key).or(attributes.get(key)).map(|v| v.trim());
+        let number = |key: &str| get(key).and_then(|v| v.parse::<f64>().ok());
+        let opacity = number("opacity").unwrap_or(1.0);
+        let paint = |key: &str, default: Option<Color>| {
+            let color = match get(key) {
+                Some(color) => Color::parse(color),
+                None => default,
+            };
+            color
+                .map(|c| c.with_opacity(opacity * number(&format!("{key}-opacity")).unwrap_or(1.0)))
+        };
+        Style {
+            fill: paint("fill", Some(Color::BLACK)),
+            stroke: paint("stroke", None),
+            stroke_width: number("stroke-width").unwrap_or(1.0),
+            dasharray: get("stroke-dasharray")
+                .map(|v| {
+                    v.split([' ', ','])
+                        .filter_map(|d| d.parse::<f64>().ok())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            font_size: get("font-size").and_then(parse_length).unwrap_or(16.0),
+            anchor: match get("text-anchor") {
+                Some("middle") => Anchor::Middle,
+                Some("end") => Anchor::End,
+                _ => Anchor::Start,
+            },
+        }
+    }
+}
+
+/// Parse an SVG length (`12`, `12px`, `10pt` or `1.2em`) into pixels.
+fn parse_length(length: &str) -> Option<f64> {
+    let (number, factor) = if let Some(pt) = length.strip_suffix("pt") {
+        (pt, 4.0 / 3.0)
+    } else if let Some(em) = length.strip_suffix("em") {
+        (em, 16.0)
+    } else {
+        (length.strip_suffix("px").unwrap_or(length), 1.0)
+    };
+    number.trim().parse::<f64>().ok().map(|n| n * factor)
+}
+
+/// Split the line through `points` into the dashes of `dasharray`. The line
+/// is returned as a whole, if it is solid.
+#[cfg(feature = "png")]
+pub(crate) fn dashes(points: &[[f64; 2]], dasharray: &[f64]) -> Vec<Vec<[f64; 2]>> {
+    let total: f64 = dasharray.iter().sum();
+    if dasharray.iter().any(|d| *d < 0.0) || total <= 0.0 || points.is_empty() {
+        return vec![points.to_vec()];
+    }
+    // An odd number of lengths is repeated, as in SVG.
+    let pattern: Vec<f64> = if dasharray.len() % 2 == 1 {
+        dasharray.iter().chain(dasharray).copied().collect()
+    } else {
+        dasharray.to_vec()
+    };
+    let mut dashes = Vec::new();
+    let mut current = vec![points[0]];
+    let (mut index, mut left) = (0, pattern[0]);
+    for segment in points.windows(2) {
+        let ([x0, y0], [x1, y1]) = (segment[0], segment[1]);
+        let length = (x1 - x0).hypot(y1 - y0);
+        let mut done = 0.0;
+        while length - done > left {
+            done += left;
+            let t = done / length;
+            let point = [x0 + t * (x1 - x0), y0 + t * (y1 - y0)];
+            // Dashes have even indices, gaps odd ones.
+            if index % 2 == 0 {
+                current.push(point);
+                dashes.push(std::mem::take(&mut current));
+            } else {
+                current = vec![point];
+            }
+            index = (index + 1) % pattern.len();
+            left = pattern[index];
+        }
+        left -= length - done;
+        if index % 2 == 0 {
+            current.push([x1, y1]);
+        }
+    }
+    if index % 2 == 0 && current.len() > 1 {
+        dashes.push(current);
+    }
+    dashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_style() {
+        let attributes: Params = [("stroke", "black"), ("fill", "none")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let style: Params = [
+            ("stroke", "#ff0000"),
+            ("font-size", "12pt"),
+            ("opacity", "0.5"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let style = Style::from_params(&attributes, &style);
+        assert_eq!(style.fill, None);
+        assert_eq!(
+            style.stroke,
+            Some(Color {
+                a: 0.5,
+                ..Color::rgb(1.0, 0.0, 0.0)
+            })
+        );
+        assert_eq!(style.font_size, 16.0);
+        assert_eq!(Color::parse("#fff"), Some(Color::WHITE));
+        assert_eq!(Color::parse("none"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "png")]
+    fn test_dashes() {
+        let dashes = dashes(&[[0.0, 0.0], [10.0, 0.0]], &[4.0, 3.0]);
+        assert_eq!(
+            dashes,
+            vec![vec![[0.0, 0.0], [4.0, 0.0]], vec![[7.0, 0.0], [10.0, 0.0]]]
+        );
+    }
+}
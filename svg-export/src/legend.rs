@@ -0,0 +1,433 @@
+// The legend of an axis lists the named plots, fills and reference lines,
+// each with a sample of its line, markers or fill next to its name. The
+// entries are laid out in columns, optionally grouped under headers, and
+// placed in a corner of the axis or outside of it.
+
+use crate::{
+    metrics::measure,
+    svg::{self, opts, Tag},
+};
+
+/// An element listed in the legend, if it has a name.
+pub(crate) trait LegendEntry {
+    fn name(&self) -> &str;
+
+    /// The group under which the element is listed, if the legend shows
+    /// group headers.
+    fn group(&self) -> &str;
+
+    /// The sample of the element in the legend, from `x0` to `x1` centered
+    /// at the height `y` (in pixels).
+    fn legend_glyph(&self, x0: f64, x1: f64, y: f64) -> Vec<Box<dyn svg::RenderTag>>;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LegendPosition {
+    /// The corner of the axis covering the fewest data points.
+    #[default]
+    Automatic,
+    UpperRight,
+    UpperLeft,
+    LowerLeft,
+    LowerRight,
+    /// Right of the axis, aligned with its top.
+    OutsideRight,
+    /// Below the axis and its label, centered.
+    Below,
+}
+
+impl LegendPosition {
+    pub const ALL: [LegendPosition; 7] = [
+        LegendPosition::Automatic,
+        LegendPosition::UpperRight,
+        LegendPosition::UpperLeft,
+        LegendPosition::LowerLeft,
+        LegendPosition::LowerRight,
+        LegendPosition::OutsideRight,
+        LegendPosition::Below,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LegendPosition::Automatic => "Automatic",
+            LegendPosition::UpperRight => "Upper Right",
+            LegendPosition::UpperLeft => "Upper Left",
+            LegendPosition::LowerLeft => "Lower Left",
+            LegendPosition::LowerRight => "Lower Right",
+            LegendPosition::OutsideRight => "Outside Right",
+            LegendPosition::Below => "Below",
+        }
+    }
+}
+
+/// Layout of the legend of an axis, see `Axis::with_legend_layout`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Legend {
+    position: LegendPosition,
+    /// Number of columns, chosen to fit the legend into the axis if `None`.
+    columns: Option<usize>,
+    /// Draw a box with a white background behind the legend.
+    frame: bool,
+    /// Font size in points.
+    font_size: f64,
+    font_family: String,
+    /// List the plots by group (see `LinePlot::with_group`), each group
+    /// under a header with its name.
+    group_headers: bool,
+}
+
+impl Default for Legend {
+    fn default() -> Self {
+        Self {
+            position: LegendPosition::default(),
+            columns: None,
+            frame: false,
+            font_size: 10.0,
+            font_family: "sans".to_string(),
+            group_headers: false,
+        }
+    }
+}
+
+/// A row of the legend.
+#[derive(Clone, Copy)]
+enum Row<'a> {
+    Header(&'a str),
+    Entry(&'a dyn LegendEntry),
+}
+
+/// Placement of the legend, in pixels.
+#[derive(Debug, PartialEq)]
+struct Layout {
+    /// Upper left corner.
+    origin: [f64; 2],
+    size: [f64; 2],
+    rows_per_column: usize,
+    column_widths: Vec<f64>,
+}
+
+impl Legend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_position(mut self, position: LegendPosition) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Use `columns` columns, or choose their number automatically if
+    /// zero.
+    pub fn with_columns(mut self, columns: usize) -> Self {
+        self.columns = (columns > 0).then_some(columns);
+        self
+    }
+
+    pub fn with_frame(mut self, flag: bool) -> Self {
+        self.frame = flag;
+        self
+    }
+
+    /// The font size, in points.
+    pub fn with_font_size(mut self, size: f64) -> Self {
+        self.font_size = size;
+        self
+    }
+
+    pub fn with_font_family(mut self, family: &str) -> Self {
+        self.font_family = family.to_string();
+        self
+    }
+
+    pub fn with_group_headers(mut self, flag: bool) -> Self {
+        self.group_headers = flag;
+        self
+    }
+
+    /// The legend of the named `entries` of the axis at `area` (`[x, y, width,
+    /// height]` in pixels). `clearance` is the space `[right, below]` the
+    /// axis taken by its ticks and labels, and `points` are the data points
+    /// in pixels, avoided by the automatic placement.
+    pub(crate) fn to_tags(
+        &self,
+        entries: &[&dyn LegendEntry],
+        area: [f64; 4],
+        clearance: [f64; 2],
+        points: &[[f64; 2]],
+    ) -> Vec<Box<dyn svg::RenderTag>> {
+        let rows = self.rows(entries);
+        if rows.is_empty() {
+            return Vec::new();
+        }
+        let Layout {
+            origin: [x0, y0],
+            size: [width, height],
+            rows_per_column,
+            column_widths,
+        } = self.layout(&rows, area, clearance, points);
+        let (font_size, row_height, sample, gap, padding, column_gap) = self.metrics();
+
+        let mut tags: Vec<Box<dyn svg::RenderTag>> = Vec::new();
+        if self.frame {
+            tags.push(Box::new(Tag::<svg::Rect>::new(
+                x0,
+                y0,
+                width,
+                height,
+                opts(&[
+                    ("fill", "white"),
+                    ("fill-opacity", "0.8"),
+                    ("stroke", "gray"),
+                    ("stroke-width", "0.8"),
+                ]),
+            )));
+        }
+
+        let font_size_pt = format!("{}pt", self.font_size);
+        let mut x = x0 + padding;
+        for (column, column_width) in rows.chunks(rows_per_column).zip(column_widths) {
+            for (i, row) in column.iter().enumerate() {
+                let center = y0 + padding + (i as f64 + 0.5) * row_height;
+                // The baseline is below the center of the lower case letters.
+                let baseline = center + 0.35 * font_size;
+                let (text, x_text, weight) = match row {
+                    Row::Header(name) => (*name, x, "bold"),
+                    Row::Entry(entry) => {
+                        tags.extend(entry.legend_glyph(x, x + sample, center));
+                        (entry.name(), x + sample + gap, "normal")
+                    }
+                };
+                tags.push(Box::new(Tag::<svg::Text>::new(
+                    x_text,
+                    baseline,
+                    0.0,
+                    text,
+                    opts(&[
+                        ("fill", "black"),
+                        ("font-family", &self.font_family),
+                        ("font-size", &font_size_pt),
+                        ("font-weight", weight),
+                    ]),
+                )));
+            }
+            x += column_width + column_gap;
+        }
+        tags
+    }
+
+    /// The rows of the named `entries`, in the order of their groups if
+    /// group headers are drawn.
+    fn rows<'a>(&self, entries: &[&'a dyn LegendEntry]) -> Vec<Row<'a>> {
+        let named: Vec<&dyn LegendEntry> = entries
+            .iter()
+            .copied()
+            .filter(|e| !e.name().is_empty())
+            .collect();
+        if !self.group_headers || named.iter().all(|e| e.group().is_empty()) {
+            return named.into_iter().map(Row::Entry).collect();
+        }
+        let mut groups: Vec<&str> = Vec::new();
+        for entry in named.iter() {
+            if !groups.contains(&entry.group()) {
+                groups.push(entry.group());
+            }
+        }
+        let mut rows = Vec::new();
+        for group in groups {
+            if !group.is_empty() {
+                rows.push(Row::Header(group));
+            }
+            rows.extend(
+                named
+                    .iter()
+                    .filter(|e| e.group() == group)
+                    .map(|e| Row::Entry(*e)),
+            );
+        }
+        rows
+    }
+
+    /// The space `[right, below]` taken by the legend of `entries` outside
+    /// of the axis at `area` (`[x, y, width, height]` in pixels).
+    pub(crate) fn outside_extent(&self, entries: &[&dyn LegendEntry], area: [f64; 4]) -> [f64; 2] {
+        let rows = self.rows(entries);
+        if rows.is_empty() {
+            return [0.0, 0.0];
+        }
+        let Layout {
+            size: [width, height],
+            ..
+        } = self.layout(&rows, area, [0.0, 0.0], &[]);
+        let margin = 0.5 * self.metrics().0;
+        match self.position {
+            LegendPosition::OutsideRight => [margin + width, 0.0],
+            LegendPosition::Below => [0.0, margin + height],
+            _ => [0.0, 0.0],
+        }
+    }
+
+    /// Font size, row height, length of the line samples, gap between
+    /// sample and text, padding and gap between columns, in pixels.
+    fn metrics(&self) -> (f64, f64, f64, f64, f64, f64) {
+        let font_size = self.font_size * 4.0 / 3.0;
+        (
+            font_size,
+            1.4 * font_size,
+            2.0 * font_size,
+            0.5 * font_size,
+            0.5 * font_size,
+            font_size,
+        )
+    }
+
+    fn layout(
+        &self,
+        rows: &[Row],
+        [ax, ay, aw, ah]: [f64; 4],
+        [right, below]: [f64; 2],
+        points: &[[f64; 2]],
+    ) -> Layout {
+        let (font_size, row_height, sample, gap, padding, column_gap) = self.metrics();
+        let margin = 0.5 * font_size;
+        let row_widths: Vec<f64> = rows
+            .iter()
+            .map(|row| match row {
+                // Bold text is a bit wider.
+                Row::Header(name) => 1.1 * measure(name, font_size).width,
+                Row::Entry(entry) => sample + gap + measure(entry.name(), font_size).width,
+            })
+            .collect();
+        let size_with = |columns: usize| {
+            let rows_per_column = rows.len().div_ceil(columns);
+            let column_widths: Vec<f64> = row_widths
+                .chunks(rows_per_column)
+                .map(|widths| widths.iter().copied().fold(0.0, f64::max))
+                .collect();
+            let width = 2.0 * padding
+                + column_widths.iter().sum::<f64>()
+                + column_gap * (column_widths.len() - 1) as f64;
+            let height = 2.0 * padding + rows_per_column as f64 * row_height;
+            (rows_per_column, column_widths, [width, height])
+        };
+
+        // Legends inside of and right of the axis get more columns until
+        // they fit its height, legends below get as many columns as fit
+        // its width.
+        let columns = match (self.columns, self.position) {
+            (Some(columns), _) => columns.clamp(1, rows.len()),
+            (None, LegendPosition::Below) => (1..=rows.len())
+                .take_while(|n| *n == 1 || size_with(*n).2[0] <= aw)
+                .last()
+                .unwrap_or(1),
+            (None, _) => (1..=rows.len())
+                .find(|n| size_with(*n).2[1] <= ah - 2.0 * margin)
+                .unwrap_or(rows.len()),
+        };
+        let (rows_per_column, column_widths, [width, height]) = size_with(columns);
+
+        let corner = |position| match position {
+            LegendPosition::UpperLeft => [ax + margin, ay + margin],
+            LegendPosition::LowerLeft => [ax + margin, ay + ah - margin - height],
+            LegendPosition::LowerRight => [ax + aw - margin - width, ay + ah - margin - height],
+            _ => [ax + aw - margin - width, ay + margin],
+        };
+        let origin = match self.position {
+            LegendPosition::Automatic => {
+                let covered = |[x0, y0]: [f64; 2]| {
+                    points
+                        .iter()
+                        .filter(|[x, y]| {
+                            (x0..=x0 + width).contains(x) && (y0..=y0 + height).contains(y)
+                        })
+                        .count()
+                };
+                // The first corner wins on ties.
+                let best = [
+                    LegendPosition::UpperRight,
+                    LegendPosition::UpperLeft,
+                    LegendPosition::LowerRight,
+                    LegendPosition::LowerLeft,
+                ]
+                .into_iter()
+                .min_by_key(|position| covered(corner(*position)))
+                .unwrap_or(LegendPosition::UpperRight);
+                corner(best)
+            }
+            LegendPosition::OutsideRight => [ax + aw + right + margin, ay],
+            LegendPosition::Below => [ax + 0.5 * (aw - width), ay + ah + below + margin],
+            position => corner(position),
+        };
+        Layout {
+            origin,
+            size: [width, height],
+            rows_per_column,
+            column_widths,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plotting::LinePlot;
+
+    fn entries(plots: &[LinePlot]) -> Vec<&dyn LegendEntry> {
+        plots.iter().map(|p| p as &dyn LegendEntry).collect()
+    }
+
+    fn plots(n: usize) -> Vec<LinePlot> {
+        (0..n)
+            .map(|i| {
+                LinePlot::new(&[0.0, 1.0], &[0.0, 1.0])
+                    .with_name(&format!("line {i}"))
+                    .with_group(if i % 2 == 0 { "even" } else { "odd" })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rows() {
+        let plots = plots(3);
+        let legend = Legend::new();
+        assert_eq!(legend.rows(&entries(&plots)).len(), 3);
+        let rows = legend.with_group_headers(true).rows(&entries(&plots));
+        let names: Vec<&str> = rows
+            .iter()
+            .map(|row| match row {
+                Row::Header(name) => name,
+                Row::Entry(entry) => entry.name(),
+            })
+            .collect();
+        assert_eq!(names, ["even", "line 0", "line 2", "odd", "line 1"]);
+    }
+
+    #[test]
+    fn test_layout() {
+        let plots = plots(30);
+        let legend = Legend::new();
+        let rows = legend.rows(&entries(&plots));
+        let area = [0.0, 0.0, 400.0, 320.0];
+
+        // 30 rows of 18.7 pixels do not fit into one column.
+        let layout = legend.layout(&rows, area, [0.0, 0.0], &[]);
+        assert_eq!(layout.column_widths.len(), 2);
+        assert_eq!(layout.rows_per_column, 15);
+        assert!(layout.size[1] <= 320.0);
+        // Upper right corner by default.
+        assert!((layout.origin[0] + layout.size[0] - 400.0 + 20.0 / 3.0).abs() < 1e-9);
+
+        let legend = legend.with_columns(3);
+        let layout = legend.layout(&rows, area, [0.0, 0.0], &[]);
+        assert_eq!(layout.rows_per_column, 10);
+
+        // Data in the upper right corner moves the legend to the left.
+        let rows = &rows[..2];
+        let layout = legend.layout(rows, area, [0.0, 0.0], &[[390.0, 10.0]]);
+        assert_eq!(layout.origin, [20.0 / 3.0, 20.0 / 3.0]);
+
+        let legend = legend.with_position(LegendPosition::OutsideRight);
+        let layout = legend.layout(rows, area, [50.0, 0.0], &[]);
+        assert_eq!(layout.origin, [400.0 + 50.0 + 20.0 / 3.0, 0.0]);
+    }
+}
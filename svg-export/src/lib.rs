@@ -1,4 +1,24 @@
+mod backend;
+mod legend;
+pub mod markup;
+mod metrics;
+mod pdf;
 mod plotting;
+#[cfg(feature = "png")]
+mod raster;
+#[cfg(test)]
+mod snapshots;
 mod svg;
+mod theme;
 
-pub use plotting::{Axis, Figure, LinePlot, Text, Ticks};
+pub use backend::{Anchor, Backend, Color, Style};
+pub use legend::{Legend, LegendPosition};
+pub use metrics::{measure, TextExtent};
+pub use pdf::PdfBackend;
+pub use plotting::{
+    format_log_tick, Axis, Figure, FillBetween, LinePlot, LineStyle, Marker, Polygon, RefLine,
+    Scale, ScatterPlot, Span, Text, Ticks,
+};
+#[cfg(feature = "png")]
+pub use raster::RasterBackend;
+pub use theme::{FigureSize, Frame, SizeUnit, Theme, TickDirection};
@@ -0,0 +1,274 @@
+//! A LaTeX-like markup for labels: `^{...}` raises and `_{...}` lowers text,
+//! and commands like `\alpha` or `\times` insert symbols, e.g.
+//! `"Wavenumber / cm^{-1}"` or `"\Delta A \times 10^{3}"`. Scripts need
+//! braces, so underscores in plain text (e.g. file names) stay as they are.
+//! `\^`, `\_`, `\{`, `\}` and `\\` insert the characters themselves.
+
+/// Vertical position of a span of text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Script {
+    #[default]
+    Normal,
+    Super,
+    Sub,
+}
+
+impl Script {
+    /// Size of the text, relative to normal text.
+    pub fn scale(&self) -> f64 {
+        match self {
+            Script::Normal => 1.0,
+            Script::Super | Script::Sub => 0.7,
+        }
+    }
+
+    /// Shift of the baseline upwards, relative to the size of normal text.
+    pub fn rise(&self) -> f64 {
+        match self {
+            Script::Normal => 0.0,
+            Script::Super => 0.35,
+            Script::Sub => -0.2,
+        }
+    }
+}
+
+/// A piece of text set in one position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub script: Script,
+}
+
+/// Split `markup` into spans of text, with the commands replaced by their
+/// symbols. Nested scripts are set in the innermost position.
+pub fn parse(markup: &str) -> Vec<Span> {
+    let mut spans: Vec<Span> = Vec::new();
+    let mut push = |c: char, script: Script| match spans.last_mut() {
+        Some(span) if span.script == script => span.text.push(c),
+        _ => spans.push(Span {
+            text: c.to_string(),
+            script,
+        }),
+    };
+    let mut scripts = Vec::new();
+    let mut chars = markup.chars().peekable();
+    while let Some(c) = chars.next() {
+        let script = scripts.last().copied().unwrap_or_default();
+        match c {
+            '^' | '_' if chars.peek() == Some(&'{') => {
+                chars.next();
+                scripts.push(if c == '^' { Script::Super } else { Script::Sub });
+            }
+            '}' if !scripts.is_empty() => {
+                scripts.pop();
+            }
+            '\\' => {
+                let mut name = String::new();
+                while let Some(c) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+                    name.push(*c);
+                    chars.next();
+                }
+                if name.is_empty() {
+                    // An escaped character, or a trailing backslash.
+                    push(chars.next().unwrap_or('\\'), script);
+                    continue;
+                }
+                // Unlike in LaTeX, spaces after a command are kept, as in
+                // `\lambda / nm`.
+                match symbol(&name) {
+                    Some(symbol) => push(symbol, script),
+                    None => {
+                        push('\\', script);
+                        name.chars().for_each(|c| push(c, script));
+                    }
+                }
+            }
+            c => push(c, script),
+        }
+    }
+    spans
+}
+
+/// The text of `markup` without the markup.
+pub fn plain(markup: &str) -> String {
+    parse(markup).into_iter().map(|span| span.text).collect()
+}
+
+/// `markup` as plain text, with scripts set in Unicode super- and subscript
+/// characters where they exist, e.g. for labels of the egui plot. Other
+/// scripts are written as `^(...)` or `_(...)`.
+pub fn to_unicode(markup: &str) -> String {
+    let mut text = String::new();
+    for Span { text: span, script } in parse(markup) {
+        let (map, mark): (fn(char) -> Option<char>, char) = match script {
+            Script::Normal => {
+                text.push_str(&span);
+                continue;
+            }
+            Script::Super => (superscript, '^'),
+            Script::Sub => (subscript, '_'),
+        };
+        match span.chars().map(map).collect::<Option<String>>() {
+            Some(mapped) => text.push_str(&mapped),
+            None => text.push_str(&format!("{mark}({span})")),
+        }
+    }
+    text
+}
+
+fn superscript(c: char) -> Option<char> {
+    let mapped = match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' | '−' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'n' => 'ⁿ',
+        'i' => 'ⁱ',
+        ' ' => ' ',
+        _ => return None,
+    };
+    Some(mapped)
+}
+
+fn subscript(c: char) -> Option<char> {
+    let mapped = match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' | '−' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'o' => 'ₒ',
+        'x' => 'ₓ',
+        'h' => 'ₕ',
+        'k' => 'ₖ',
+        'l' => 'ₗ',
+        'm' => 'ₘ',
+        'n' => 'ₙ',
+        'p' => 'ₚ',
+        's' => 'ₛ',
+        't' => 'ₜ',
+        ' ' => ' ',
+        _ => return None,
+    };
+    Some(mapped)
+}
+
+/// The symbol of the command `\name`.
+fn symbol(name: &str) -> Option<char> {
+    let symbol = match name {
+        "alpha" => 'α',
+        "beta" => 'β',
+        "gamma" => 'γ',
+        "delta" => 'δ',
+        "epsilon" => 'ε',
+        "zeta" => 'ζ',
+        "eta" => 'η',
+        "theta" => 'θ',
+        "iota" => 'ι',
+        "kappa" => 'κ',
+        "lambda" => 'λ',
+        "mu" => 'μ',
+        "nu" => 'ν',
+        "xi" => 'ξ',
+        "pi" => 'π',
+        "rho" => 'ρ',
+        "sigma" => 'σ',
+        "tau" => 'τ',
+        "upsilon" => 'υ',
+        "phi" => 'φ',
+        "chi" => 'χ',
+        "psi" => 'ψ',
+        "omega" => 'ω',
+        "Gamma" => 'Γ',
+        "Delta" => 'Δ',
+        "Theta" => 'Θ',
+        "Lambda" => 'Λ',
+        "Xi" => 'Ξ',
+        "Pi" => 'Π',
+        "Sigma" => 'Σ',
+        "Upsilon" => 'Υ',
+        "Phi" => 'Φ',
+        "Psi" => 'Ψ',
+        "Omega" => 'Ω',
+        "times" => '×',
+        "cdot" => '·',
+        "pm" => '±',
+        "deg" | "circ" => '°',
+        "AA" => 'Å',
+        "infty" => '∞',
+        "approx" => '≈',
+        "leq" => '≤',
+        "geq" => '≥',
+        "to" | "rightarrow" => '→',
+        "minus" => '−',
+        _ => return None,
+    };
+    Some(symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, script: Script) -> Span {
+        Span {
+            text: text.to_string(),
+            script,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("cm^{-1}"),
+            vec![span("cm", Script::Normal), span("-1", Script::Super)]
+        );
+        assert_eq!(
+            parse("\\Delta A \\times 10^{3}"),
+            vec![span("Δ A × 10", Script::Normal), span("3", Script::Super)]
+        );
+        assert_eq!(
+            parse("k_{B}T"),
+            vec![
+                span("k", Script::Normal),
+                span("B", Script::Sub),
+                span("T", Script::Normal)
+            ]
+        );
+        // Plain text is left alone.
+        assert_eq!(plain("file_01.csv a^b {x}"), "file_01.csv a^b {x}");
+        assert_eq!(plain("\\foo \\_ \\{\\}"), "\\foo _ {}");
+    }
+
+    #[test]
+    fn test_to_unicode() {
+        assert_eq!(to_unicode("Wavenumber / cm^{-1}"), "Wavenumber / cm⁻¹");
+        assert_eq!(to_unicode("CO_{2}"), "CO₂");
+        assert_eq!(to_unicode("I_{max}"), "Iₘₐₓ");
+        assert_eq!(to_unicode("T_{C}"), "T_(C)");
+        assert_eq!(to_unicode("\\lambda / nm"), "λ / nm");
+    }
+}
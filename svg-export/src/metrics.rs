@@ -0,0 +1,100 @@
+// Text measurement with the metrics of the default sans font. The widths are
+// those of Helvetica, which Arial and Liberation Sans share, so that texts
+// take the same space in the SVG and PDF output. The PNG output draws its
+// texts in Ubuntu Light, whose widths differ slightly, into the same layout.
+
+use crate::markup::{self, Span};
+
+/// The font size of texts without one, in pixels, as in SVG.
+pub const DEFAULT_FONT_SIZE: f64 = 16.0;
+
+/// Height of the font above the baseline, relative to the font size.
+const ASCENT: f64 = 0.718;
+/// Depth of the font below the baseline, relative to the font size.
+const DESCENT: f64 = 0.207;
+
+/// The extent of a line of text, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextExtent {
+    pub width: f64,
+    /// Height above the baseline.
+    pub ascent: f64,
+    /// Depth below the baseline.
+    pub descent: f64,
+}
+
+impl TextExtent {
+    pub fn height(&self) -> f64 {
+        self.ascent + self.descent
+    }
+}
+
+/// Measure `text` (in the markup of the `markup` module) set in the default
+/// sans font of size `font_size` (in pixels).
+pub fn measure(text: &str, font_size: f64) -> TextExtent {
+    let spans = markup::parse(text);
+    let (mut ascent, mut descent) = (ASCENT, DESCENT);
+    for span in spans.iter() {
+        let (scale, rise) = (span.script.scale(), span.script.rise());
+        ascent = ascent.max(rise + scale * ASCENT);
+        descent = descent.max(scale * DESCENT - rise);
+    }
+    TextExtent {
+        width: markup_width(&spans) * font_size,
+        ascent: ascent * font_size,
+        descent: descent * font_size,
+    }
+}
+
+/// Width of the text of `spans`, relative to the font size.
+pub(crate) fn markup_width(spans: &[Span]) -> f64 {
+    spans
+        .iter()
+        .map(|span| text_width(&span.text) * span.script.scale())
+        .sum()
+}
+
+/// Width of the plain `text`, relative to the font size.
+pub fn text_width(text: &str) -> f64 {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' => HELVETICA_WIDTHS[(c as u8 - b' ') as usize],
+            // Most other characters (digits of other scripts, Greek letters
+            // or the minus sign) are about as wide as a digit.
+            _ => 556,
+        })
+        .sum::<u32>() as f64
+        / 1000.0
+}
+
+/// Widths of the printable ASCII characters in Helvetica, in thousandths of
+/// the font size.
+#[rustfmt::skip]
+const HELVETICA_WIDTHS: [u32; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure() {
+        assert!((text_width("1.0") - 1.39).abs() < 1e-9);
+        let extent = measure("10", 10.0);
+        assert!((extent.width - 11.12).abs() < 1e-9);
+        assert!((extent.height() - 9.25).abs() < 1e-9);
+        // Unknown characters are as wide as digits.
+        assert_eq!(text_width("\u{3b4}"), text_width("0"));
+        // Scripts are smaller, but extend the line.
+        let extent = measure("cm^{-1}", 10.0);
+        assert!((extent.width - 10.0 * (text_width("cm") + 0.7 * text_width("-1"))).abs() < 1e-9);
+        assert!(extent.ascent > 7.18);
+        assert!((extent.descent - 2.07).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,315 @@
+// Backend writing a `Figure` into a single page vector PDF. Texts are set in
+// the standard Helvetica font, and Greek letters and math symbols in the
+// standard Symbol font, which PDF viewers provide, so no font has to be
+// embedded.
+
+use std::fmt::Write;
+
+use crate::backend::{Backend, Color, Style};
+use crate::metrics::text_width;
+use crate::svg::num;
+
+/// PDF points per pixel of the figure (CSS pixels at 96 DPI).
+const POINTS_PER_PIXEL: f64 = 0.75;
+
+pub struct PdfBackend {
+    width: f64,
+    height: f64,
+    content: String,
+    /// The opacities used, each is set by its own graphics state.
+    opacities: Vec<f64>,
+}
+
+impl PdfBackend {
+    /// A backend drawing a figure of `width` x `height` pixels.
+    pub fn new(width: u64, height: u64) -> Self {
+        let mut content = String::new();
+        // Draw in pixels with the origin in the upper left corner.
+        writeln!(
+            content,
+            "{} 0 0 {} 0 {} cm 1 j 1 J",
+            num(POINTS_PER_PIXEL),
+            num(-POINTS_PER_PIXEL),
+            num(height as f64 * POINTS_PER_PIXEL)
+        )
+        .expect(FAILED_STRING_WRITE);
+        Self {
+            width: width as f64,
+            height: height as f64,
+            content,
+            opacities: Vec::new(),
+        }
+    }
+
+    /// The raw PDF document.
+    pub fn finish(self) -> Vec<u8> {
+        let graphics_states: String = self
+            .opacities
+            .iter()
+            .enumerate()
+            .map(|(i, a)| format!("/GS{i} << /ca {a} /CA {a} >> ", a = num(*a)))
+            .collect();
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Contents 4 0 R \
+                 /Resources << /Font << /F1 5 0 R /F2 6 0 R >> \
+                 /ExtGState << {graphics_states}>> >> >>",
+                num(self.width * POINTS_PER_PIXEL),
+                num(self.height * POINTS_PER_PIXEL),
+            ),
+            format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                self.content.len(),
+                self.content
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Symbol >>".to_string(),
+        ];
+
+        let mut pdf: Vec<u8> = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n{object}\nendobj\n", i + 1).into_bytes());
+        }
+        let xref = pdf.len();
+        let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            writeln!(trailer, "{offset:010} 00000 n ").expect(FAILED_STRING_WRITE);
+        }
+        write!(
+            trailer,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        )
+        .expect(FAILED_STRING_WRITE);
+        pdf.extend(trailer.into_bytes());
+        pdf
+    }
+
+    /// Set the opacity `alpha` for the following drawing operations.
+    fn set_opacity(&mut self, alpha: f64) {
+        if alpha >= 1.0 {
+            return;
+        }
+        let alpha = (alpha * 100.0).round() / 100.0;
+        let index = match self.opacities.iter().position(|a| *a == alpha) {
+            Some(index) => index,
+            None => {
+                self.opacities.push(alpha);
+                self.opacities.len() - 1
+            }
+        };
+        writeln!(self.content, "/GS{index} gs").expect(FAILED_STRING_WRITE);
+    }
+
+    fn path(&mut self, points: &[[f64; 2]]) {
+        for (i, [x, y]) in points.iter().enumerate() {
+            let op = if i == 0 { "m" } else { "l" };
+            writeln!(self.content, "{} {} {op}", num(*x), num(*y)).expect(FAILED_STRING_WRITE);
+        }
+    }
+}
+
+impl Backend for PdfBackend {
+    fn stroke(&mut self, points: &[[f64; 2]], closed: bool, style: &Style) {
+        let Some(color) = style.stroke else {
+            return;
+        };
+        self.content.push_str("q\n");
+        self.set_opacity(color.a);
+        let dasharray: Vec<String> = style.dasharray.iter().map(|d| num(*d)).collect();
+        writeln!(
+            self.content,
+            "{} RG {} w [{}] 0 d",
+            rgb(color),
+            num(style.stroke_width),
+            dasharray.join(" ")
+        )
+        .expect(FAILED_STRING_WRITE);
+        self.path(points);
+        self.content
+            .push_str(if closed { "h S\nQ\n" } else { "S\nQ\n" });
+    }
+
+    fn fill(&mut self, points: &[[f64; 2]], style: &Style) {
+        let Some(color) = style.fill else {
+            return;
+        };
+        self.content.push_str("q\n");
+        self.set_opacity(color.a);
+        writeln!(self.content, "{} rg", rgb(color)).expect(FAILED_STRING_WRITE);
+        self.path(points);
+        self.content.push_str("h f\nQ\n");
+    }
+
+    fn text(&mut self, text: &str, [x, y]: [f64; 2], angle: f64, style: &Style) {
+        let Some(color) = style.fill else {
+            return;
+        };
+        let size = style.font_size;
+        let width = self.text_width(text, style);
+        let (sin, cos) = angle.to_radians().sin_cos();
+        // The text space is flipped back, as the y-axis points downwards.
+        let shift = style.anchor.offset() * width;
+        let (x, y) = (x - shift * cos, y - shift * sin);
+        self.content.push_str("q\n");
+        self.set_opacity(color.a);
+        write!(
+            self.content,
+            "{} rg BT {} {} {} {} {} {} Tm",
+            rgb(color),
+            num(cos),
+            num(sin),
+            num(sin),
+            num(-cos),
+            num(x),
+            num(y),
+        )
+        .expect(FAILED_STRING_WRITE);
+        // Each run continues where the previous one ended.
+        for (font, run) in runs(text) {
+            write!(self.content, " /F{font} {} Tf ({run}) Tj", num(size))
+                .expect(FAILED_STRING_WRITE);
+        }
+        self.content.push_str(" ET\nQ\n");
+    }
+
+    fn text_width(&self, text: &str, style: &Style) -> f64 {
+        let width: f64 = text
+            .chars()
+            .map(|c| match symbol(c) {
+                Some((_, width)) => width as f64 / 1000.0,
+                None => text_width(c.encode_utf8(&mut [0; 4])),
+            })
+            .sum();
+        width * style.font_size
+    }
+}
+
+const FAILED_STRING_WRITE: &str = "Failed to write into string.";
+
+fn rgb(color: Color) -> String {
+    format!("{} {} {}", num(color.r), num(color.g), num(color.b))
+}
+
+/// Split `text` into runs of the Helvetica (`1`) and the Symbol font (`2`),
+/// each encoded as a PDF string. Characters neither font can represent are
+/// replaced by `?`.
+fn runs(text: &str) -> Vec<(u8, String)> {
+    let mut runs: Vec<(u8, String)> = Vec::new();
+    for c in text.chars() {
+        let (font, byte) = match symbol(c) {
+            Some((byte, _)) => (2, byte),
+            None => (1, winansi(c)),
+        };
+        if runs.last().is_none_or(|(last, _)| *last != font) {
+            runs.push((font, String::new()));
+        }
+        if let Some((_, run)) = runs.last_mut() {
+            push_byte(run, byte);
+        }
+    }
+    runs
+}
+
+/// Append `byte` to the PDF string `escaped`.
+fn push_byte(escaped: &mut String, byte: u8) {
+    match byte {
+        b'(' | b')' | b'\\' => {
+            escaped.push('\\');
+            escaped.push(byte as char);
+        }
+        0x20..=0x7e => escaped.push(byte as char),
+        byte => write!(escaped, "\\{byte:03o}").expect(FAILED_STRING_WRITE),
+    }
+}
+
+/// The code and width (in thousandths of the font size) of the Greek
+/// letters and math symbols, which are set in the Symbol font.
+fn symbol(c: char) -> Option<(u8, u32)> {
+    let symbol = match c {
+        'α' => (b'a', 631),
+        'β' => (b'b', 549),
+        'γ' => (b'g', 411),
+        'δ' => (b'd', 494),
+        'ε' => (b'e', 439),
+        'ζ' => (b'z', 494),
+        'η' => (b'h', 603),
+        'θ' => (b'q', 521),
+        'ι' => (b'i', 329),
+        'κ' => (b'k', 549),
+        'λ' => (b'l', 549),
+        'μ' => (b'm', 576),
+        'ν' => (b'n', 521),
+        'ξ' => (b'x', 493),
+        'π' => (b'p', 549),
+        'ρ' => (b'r', 549),
+        'σ' => (b's', 603),
+        'τ' => (b't', 439),
+        'υ' => (b'u', 576),
+        'φ' => (b'f', 521),
+        'χ' => (b'c', 549),
+        'ψ' => (b'y', 686),
+        'ω' => (b'w', 686),
+        'Γ' => (b'G', 603),
+        'Δ' => (b'D', 612),
+        'Θ' => (b'Q', 741),
+        'Λ' => (b'L', 686),
+        'Ξ' => (b'X', 645),
+        'Π' => (b'P', 768),
+        'Σ' => (b'S', 592),
+        'Υ' => (b'U', 690),
+        'Φ' => (b'F', 763),
+        'Ψ' => (b'Y', 795),
+        'Ω' => (b'W', 768),
+        '∞' => (0xa5, 713),
+        '≈' => (0xbb, 549),
+        '≤' => (0xa3, 549),
+        '≥' => (0xb3, 549),
+        '→' => (0xae, 987),
+        _ => return None,
+    };
+    Some(symbol)
+}
+
+fn winansi(c: char) -> u8 {
+    match c {
+        ' '..='~' => c as u8,
+        '\u{a0}'..='\u{ff}' => c as u32 as u8,
+        '\u{2212}' | '\u{2013}' => 0x96,
+        '\u{2014}' => 0x97,
+        '\u{2022}' => 0x95,
+        '\u{2026}' => 0x85,
+        '\u{20ac}' => 0x80,
+        _ => b'?',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pdf_helpers() {
+        assert_eq!(num(1.5), "1.5");
+        assert_eq!(num(-0.0001), "0");
+        assert_eq!(num(2.0), "2");
+        assert_eq!(
+            runs("a(b)\u{b5}\u{4e2d}"),
+            vec![(1, "a\\(b\\)\\265?".to_string())]
+        );
+        assert_eq!(
+            runs("\u{394}A / \u{3bc}m"),
+            vec![
+                (2, "D".to_string()),
+                (1, "A / ".to_string()),
+                (2, "m".to_string()),
+                (1, "m".to_string())
+            ]
+        );
+    }
+}
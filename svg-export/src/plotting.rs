@@ -2,7 +2,14 @@
 
 use std::{collections::HashMap, fmt::Write};
 
-use crate::svg::{self, opts, Params, Tag};
+use crate::{
+    backend::Backend,
+    legend::{Legend, LegendEntry},
+    metrics::{measure, DEFAULT_FONT_SIZE},
+    pdf::PdfBackend,
+    svg::{self, opts, Params, Tag},
+    theme::{Frame, Theme},
+};
 
 /// The basic plotting primitive. Can be converted into a Vec of `svg::Tag`.
 trait Element {
@@ -14,6 +21,11 @@ trait Element {
     fn to_tags(&self, ax: &Axis, fig: &Figure) -> Vec<Box<dyn svg::RenderTag>>;
     fn add_svg_property(&mut self, key: &str, value: &str);
     fn identifier(&self) -> &str;
+
+    /// The entry of the element in the legend, if it can be listed there.
+    fn legend_entry(&self) -> Option<&dyn LegendEntry> {
+        None
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -53,10 +65,46 @@ impl Figure {
         }
     }
 
-    pub fn add_axis(&mut self, ax: Axis) {
+    /// Add `ax` to the figure. Axes with automatic margins are fitted into
+    /// their area, and aligned with the other axes in the same column.
+    pub fn add_axis(&mut self, mut ax: Axis) {
+        if let Some([u, _, width, _]) = ax.outer {
+            ax.fit_margins(self);
+            let same_column = |other: &Axis| {
+                other
+                    .outer
+                    .is_some_and(|outer| outer[0] == u && outer[2] == width)
+            };
+            let left = self
+                .axes
+                .iter()
+                .filter(|other| same_column(other))
+                .map(|other| other.u)
+                .fold(ax.u, f64::max);
+            let right = self
+                .axes
+                .iter()
+                .filter(|other| same_column(other))
+                .map(|other| other.u + other.width)
+                .fold(ax.u + ax.width, f64::min);
+            for other in self
+                .axes
+                .iter_mut()
+                .chain(std::iter::once(&mut ax))
+                .filter(|other| same_column(other))
+            {
+                other.u = left;
+                other.width = right - left;
+            }
+        }
         self.axes.push(ax);
     }
 
+    /// The axes of the figure, in the order they were added.
+    pub fn axes(&self) -> &[Axis] {
+        &self.axes
+    }
+
     /// Render this `Figure` to raw SVG markup.
     pub fn render(&self) -> String {
         let mut root = Tag::<svg::SVG>::new(self.width, self.height, None);
@@ -66,6 +114,31 @@ impl Figure {
         }
         crate::svg::render(&root)
     }
+
+    /// Draw this `Figure` with `backend`, e.g. into another format than SVG.
+    pub fn draw(&self, backend: &mut dyn Backend) {
+        for ax in self.axes.iter() {
+            for tag in ax.to_tags(self) {
+                tag.draw(backend);
+            }
+        }
+    }
+
+    /// Render this `Figure` to a vector PDF document.
+    pub fn render_pdf(&self) -> Vec<u8> {
+        let mut backend = PdfBackend::new(self.width, self.height);
+        self.draw(&mut backend);
+        backend.finish()
+    }
+
+    /// Rasterise this `Figure` to a PNG image with a resolution of `dpi`
+    /// (where 96 DPI yield one pixel per pixel of the figure).
+    #[cfg(feature = "png")]
+    pub fn render_png(&self, dpi: f64) -> Result<Vec<u8>, String> {
+        let mut backend = crate::raster::RasterBackend::new(self.width, self.height, dpi)?;
+        self.draw(&mut backend);
+        backend.finish(dpi)
+    }
 }
 
 impl Default for Figure {
@@ -82,24 +155,43 @@ impl Default for Figure {
 //
 // ----------------------------------------------------------------------------
 
+/// Gap between the axis and its tick labels, in pixels.
+const TICK_LABEL_PAD: f64 = 6.0;
+/// Gap between the tick labels and the axis labels, in pixels.
+const AXIS_LABEL_PAD: f64 = 8.0;
+/// Distance of axes with automatic margins from the edges of their area, in
+/// pixels.
+const OUTER_PAD: f64 = 8.0;
+
 /// The container for plots and other elements.
 pub struct Axis {
-    draw_legend: bool,
+    legend: Option<Legend>,
     draw_xaxis: bool,
     draw_yaxis: bool,
+    draw_xtick_labels: bool,
     elements: Vec<Box<dyn Element>>,
     xlabel: String,
     ylabel: String,
+    y2label: String,
     height: f64,
     limits: [f64; 4],
+    /// Limits of the secondary (right) y-axis, if it is used.
+    y2limits: Option<[f64; 2]>,
+    xscale: Scale,
+    /// Scale of the primary and secondary y-axis.
+    yscale: Scale,
     plots: Vec<LinePlot>,
     style: svg::Params,
+    theme: Theme,
     pub ticks: Ticks,
     /// u coordinate for placement in Figure, normalized to [0, 1]
     u: f64,
     /// v coordinate for placement in Figure, normalized to [0, 1]
     v: f64,
     width: f64,
+    /// The area `[u, v, width, height]` of the axis including its ticks and
+    /// labels, if its margins are fitted automatically.
+    outer: Option<[f64; 4]>,
 }
 
 impl Axis {
@@ -110,18 +202,63 @@ impl Axis {
             width,
             height,
             limits: [0.0, 1.0, 0.0, 1.0],
+            y2limits: None,
+            xscale: Scale::default(),
+            yscale: Scale::default(),
             ticks: Default::default(),
-            draw_legend: false,
+            legend: None,
             draw_xaxis: true,
             draw_yaxis: true,
+            draw_xtick_labels: true,
             plots: Vec::new(),
             elements: Vec::new(),
             style: element_opts(&[("fill", "none"), ("stroke", "none")]),
+            theme: Theme::default(),
             xlabel: "".to_string(),
             ylabel: "".to_string(),
+            y2label: "".to_string(),
+            outer: None,
         }
     }
 
+    /// Create `n` axes stacked vertically (from top to bottom) within the
+    /// area of the default axis, separated by `gap`.
+    pub fn stacked(n: usize, gap: f64) -> Vec<Self> {
+        let Axis {
+            u,
+            v,
+            width,
+            height,
+            ..
+        } = Axis::default();
+        Axis::stacked_in([u, v, width, height], n, gap)
+    }
+
+    /// Create `n` axes stacked vertically (from top to bottom) within the
+    /// area `[u, v, width, height]` of the figure, separated by `gap`.
+    pub fn stacked_in([u, v, width, height]: [f64; 4], n: usize, gap: f64) -> Vec<Self> {
+        let panel_height = (height - gap * n.saturating_sub(1) as f64) / n as f64;
+        (0..n)
+            .map(|i| Axis::new(u, v + i as f64 * (panel_height + gap), width, panel_height))
+            .collect()
+    }
+
+    /// Create an axis placed inside of this axis, e.g. for a magnified inset.
+    /// `u` and `v` are the axis coordinates of its lower left corner (from 0
+    /// to 1 from left to right and bottom to top), `width` and `height` are
+    /// relative to the size of this axis. The inset has a white background
+    /// to hide the elements of this axis below it, and the same theme.
+    pub fn inset(&self, u: f64, v: f64, width: f64, height: f64) -> Self {
+        Axis::new(
+            self.u + u * self.width,
+            self.v + (1.0 - v - height) * self.height,
+            width * self.width,
+            height * self.height,
+        )
+        .with_background("white")
+        .with_theme(&self.theme)
+    }
+
     pub fn xlim(&mut self, xmin: f64, xmax: f64) {
         self.limits[0] = xmin;
         self.limits[1] = xmax;
@@ -132,14 +269,70 @@ impl Axis {
         self.limits[3] = ymax;
     }
 
-    pub fn add_line(&mut self, line: LinePlot) {
+    /// Set the limits of the secondary y-axis on the right, which is used
+    /// by lines added with `LinePlot::with_secondary_y`.
+    pub fn y2lim(&mut self, ymin: f64, ymax: f64) {
+        self.y2limits = Some([ymin, ymax]);
+    }
+
+    /// Add a line, which takes the next color of the color cycle of the
+    /// theme if it has no color.
+    pub fn add_line(&mut self, mut line: LinePlot) {
+        if !line.style.contains_key("stroke") {
+            if let Some(color) = self.theme.cycle_color(self.plots.len()) {
+                line.set_color(color);
+            }
+        }
         self.plots.push(line);
     }
 
+    /// Add a scatter plot, which is listed in the legend like the lines.
+    pub fn add_scatter(&mut self, scatter: ScatterPlot) {
+        self.add_line(scatter.line);
+    }
+
     pub fn add_label(&mut self, label: Text) {
         self.elements.push(Box::new(label));
     }
 
+    /// Add a filled area, which is drawn below the lines and not listed in
+    /// the legend.
+    pub fn add_area(&mut self, area: Polygon) {
+        self.elements.push(Box::new(area));
+    }
+
+    /// Add the area between two curves, e.g. a band of the standard
+    /// deviation around a mean. It is drawn below the lines and listed in
+    /// the legend if it has a name.
+    pub fn add_fill(&mut self, fill: FillBetween) {
+        self.elements.push(Box::new(fill));
+    }
+
+    /// Highlight a range of x- or y-values over the whole axis.
+    pub fn add_span(&mut self, span: Span) {
+        self.elements.push(Box::new(span));
+    }
+
+    /// Add a vertical or horizontal line over the whole axis, e.g. at the
+    /// position of a known band.
+    pub fn add_ref_line(&mut self, line: RefLine) {
+        self.elements.push(Box::new(line));
+    }
+
+    /// Mark the region `[xmin, xmax, ymin, ymax]` (in data coordinates) with
+    /// a dashed box, e.g. the region magnified in an inset.
+    pub fn add_marker_box(&mut self, region: [f64; 4]) {
+        let [xmin, xmax, ymin, ymax] = region;
+        let line = LinePlot::new(
+            &[xmin, xmax, xmax, xmin, xmin],
+            &[ymin, ymin, ymax, ymax, ymin],
+        )
+        .with_color("black")
+        .with_linewidth(1.0)
+        .with_dasharray("4 3");
+        self.elements.push(Box::new(line));
+    }
+
     pub fn with_xlim(mut self, xmin: f64, xmax: f64) -> Self {
         self.xlim(xmin, xmax);
         self.autoticks();
@@ -162,6 +355,68 @@ impl Axis {
         self
     }
 
+    pub fn with_xscale(mut self, scale: Scale) -> Self {
+        self.xscale = scale;
+        self.autoticks();
+        self
+    }
+
+    pub fn with_yscale(mut self, scale: Scale) -> Self {
+        self.yscale = scale;
+        self.autoticks();
+        self
+    }
+
+    pub fn with_y2lim(mut self, ymin: f64, ymax: f64) -> Self {
+        self.y2lim(ymin, ymax);
+        self.autoticks();
+        self
+    }
+
+    pub fn with_y2label(mut self, text: &str) -> Self {
+        self.y2label = text.to_owned();
+        self
+    }
+
+    /// Place the axis with its ticks, labels and a legend outside of it into
+    /// its current area, instead of the axis alone. The margins are fitted
+    /// to the extents of the labels when the axis is added to a figure.
+    pub fn with_auto_margins(mut self, flag: bool) -> Self {
+        self.outer = flag.then_some([self.u, self.v, self.width, self.height]);
+        self
+    }
+
+    /// Shrink the axis to leave room for its ticks and labels within the
+    /// area given by `with_auto_margins`, measured for the size of `fig`.
+    /// Only necessary before the axis is added to `fig`, e.g. to place an
+    /// inset.
+    pub fn fit_margins(&mut self, fig: &Figure) {
+        let Some([u, v, width, height]) = self.outer else {
+            return;
+        };
+        let (fw, fh) = (fig.width as f64, fig.height as f64);
+        let [left, mut right, mut bottom, top] = self.label_extents();
+        if let Some(legend) = &self.legend {
+            let area = [fw * u, fh * v, fw * width, fh * height];
+            let [legend_right, legend_below] = legend.outside_extent(&self.legend_entries(), area);
+            right += legend_right;
+            bottom += legend_below;
+        }
+        let [left, right, bottom, top] = [left, right, bottom, top].map(|m| m + OUTER_PAD);
+        // A tenth of the area is kept for the plot in any case.
+        self.u = u + left / fw;
+        self.v = v + top / fh;
+        self.width = (width - (left + right) / fw).max(0.1 * width);
+        self.height = (height - (top + bottom) / fh).max(0.1 * height);
+    }
+
+    /// Leave a margin (normalized to the figure width) to the right of the
+    /// axis, e.g. for the ticks and label of the secondary y-axis.
+    pub fn with_right_margin(mut self, margin: f64) -> Self {
+        self.width = 1.0 - self.u - margin;
+        self
+    }
+
     pub fn draw_xaxis(mut self, should_draw: bool) -> Self {
         self.draw_xaxis = should_draw;
         self
@@ -172,6 +427,13 @@ impl Axis {
         self
     }
 
+    /// Whether the labels of the x-ticks are drawn, e.g. only for the lowest
+    /// of several axes sharing the x-axis.
+    pub fn with_xtick_labels(mut self, flag: bool) -> Self {
+        self.draw_xtick_labels = flag;
+        self
+    }
+
     pub fn with_x_minor_ticks(mut self, num: usize) -> Self {
         self.ticks.x_num_minor = num;
         self
@@ -182,8 +444,39 @@ impl Axis {
         self
     }
 
+    /// Fill the area of the axis with `color`.
+    pub fn with_background(mut self, color: &str) -> Self {
+        self.style.insert("fill".to_string(), color.to_string());
+        self
+    }
+
+    /// Draw the axis in the style of `theme`. The legend of the theme
+    /// replaces the layout set before, and lines added afterwards without a
+    /// color take the colors of its color cycle.
+    pub fn with_theme(mut self, theme: &Theme) -> Self {
+        self.theme = Theme {
+            legend: theme.legend.clone().with_font_family(&theme.font_family),
+            ..theme.clone()
+        };
+        if self.legend.is_some() {
+            self.legend = Some(self.theme.legend.clone());
+        }
+        self
+    }
+
+    /// Draw a legend of the named plots, with the layout of the theme unless
+    /// set by `with_legend_layout`.
     pub fn with_legend(mut self, flag: bool) -> Self {
-        self.draw_legend = flag;
+        self.legend = if flag {
+            self.legend.or_else(|| Some(self.theme.legend.clone()))
+        } else {
+            None
+        };
+        self
+    }
+
+    pub fn with_legend_layout(mut self, legend: Legend) -> Self {
+        self.legend = Some(legend);
         self
     }
 
@@ -228,22 +521,27 @@ impl Axis {
             (2.5, 5.0)
         };
 
-        self.ticks.xpos = positions(xmin, xmax, mx);
-        self.ticks.ypos = positions(ymin, ymax, my);
+        let positions = |min: f64, max: f64, mult: f64, scale: Scale| {
+            if scale.log {
+                log_positions(min, max)
+            } else {
+                positions(min, max, mult)
+            }
+        };
 
-        // Make room for the ytick labels by resizing and moving the axis.
-        // TODO: This will make the axis shrink with each call to this function.
-        // let shift = {
-        //     let width = self.ticks.y_tick_label_character_width();
-        //     width as f64 * 1e-3
-        // };
-        // self.u += shift;
-        // self.width -= shift;
+        self.ticks.xpos = positions(xmin, xmax, mx, self.xscale);
+        self.ticks.ypos = positions(ymin, ymax, my, self.yscale);
+        if self.y2limits.is_some() {
+            let [_, _, y2min, y2max] = self.limits_ordered_for(true);
+            self.ticks.y2pos = positions(y2min, y2max, my, self.yscale);
+        }
     }
 
+    /// The transformations for elements on the primary or secondary y-axis.
     fn transformations(
         &self,
         fig: &Figure,
+        secondary: bool,
     ) -> (
         impl Fn(f64) -> f64,
         impl Fn(f64) -> f64,
@@ -252,12 +550,15 @@ impl Axis {
     ) {
         let (fw, fh) = (fig.width, fig.height);
         let (au, av, aw, ah) = (self.u, self.v, self.width, self.height);
-        let [xmin, xmax, ymin, ymax] = self.limits;
+        let [xmin, xmax, ymin, ymax] = self.limits_for(secondary);
+        let (xscale, yscale) = (self.xscale, self.yscale);
+        let (xmin, xmax) = xscale.axis_limits(xmin, xmax);
+        let (ymin, ymax) = yscale.axis_limits(ymin, ymax);
 
         let x = move |u| fw as f64 * (au + u * aw);
         let y = move |v| fh as f64 * (av + v * ah);
-        let u = move |x| (x - xmin) / (xmax - xmin);
-        let v = move |y| 1.0 - (y - ymin) / (ymax - ymin);
+        let u = move |x| xscale.orient((xscale.apply(x) - xmin) / (xmax - xmin));
+        let v = move |y| 1.0 - yscale.orient((yscale.apply(y) - ymin) / (ymax - ymin));
 
         (x, y, u, v)
     }
@@ -289,55 +590,64 @@ impl Axis {
                 .unwrap_or(Vec::new()),
         );
 
-        // Add axis labels.
+        children.extend(self.frame_tags(fig));
+
+        // Add axis labels, outside of the tick labels.
+        let (font_size, tick_font_size) =
+            (self.theme.label_pixels(), self.theme.tick_label_pixels());
+        let line = measure("", tick_font_size);
+        let (left, right, bottom) = (
+            w * self.u,
+            w * (self.u + self.width),
+            h * (self.v + self.height),
+        );
         if self.draw_xaxis && !self.xlabel.is_empty() {
-            let xlabel = Text {
-                text: self.xlabel.to_owned(),
-                u: 0.5,
-                v: -0.1,
-                angle: 0.0,
-                style: element_opts(&[("text-anchor", "middle")]),
-            }
-            .to_tags(&self, &fig);
-            children.extend(xlabel);
+            let tick_labels = if self.draw_xtick_labels {
+                self.tick_label_pad() + line.height()
+            } else {
+                0.0
+            };
+            children.push(axis_label(
+                &self.xlabel,
+                w * (self.u + 0.5 * self.width),
+                bottom + tick_labels + AXIS_LABEL_PAD + measure(&self.xlabel, font_size).ascent,
+                0.0,
+                &self.theme,
+            ));
         }
+        // The tops of the rotated y-labels face the axis on the right and
+        // away from the axis on the left.
+        let center = h * (self.v + 0.5 * self.height);
         if self.draw_yaxis && !self.ylabel.is_empty() {
-            let y_tick_label_width = self.ticks.y_tick_label_character_width();
-            let ylabel = Text {
-                text: self.ylabel.to_owned(),
-                u: -0.075 - 0.005 * y_tick_label_width as f64,
-                v: 0.5,
-                angle: 270.0,
-                style: element_opts(&[("text-anchor", "middle")]),
-            }
-            .to_tags(&self, &fig);
-            children.extend(ylabel);
-        }
-
-        if self.draw_legend {
-            let mut legend_elements = Vec::new();
-            for (i, p) in self.plots.iter().filter(|p| !p.name.is_empty()).enumerate() {
-                let label = Text {
-                    text: p.name.to_owned(),
-                    u: 0.95,
-                    v: 0.95 - (i as f64 * 0.05),
-                    angle: 0.0,
-                    style: element_opts(&[
-                        (
-                            "fill",
-                            p.style.get("stroke").unwrap_or(&"black".to_string()),
-                        ),
-                        ("text-anchor", "end"),
-                        ("font-size", "10pt"),
-                    ]),
-                };
-                legend_elements.push(label);
+            let tick_labels = self.ticks.y_tick_label_width(self.yscale, tick_font_size);
+            let descent = measure(&self.ylabel, font_size).descent;
+            let x = left - self.tick_label_pad() - tick_labels - AXIS_LABEL_PAD - descent;
+            children.push(axis_label(&self.ylabel, x, center, 270.0, &self.theme));
+        }
+        if self.draw_yaxis && self.y2limits.is_some() && !self.y2label.is_empty() {
+            let tick_labels = self.ticks.y2_tick_label_width(self.yscale, tick_font_size);
+            let descent = measure(&self.y2label, font_size).descent;
+            let x = right + self.tick_label_pad() + tick_labels + AXIS_LABEL_PAD + descent;
+            children.push(axis_label(&self.y2label, x, center, 90.0, &self.theme));
+        }
+
+        if let Some(legend) = &self.legend {
+            // The data points inside of the axis, which the legend avoids.
+            let mut points = Vec::new();
+            for plot in self.plots.iter() {
+                let (x, y, u, v) = self.transformations(fig, plot.secondary);
+                points.extend(
+                    plot.xs
+                        .iter()
+                        .zip(&plot.ys)
+                        .map(|(xi, yi)| (u(*xi), v(*yi)))
+                        .filter(|(ui, vi)| (0.0..=1.0).contains(ui) && (0.0..=1.0).contains(vi))
+                        .map(|(ui, vi)| [x(ui), y(vi)]),
+                );
             }
-            children.extend(
-                legend_elements
-                    .iter()
-                    .flat_map(|lab| lab.to_tags(self, fig)),
-            );
+            let area = [w * self.u, h * self.v, w * self.width, h * self.height];
+            let [_, right, below, _] = self.label_extents();
+            children.extend(legend.to_tags(&self.legend_entries(), area, [right, below], &points));
         }
         children.extend(self.ticks.to_tags(self, fig));
         let mut ax_rect = Tag::<svg::Rect>::new(
@@ -351,8 +661,132 @@ impl Axis {
         vec![Box::new(ax_rect)]
     }
 
+    /// The plots and other elements listed in the legend, plots first.
+    fn legend_entries(&self) -> Vec<&dyn LegendEntry> {
+        let mut entries: Vec<&dyn LegendEntry> =
+            self.plots.iter().map(|p| p as &dyn LegendEntry).collect();
+        entries.extend(self.elements.iter().filter_map(|el| el.legend_entry()));
+        entries
+    }
+
+    /// Gap between the axis and its tick labels, in pixels, which includes
+    /// the part of the ticks outside of the axis.
+    fn tick_label_pad(&self) -> f64 {
+        TICK_LABEL_PAD + self.theme.tick_outside()
+    }
+
+    /// The lengths of the ticks of the x- and y-axis, in pixels.
+    fn tick_lengths(&self, fig: &Figure) -> (f64, f64) {
+        match self.theme.tick_length {
+            Some(length) => (length, length),
+            None => (
+                0.02 * self.height * fig.height as f64,
+                0.01 * self.width * fig.width as f64,
+            ),
+        }
+    }
+
+    /// The lines along the edges of the axis, as set by the theme.
+    fn frame_tags(&self, fig: &Figure) -> Vec<Box<dyn svg::RenderTag>> {
+        let (w, h) = (fig.width as f64, fig.height as f64);
+        let [left, top] = [w * self.u, h * self.v];
+        let [right, bottom] = [left + w * self.width, top + h * self.height];
+        let edges = match self.theme.frame {
+            Frame::None => Vec::new(),
+            Frame::Open => {
+                let mut edges = Vec::new();
+                if self.draw_xaxis {
+                    edges.push([left, right, bottom, bottom]);
+                }
+                if self.draw_yaxis {
+                    edges.push([left, left, top, bottom]);
+                    if self.y2limits.is_some() {
+                        edges.push([right, right, top, bottom]);
+                    }
+                }
+                edges
+            }
+            Frame::Box => vec![
+                [left, right, bottom, bottom],
+                [left, left, top, bottom],
+                [right, right, top, bottom],
+                [left, right, top, top],
+            ],
+        };
+        let linewidth = format!("{}", self.ticks.linewidth);
+        let style = [
+            ("stroke", &self.ticks.color[..]),
+            ("stroke-width", &linewidth[..]),
+            ("stroke-linecap", "square"),
+        ];
+        edges
+            .into_iter()
+            .map(|[x1, x2, y1, y2]| {
+                Box::new(Tag::<svg::Line>::new(x1, x2, y1, y2, opts(&style)))
+                    as Box<dyn svg::RenderTag>
+            })
+            .collect()
+    }
+
     fn limits_ordered(&self) -> [f64; 4] {
-        let [mut xmin, mut xmax, mut ymin, mut ymax] = self.limits;
+        self.limits_ordered_for(false)
+    }
+
+    /// The space `[left, right, bottom, top]` around the axis taken by its
+    /// ticks and labels, in pixels.
+    fn label_extents(&self) -> [f64; 4] {
+        let (font_size, tick_font_size) =
+            (self.theme.label_pixels(), self.theme.tick_label_pixels());
+        let line = measure("", tick_font_size);
+        let pad = self.tick_label_pad();
+        let [mut left, mut right, mut bottom, mut top] = [0.0; 4];
+        if self.draw_xaxis {
+            let (labels, _) = self.ticks.format_ticks(self.xscale, self.yscale);
+            if self.draw_xtick_labels && !labels.is_empty() {
+                bottom += pad + line.height();
+                // The labels of ticks at the ends of the axis stick out by
+                // half of their width.
+                let half_width = 0.5 * max_width(&labels, tick_font_size);
+                (left, right) = (half_width, half_width);
+            }
+            if !self.xlabel.is_empty() {
+                bottom += AXIS_LABEL_PAD + measure(&self.xlabel, font_size).height();
+            }
+        }
+        if self.draw_yaxis {
+            let label = |text: &str| {
+                if text.is_empty() {
+                    0.0
+                } else {
+                    AXIS_LABEL_PAD + measure(text, font_size).height()
+                }
+            };
+            // The label of a tick at the top is centered on it.
+            top = 0.5 * line.ascent;
+            left = left.max(
+                pad + self.ticks.y_tick_label_width(self.yscale, tick_font_size)
+                    + label(&self.ylabel),
+            );
+            if self.y2limits.is_some() {
+                right = right.max(
+                    pad + self.ticks.y2_tick_label_width(self.yscale, tick_font_size)
+                        + label(&self.y2label),
+                );
+            }
+        }
+        [left, right, bottom, top]
+    }
+
+    /// The limits of the x-axis and the primary or secondary y-axis.
+    fn limits_for(&self, secondary: bool) -> [f64; 4] {
+        match (secondary, self.y2limits) {
+            (true, Some([ymin, ymax])) => [self.limits[0], self.limits[1], ymin, ymax],
+            _ => self.limits,
+        }
+    }
+
+    fn limits_ordered_for(&self, secondary: bool) -> [f64; 4] {
+        let [mut xmin, mut xmax, mut ymin, mut ymax] = self.limits_for(secondary);
         // Bring limits in correct order.
         let (xmin, xmax) = (xmin.min(xmax), xmin.max(xmax));
         let (ymin, ymax) = (ymin.min(ymax), ymin.max(ymax));
@@ -368,15 +802,23 @@ impl Axis {
     /// Additionally, the function splits the data into segments whenever
     /// NaN values are encountered, because NaNs in the SVG output lead
     /// to the polyline not being drawn fully.
+    ///
+    /// On logarithmic axes, the crossings are calculated on the logarithm of
+    /// the data, where the data is drawn with straight lines.
     fn segment_lineplot_data(&self, line: &LinePlot) -> Vec<(Vec<f64>, Vec<f64>)> {
-        let (xs, ys) = (&line.xs[..], &line.ys[..]);
+        let (xscale, yscale) = (self.xscale, self.yscale);
+        let xs: Vec<f64> = line.xs.iter().map(|x| xscale.apply(*x)).collect();
+        let ys: Vec<f64> = line.ys.iter().map(|y| yscale.apply(*y)).collect();
+        let (xs, ys) = (&xs[..], &ys[..]);
         let (nx, ny) = (xs.len(), ys.len());
         let n = nx.min(ny);
         if n < 2 {
             return Vec::new();
         }
 
-        let [xmin, xmax, ymin, ymax] = self.limits_ordered();
+        let [xmin, xmax, ymin, ymax] = self.limits_ordered_for(line.secondary);
+        let (xmin, xmax) = xscale.axis_limits(xmin, xmax);
+        let (ymin, ymax) = yscale.axis_limits(ymin, ymax);
 
         // Short circuit if everything fits into axis.
         if xs.iter().all(|x| xmin <= *x && *x <= xmax)
@@ -536,7 +978,18 @@ impl Axis {
                 });
             }
         }
+        if !(xscale.log || yscale.log) {
+            return segments;
+        }
         segments
+            .into_iter()
+            .map(|(xp, yp)| {
+                (
+                    xp.into_iter().map(|x| xscale.invert(x)).collect(),
+                    yp.into_iter().map(|y| yscale.invert(y)).collect(),
+                )
+            })
+            .collect()
     }
 }
 
@@ -553,6 +1006,55 @@ impl From<[f64; 4]> for Axis {
     }
 }
 
+/// The scale of an axis: linear or logarithmic (base 10), and optionally
+/// reversed, i.e. decreasing to the right or to the top.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Scale {
+    pub log: bool,
+    pub reversed: bool,
+}
+
+impl Scale {
+    /// Map a data value onto the axis, on which it is placed linearly.
+    fn apply(&self, z: f64) -> f64 {
+        if self.log {
+            z.log10()
+        } else {
+            z
+        }
+    }
+
+    fn invert(&self, z: f64) -> f64 {
+        if self.log {
+            10f64.powf(z)
+        } else {
+            z
+        }
+    }
+
+    /// Flip the normalized position `t` on a reversed axis.
+    fn orient(&self, t: f64) -> f64 {
+        if self.reversed {
+            1.0 - t
+        } else {
+            t
+        }
+    }
+
+    /// The limits mapped onto the axis. Non-positive limits of logarithmic
+    /// axes are replaced, so that the axis spans three decades.
+    fn axis_limits(&self, min: f64, max: f64) -> (f64, f64) {
+        if !self.log {
+            return (min, max);
+        }
+        let (min, max) = (
+            if min > 0.0 { min } else { max / 1e3 },
+            if max > 0.0 { max } else { min / 1e3 },
+        );
+        (self.apply(min), self.apply(max))
+    }
+}
+
 // ----------------------------------------------------------------------------
 //
 //
@@ -567,22 +1069,78 @@ pub struct Text {
     u: f64,
     v: f64,
     angle: f64,
+    /// `u` and `v` are data coordinates instead of axis coordinates.
+    data: bool,
     style: svg::Params,
 }
 
+impl Text {
+    /// Create a text at the axis coordinates `u`, `v`, which range from 0 to
+    /// 1 within the axis (from left to right and bottom to top).
+    pub fn new(text: &str, u: f64, v: f64) -> Self {
+        Text {
+            text: text.to_owned(),
+            u,
+            v,
+            angle: 0.0,
+            data: false,
+            style: svg::Params::new(),
+        }
+    }
+
+    /// Create a text at the data coordinates `x`, `y`, e.g. an annotation of
+    /// a line. It is not drawn outside of the limits of the axis.
+    pub fn at(text: &str, x: f64, y: f64) -> Self {
+        Text {
+            data: true,
+            ..Text::new(text, x, y)
+        }
+    }
+
+    pub fn with_angle(mut self, angle: f64) -> Self {
+        self.angle = angle;
+        self
+    }
+
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.style.insert("fill".to_string(), color.to_string());
+        self
+    }
+
+    /// Set the SVG `text-anchor`, i.e. `start`, `middle` or `end`.
+    pub fn with_anchor(mut self, anchor: &str) -> Self {
+        self.style
+            .insert("text-anchor".to_string(), anchor.to_string());
+        self
+    }
+
+    pub fn with_font_size(mut self, size: &str) -> Self {
+        self.style.insert("font-size".to_string(), size.to_string());
+        self
+    }
+}
+
 impl Element for Text {
     fn to_tags(&self, ax: &Axis, fig: &Figure) -> Vec<Box<dyn svg::RenderTag>> {
-        let (x, y, _, _) = ax.transformations(fig);
+        let (x, y, u, v) = ax.transformations(fig, false);
 
-        let (x, y) = (x(self.u), y(1.0 - self.v));
+        let (x, y) = if self.data {
+            let (u, v) = (u(self.u), v(self.v));
+            if !((0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v)) {
+                return Vec::new();
+            }
+            (x(u), y(v))
+        } else {
+            (x(self.u), y(1.0 - self.v))
+        };
         let mut style = Vec::new();
         style.extend(self.style.iter().map(|(k, v)| (&k[..], &v[..])));
 
-        // Set default font to sans
+        // Set default font to the one of the theme.
         let mut params = svg::opts(&style);
         params.as_mut().map(|mut ps| {
             if !ps.contains_key("font-family") {
-                ps.insert("font-family".to_string(), "sans".to_string());
+                ps.insert("font-family".to_string(), ax.theme.font_family.clone());
             };
         });
 
@@ -612,6 +1170,8 @@ impl Element for Text {
 pub struct Ticks {
     pub xpos: Vec<f64>,
     pub ypos: Vec<f64>,
+    /// Positions on the secondary y-axis, only drawn if the axis has one.
+    pub y2pos: Vec<f64>,
     pub x_num_minor: usize,
     pub y_num_minor: usize,
     color: String,
@@ -624,6 +1184,7 @@ impl Default for Ticks {
         Self {
             xpos: Vec::new(),
             ypos: Vec::new(),
+            y2pos: Vec::new(),
             x_num_minor: 3,
             y_num_minor: 3,
             color: "black".to_string(),
@@ -635,8 +1196,9 @@ impl Default for Ticks {
 
 impl Element for Ticks {
     fn to_tags(&self, ax: &Axis, fig: &Figure) -> Vec<Box<dyn svg::RenderTag>> {
-        let (x, y, u, v) = ax.transformations(fig);
+        let (x, y, u, v) = ax.transformations(fig, false);
         let [xmin, xmax, ymin, ymax] = ax.limits_ordered();
+        let (xscale, yscale) = (ax.xscale, ax.yscale);
 
         let width_param = format!("{}", self.linewidth);
         let style = [
@@ -644,40 +1206,57 @@ impl Element for Ticks {
             ("stroke-width", &width_param[..]),
         ];
         let style_minor = [("stroke", "lightgray"), ("stroke-width", &width_param[..])];
-        let style_xtick_label = [("text-anchor", "middle"), ("font-family", "sans")];
-        let style_ytick_label = [("text-anchor", "end"), ("font-family", "sans")];
+        // Tick labels are placed by the extent of a line of text.
+        let theme = &ax.theme;
+        let line = measure("", theme.tick_label_pixels());
+        let pad = ax.tick_label_pad();
+        let font_size = format!("{}pt", theme.tick_label_size);
+        let label_style = |anchor| {
+            opts(&[
+                ("text-anchor", anchor),
+                ("font-family", &theme.font_family),
+                ("font-size", &font_size),
+            ])
+        };
+
+        // The ends of the ticks, in pixels. The x-ticks run downwards from
+        // `bottom`, the y-ticks from `left` to the right and the secondary
+        // y-ticks from `right`, with the part outside of the axis.
+        let (inside, outside) = theme.tick_direction.split();
+        let (xlength, ylength) = ax.tick_lengths(fig);
+        let (bottom, top) = (y(1.0) - inside * xlength, y(1.0) + outside * xlength);
+        let (left, right) = (x(0.0) - outside * ylength, x(0.0) + inside * ylength);
+        let (left2, right2) = (x(1.0) - inside * ylength, x(1.0) + outside * ylength);
 
         let mut xticks: Vec<Box<dyn svg::RenderTag>> = Vec::new();
         let mut yticks: Vec<Box<dyn svg::RenderTag>> = Vec::new();
 
-        let (xtick_labels, ytick_labels) = self.format_ticks();
+        let (xtick_labels, ytick_labels) = self.format_ticks(xscale, yscale);
 
         if ax.draw_xaxis {
             let mut iter = self.xpos.iter().zip(xtick_labels).peekable();
             while let Some((&xi, li)) = iter.next() {
-                let lt = Tag::<svg::Line>::new(x(u(xi)), x(u(xi)), y(0.99), y(1.01), opts(&style));
+                let lt = Tag::<svg::Line>::new(x(u(xi)), x(u(xi)), bottom, top, opts(&style));
                 let tt = Tag::<svg::Text>::new(
                     x(u(xi)),
-                    y(1.05),
+                    y(1.0) + pad + line.ascent,
                     0.0,
                     &li,
-                    opts(&style_xtick_label.clone()),
+                    label_style("middle"),
                 );
                 xticks.push(Box::new(lt));
-                xticks.push(Box::new(tt));
+                if ax.draw_xtick_labels {
+                    xticks.push(Box::new(tt));
+                }
 
                 // Draw minor ticks.
                 if let Some((&xj, _)) = iter.peek() {
-                    if self.x_num_minor == 0 {
-                        continue;
-                    }
-                    let step = (xj - xi) / (self.x_num_minor as f64 + 1.0);
-                    for i in 1..=self.x_num_minor {
+                    for xk in minor_positions(xi, xj, self.x_num_minor, xscale) {
                         let lt = Tag::<svg::Line>::new(
-                            x(u(xi + i as f64 * step)),
-                            x(u(xi + i as f64 * step)),
-                            y(0.99),
-                            y(1.01),
+                            x(u(xk)),
+                            x(u(xk)),
+                            bottom,
+                            top,
                             opts(&style_minor),
                         );
                         xticks.push(Box::new(lt));
@@ -689,30 +1268,57 @@ impl Element for Ticks {
         if ax.draw_yaxis {
             let mut iter = self.ypos.iter().zip(ytick_labels).peekable();
             while let Some((&yi, li)) = iter.next() {
-                let lt =
-                    Tag::<svg::Line>::new(x(-0.005), x(0.005), y(v(yi)), y(v(yi)), opts(&style));
+                let lt = Tag::<svg::Line>::new(left, right, y(v(yi)), y(v(yi)), opts(&style));
                 let tt = Tag::<svg::Text>::new(
-                    x(-0.03),
-                    y(v(yi) + 0.02),
+                    x(0.0) - pad,
+                    y(v(yi)) + 0.5 * line.ascent,
                     0.0,
                     &li,
-                    opts(&style_ytick_label.clone()),
+                    label_style("end"),
                 );
                 yticks.push(Box::new(lt));
                 yticks.push(Box::new(tt));
 
                 // Draw minor ticks.
                 if let Some((&yj, _)) = iter.peek() {
-                    if self.y_num_minor == 0 {
-                        continue;
+                    for yk in minor_positions(yi, yj, self.y_num_minor, yscale) {
+                        let lt = Tag::<svg::Line>::new(
+                            left,
+                            right,
+                            y(v(yk)),
+                            y(v(yk)),
+                            opts(&style_minor),
+                        );
+                        yticks.push(Box::new(lt));
                     }
-                    let step = (yj - yi) / (self.y_num_minor as f64 + 1.0);
-                    for i in 1..=self.y_num_minor {
+                }
+            }
+        }
+
+        if let (true, Some(_)) = (ax.draw_yaxis, ax.y2limits) {
+            let (_, _, _, v2) = ax.transformations(fig, true);
+            let y2tick_labels = format_scale_ticks(&self.y2pos, yscale);
+            let mut iter = self.y2pos.iter().zip(y2tick_labels).peekable();
+            while let Some((&yi, li)) = iter.next() {
+                let lt = Tag::<svg::Line>::new(left2, right2, y(v2(yi)), y(v2(yi)), opts(&style));
+                let tt = Tag::<svg::Text>::new(
+                    x(1.0) + pad,
+                    y(v2(yi)) + 0.5 * line.ascent,
+                    0.0,
+                    &li,
+                    label_style("start"),
+                );
+                yticks.push(Box::new(lt));
+                yticks.push(Box::new(tt));
+
+                // Draw minor ticks.
+                if let Some((&yj, _)) = iter.peek() {
+                    for yk in minor_positions(yi, yj, self.y_num_minor, yscale) {
                         let lt = Tag::<svg::Line>::new(
-                            x(-0.005),
-                            x(0.005),
-                            y(v(yi + i as f64 * step)),
-                            y(v(yi + i as f64 * step)),
+                            left2,
+                            right2,
+                            y(v2(yk)),
+                            y(v2(yk)),
                             opts(&style_minor),
                         );
                         yticks.push(Box::new(lt));
@@ -735,16 +1341,23 @@ impl Element for Ticks {
 }
 
 impl Ticks {
-    fn format_ticks(&self) -> (Vec<String>, Vec<String>) {
-        let xtick_labels = format_ticks(&self.xpos);
-        let ytick_labels = format_ticks(&self.ypos);
+    fn format_ticks(&self, xscale: Scale, yscale: Scale) -> (Vec<String>, Vec<String>) {
+        let xtick_labels = format_scale_ticks(&self.xpos, xscale);
+        let ytick_labels = format_scale_ticks(&self.ypos, yscale);
 
         (xtick_labels, ytick_labels)
     }
 
-    fn y_tick_label_character_width(&self) -> usize {
-        let (_, labels) = self.format_ticks();
-        labels.iter().map(|lab| lab.len()).max().unwrap_or(0)
+    /// Width of the widest label of the y-ticks set in `font_size`, in
+    /// pixels.
+    fn y_tick_label_width(&self, yscale: Scale, font_size: f64) -> f64 {
+        max_width(&format_scale_ticks(&self.ypos, yscale), font_size)
+    }
+
+    /// Width of the widest label of the secondary y-ticks set in
+    /// `font_size`, in pixels.
+    fn y2_tick_label_width(&self, yscale: Scale, font_size: f64) -> f64 {
+        max_width(&format_scale_ticks(&self.y2pos, yscale), font_size)
     }
 }
 
@@ -762,6 +1375,14 @@ pub struct LinePlot {
     ys: Vec<f64>,
     style: svg::Params,
     name: String,
+    /// The group of the line, under which it is listed in the legend.
+    group: String,
+    /// Whether the line is drawn against the secondary y-axis.
+    secondary: bool,
+    line_style: LineStyle,
+    marker: Option<Marker>,
+    /// Size of the markers in pixels, by default three times the linewidth.
+    marker_size: Option<f64>,
 }
 
 impl LinePlot {
@@ -776,6 +1397,11 @@ impl LinePlot {
             ys: ys.to_vec(),
             style,
             name: String::new(),
+            group: String::new(),
+            secondary: false,
+            line_style: LineStyle::Solid,
+            marker: None,
+            marker_size: None,
         }
     }
 
@@ -790,12 +1416,51 @@ impl LinePlot {
         self
     }
 
+    /// Draw the line dashed, with the SVG `stroke-dasharray`, e.g. `"4 3"`.
+    pub fn with_dasharray(mut self, dasharray: &str) -> Self {
+        self.style
+            .insert("stroke-dasharray".to_string(), dasharray.to_string());
+        self
+    }
+
     pub fn with_name(mut self, name: &str) -> Self {
         self.name.drain(..);
         self.name.write_str(name);
         self
     }
 
+    /// List the line under the header `group` in the legend, if the legend
+    /// shows group headers (see `Legend::with_group_headers`).
+    pub fn with_group(mut self, group: &str) -> Self {
+        self.group = group.to_string();
+        self
+    }
+
+    /// Draw the line against the secondary y-axis of the axis it is added
+    /// to (see `Axis::with_y2lim`).
+    pub fn with_secondary_y(mut self, flag: bool) -> Self {
+        self.secondary = flag;
+        self
+    }
+
+    /// Draw the line dashed or dotted, with dashes scaled to the linewidth.
+    /// With `LineStyle::None` only the markers are drawn.
+    pub fn with_line_style(mut self, line_style: LineStyle) -> Self {
+        self.line_style = line_style;
+        self
+    }
+
+    /// Mark each data point inside of the axis with `marker`.
+    pub fn with_marker(mut self, marker: Option<Marker>) -> Self {
+        self.marker = marker;
+        self
+    }
+
+    pub fn with_marker_size(mut self, size: f64) -> Self {
+        self.marker_size = Some(size);
+        self
+    }
+
     pub fn set_color(&mut self, color: &str) {
         self.style.insert("stroke".to_string(), color.to_string());
     }
@@ -809,41 +1474,890 @@ impl LinePlot {
         ax.add_line(self);
         ax
     }
-}
 
-impl Element for LinePlot {
-    fn to_tags(&self, ax: &Axis, fig: &Figure) -> Vec<Box<dyn svg::RenderTag>> {
-        let (x, y, u, v) = ax.transformations(fig);
-        let mut svg_tags: Vec<Box<dyn svg::RenderTag>> = Vec::new();
-        for (xs, ys) in ax.segment_lineplot_data(self).into_iter() {
-            let xs = xs.iter().map(|xi| x(u(*xi)));
-            let ys = ys.iter().map(|yi| y(v(*yi)));
-            svg_tags.push(Box::new(Tag::<svg::Polyline>::new(
-                xs,
-                ys,
-                Some(self.style.clone()),
-            )));
-        }
+    fn linewidth(&self) -> f64 {
+        self.style
+            .get("stroke-width")
+            .and_then(|w| w.parse().ok())
+            .unwrap_or(2.0)
+    }
 
-        svg_tags
+    fn color(&self) -> &str {
+        self.style.get("stroke").map_or("black", |c| c.as_str())
     }
 
-    fn add_svg_property(&mut self, key: &str, value: &str) {
-        self.style.insert(key.to_string(), value.to_string());
+    /// The style of the line, `None` if no line is drawn.
+    fn line_params(&self) -> Option<svg::Params> {
+        let mut style = self.style.clone();
+        if let Some(dasharray) = self.line_style.dasharray(self.linewidth()) {
+            style.insert("stroke-dasharray".to_string(), dasharray);
+        }
+        (self.line_style != LineStyle::None).then_some(style)
     }
 
-    fn identifier(&self) -> &str {
-        "LinePlot"
+    /// The markers at the pixel coordinates `points`.
+    fn marker_tags(
+        &self,
+        points: impl IntoIterator<Item = (f64, f64)>,
+    ) -> Vec<Box<dyn svg::RenderTag>> {
+        let Some(marker) = self.marker else {
+            return Vec::new();
+        };
+        let size = self.marker_size.unwrap_or_else(|| 3.0 * self.linewidth());
+        let style = element_opts(&[("fill", self.color()), ("stroke", "none")]);
+        points
+            .into_iter()
+            .map(|(x, y)| marker.to_tag(x, y, size, style.clone()))
+            .collect()
     }
 }
 
+impl LegendEntry for LinePlot {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn group(&self) -> &str {
+        &self.group
+    }
+
+    fn legend_glyph(&self, x0: f64, x1: f64, y: f64) -> Vec<Box<dyn svg::RenderTag>> {
+        let mut tags: Vec<Box<dyn svg::RenderTag>> = Vec::new();
+        if let Some(style) = self.line_params() {
+            tags.push(Box::new(Tag::<svg::Polyline>::new(
+                [x0, x1],
+                [y, y],
+                Some(style),
+            )));
+        }
+        tags.extend(self.marker_tags([(0.5 * (x0 + x1), y)]));
+        tags
+    }
+}
+
+impl Element for LinePlot {
+    fn to_tags(&self, ax: &Axis, fig: &Figure) -> Vec<Box<dyn svg::RenderTag>> {
+        let (x, y, u, v) = ax.transformations(fig, self.secondary);
+        let mut svg_tags: Vec<Box<dyn svg::RenderTag>> = Vec::new();
+        if let Some(style) = self.line_params() {
+            for (xs, ys) in ax.segment_lineplot_data(self).into_iter() {
+                let xs = xs.iter().map(|xi| x(u(*xi)));
+                let ys = ys.iter().map(|yi| y(v(*yi)));
+                svg_tags.push(Box::new(Tag::<svg::Polyline>::new(
+                    xs,
+                    ys,
+                    Some(style.clone()),
+                )));
+            }
+        }
+
+        // Markers are only drawn for the points inside of the axis.
+        let inside = self
+            .xs
+            .iter()
+            .zip(&self.ys)
+            .map(|(xi, yi)| (u(*xi), v(*yi)))
+            .filter(|(ui, vi)| (0.0..=1.0).contains(ui) && (0.0..=1.0).contains(vi))
+            .map(|(ui, vi)| (x(ui), y(vi)));
+        svg_tags.extend(self.marker_tags(inside));
+
+        svg_tags
+    }
+
+    fn add_svg_property(&mut self, key: &str, value: &str) {
+        self.style.insert(key.to_string(), value.to_string());
+    }
+
+    fn identifier(&self) -> &str {
+        "LinePlot"
+    }
+}
+
+// ----------------------------------------------------------------------------
+//
+//
+// Line styles and markers
+//
+//
+// ----------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+    /// No line, e.g. for plots of markers only.
+    None,
+}
+
+impl LineStyle {
+    /// The SVG `stroke-dasharray` for lines of width `linewidth`.
+    fn dasharray(&self, linewidth: f64) -> Option<String> {
+        match self {
+            LineStyle::Dashed => Some(format!("{} {}", 4.0 * linewidth, 2.5 * linewidth)),
+            LineStyle::Dotted => Some(format!("{linewidth} {}", 2.0 * linewidth)),
+            LineStyle::Solid | LineStyle::None => None,
+        }
+    }
+}
+
+/// Shape drawn at the data points of a plot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Marker {
+    Circle,
+    Square,
+    /// An equilateral triangle pointing upwards.
+    Triangle,
+}
+
+impl Marker {
+    /// The marker of width `size` centered at `x`, `y` (in pixels).
+    fn to_tag(self, x: f64, y: f64, size: f64, style: svg::Params) -> Box<dyn svg::RenderTag> {
+        let r = 0.5 * size;
+        match self {
+            Marker::Circle => Box::new(Tag::<svg::Circle>::new(x, y, r, Some(style))),
+            Marker::Square => {
+                Box::new(Tag::<svg::Rect>::new(x - r, y - r, size, size, Some(style)))
+            }
+            Marker::Triangle => {
+                // Corners at the circumradius around the centroid.
+                let circumradius = size / 3f64.sqrt();
+                Box::new(Tag::<svg::Polygon>::new(
+                    [x, x + r, x - r],
+                    [
+                        y - circumradius,
+                        y + 0.5 * circumradius,
+                        y + 0.5 * circumradius,
+                    ],
+                    Some(style),
+                ))
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+//
+//
+// ScatterPlot
+//
+//
+// ----------------------------------------------------------------------------
+
+/// Markers at the points `xs`, `ys` without a connecting line, e.g. for peak
+/// positions. Circles are used by default.
+#[derive(Clone)]
+pub struct ScatterPlot {
+    line: LinePlot,
+}
+
+impl ScatterPlot {
+    pub fn new(xs: &[f64], ys: &[f64]) -> Self {
+        Self {
+            line: LinePlot::new(xs, ys)
+                .with_line_style(LineStyle::None)
+                .with_marker(Some(Marker::Circle))
+                .with_marker_size(6.0),
+        }
+    }
+
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.line.set_color(color);
+        self
+    }
+
+    pub fn with_marker(mut self, marker: Marker) -> Self {
+        self.line.marker = Some(marker);
+        self
+    }
+
+    pub fn with_marker_size(mut self, size: f64) -> Self {
+        self.line.marker_size = Some(size);
+        self
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.line = self.line.with_name(name);
+        self
+    }
+
+    pub fn with_group(mut self, group: &str) -> Self {
+        self.line.group = group.to_string();
+        self
+    }
+
+    pub fn with_secondary_y(mut self, flag: bool) -> Self {
+        self.line.secondary = flag;
+        self
+    }
+}
+
+impl Element for ScatterPlot {
+    fn to_tags(&self, ax: &Axis, fig: &Figure) -> Vec<Box<dyn svg::RenderTag>> {
+        self.line.to_tags(ax, fig)
+    }
+
+    fn add_svg_property(&mut self, key: &str, value: &str) {
+        self.line.add_svg_property(key, value);
+    }
+
+    fn identifier(&self) -> &str {
+        "ScatterPlot"
+    }
+}
+
+// ----------------------------------------------------------------------------
+//
+//
+// Polygon
+//
+//
+// ----------------------------------------------------------------------------
+
+/// Filled area enclosed by the points `xs`, `ys` (in data coordinates), e.g.
+/// the area of an integral.
+#[derive(Clone)]
+pub struct Polygon {
+    xs: Vec<f64>,
+    ys: Vec<f64>,
+    style: svg::Params,
+    /// Whether the area is drawn against the secondary y-axis.
+    secondary: bool,
+}
+
+impl Polygon {
+    pub fn new(xs: &[f64], ys: &[f64]) -> Self {
+        let mut style = svg::Params::new();
+        style.insert("fill".to_string(), "gray".to_string());
+        style.insert("fill-opacity".to_string(), "0.3".to_string());
+        style.insert("stroke".to_string(), "none".to_string());
+        Self {
+            xs: xs.to_vec(),
+            ys: ys.to_vec(),
+            style,
+            secondary: false,
+        }
+    }
+
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.style.insert("fill".to_string(), color.to_string());
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: f64) -> Self {
+        self.style
+            .insert("fill-opacity".to_string(), format!("{opacity}"));
+        self
+    }
+
+    /// Draw the area against the secondary y-axis of the axis it is added
+    /// to (see `Axis::with_y2lim`).
+    pub fn with_secondary_y(mut self, flag: bool) -> Self {
+        self.secondary = flag;
+        self
+    }
+}
+
+/// Clip a polygon in axis coordinates to the axis, i.e. to the unit square,
+/// by the Sutherland-Hodgman algorithm. Edges leaving the axis are cut where
+/// they cross its edges, so the area inside of the axis is kept.
+fn clip_to_axis(mut points: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    // Each edge of the axis, given by the coordinate it bounds (0 for u, 1
+    // for v), its value and whether the inside is above the value.
+    for (coordinate, bound, above) in [
+        (0, 0.0, true),
+        (0, 1.0, false),
+        (1, 0.0, true),
+        (1, 1.0, false),
+    ] {
+        let get = |p: &(f64, f64)| if coordinate == 0 { p.0 } else { p.1 };
+        let inside = |p: &(f64, f64)| {
+            if above {
+                get(p) >= bound
+            } else {
+                get(p) <= bound
+            }
+        };
+        let crossing = |a: &(f64, f64), b: &(f64, f64)| {
+            let t = (bound - get(a)) / (get(b) - get(a));
+            (a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1))
+        };
+        let Some(mut previous) = points.last().copied() else {
+            return points;
+        };
+        let mut clipped = Vec::with_capacity(points.len() + 2);
+        for point in points {
+            match (inside(&previous), inside(&point)) {
+                (true, true) => clipped.push(point),
+                (true, false) => clipped.push(crossing(&previous, &point)),
+                (false, true) => {
+                    clipped.push(crossing(&previous, &point));
+                    clipped.push(point);
+                }
+                (false, false) => (),
+            }
+            previous = point;
+        }
+        points = clipped;
+    }
+    points
+}
+
+impl Element for Polygon {
+    fn to_tags(&self, ax: &Axis, fig: &Figure) -> Vec<Box<dyn svg::RenderTag>> {
+        let (x, y, u, v) = ax.transformations(fig, self.secondary);
+        let points: Vec<(f64, f64)> = self
+            .xs
+            .iter()
+            .zip(&self.ys)
+            .map(|(xi, yi)| (u(*xi), v(*yi)))
+            .filter(|(ui, vi)| ui.is_finite() && vi.is_finite())
+            .collect();
+        let (xs, ys): (Vec<f64>, Vec<f64>) = clip_to_axis(points)
+            .into_iter()
+            .map(|(ui, vi)| (x(ui), y(vi)))
+            .unzip();
+        if xs.len() < 3 {
+            return Vec::new();
+        }
+        vec![Box::new(Tag::<svg::Polygon>::new(
+            xs,
+            ys,
+            Some(self.style.clone()),
+        ))]
+    }
+
+    fn add_svg_property(&mut self, key: &str, value: &str) {
+        self.style.insert(key.to_string(), value.to_string());
+    }
+
+    fn identifier(&self) -> &str {
+        "Polygon"
+    }
+}
+
+// ----------------------------------------------------------------------------
+//
+//
+// Fills, spans and reference lines
+//
+//
 // ----------------------------------------------------------------------------
-//
-//
-// Helpers
-//
-//
-// ----------------------------------------------------------------------------
+
+/// Direction of a span or reference line, which covers the axis from edge
+/// to edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Orientation {
+    /// At x-values, from the bottom to the top of the axis.
+    Vertical,
+    /// At y-values, from the left to the right of the axis.
+    Horizontal,
+}
+
+/// The sample of a filled area in the legend, a rectangle from `x0` to `x1`
+/// centered at `y` (in pixels).
+fn fill_glyph(x0: f64, x1: f64, y: f64, style: &svg::Params) -> Vec<Box<dyn svg::RenderTag>> {
+    let height = 0.35 * (x1 - x0);
+    vec![Box::new(Tag::<svg::Rect>::new(
+        x0,
+        y - 0.5 * height,
+        x1 - x0,
+        height,
+        Some(style.clone()),
+    ))]
+}
+
+/// The area between the curves `ys1` and `ys2` over `xs` (in data
+/// coordinates), e.g. a band of ±σ around a mean or the area between a
+/// curve and its baseline.
+#[derive(Clone)]
+pub struct FillBetween {
+    area: Polygon,
+    name: String,
+    group: String,
+}
+
+impl FillBetween {
+    pub fn new(xs: &[f64], ys1: &[f64], ys2: &[f64]) -> Self {
+        let n = xs.len().min(ys1.len()).min(ys2.len());
+        let outline_xs: Vec<f64> = xs[..n]
+            .iter()
+            .chain(xs[..n].iter().rev())
+            .copied()
+            .collect();
+        let outline_ys: Vec<f64> = ys1[..n]
+            .iter()
+            .chain(ys2[..n].iter().rev())
+            .copied()
+            .collect();
+        Self {
+            area: Polygon::new(&outline_xs, &outline_ys),
+            name: String::new(),
+            group: String::new(),
+        }
+    }
+
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.area = self.area.with_color(color);
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: f64) -> Self {
+        self.area = self.area.with_opacity(opacity);
+        self
+    }
+
+    /// Outline the area with a thin line of `color`.
+    pub fn with_edge_color(mut self, color: &str) -> Self {
+        self.area.add_svg_property("stroke", color);
+        self.area.add_svg_property("stroke-width", "1");
+        self
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// List the area under the header `group` in the legend, see
+    /// `LinePlot::with_group`.
+    pub fn with_group(mut self, group: &str) -> Self {
+        self.group = group.to_string();
+        self
+    }
+
+    /// Draw the area against the secondary y-axis of the axis it is added
+    /// to (see `Axis::with_y2lim`).
+    pub fn with_secondary_y(mut self, flag: bool) -> Self {
+        self.area = self.area.with_secondary_y(flag);
+        self
+    }
+}
+
+impl Element for FillBetween {
+    fn to_tags(&self, ax: &Axis, fig: &Figure) -> Vec<Box<dyn svg::RenderTag>> {
+        self.area.to_tags(ax, fig)
+    }
+
+    fn add_svg_property(&mut self, key: &str, value: &str) {
+        self.area.add_svg_property(key, value);
+    }
+
+    fn identifier(&self) -> &str {
+        "FillBetween"
+    }
+
+    fn legend_entry(&self) -> Option<&dyn LegendEntry> {
+        Some(self)
+    }
+}
+
+impl LegendEntry for FillBetween {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn group(&self) -> &str {
+        &self.group
+    }
+
+    fn legend_glyph(&self, x0: f64, x1: f64, y: f64) -> Vec<Box<dyn svg::RenderTag>> {
+        fill_glyph(x0, x1, y, &self.area.style)
+    }
+}
+
+/// A range of x-values (or y-values) highlighted over the whole height (or
+/// width) of the axis, e.g. an integration window.
+#[derive(Clone)]
+pub struct Span {
+    orientation: Orientation,
+    range: [f64; 2],
+    style: svg::Params,
+    /// Text at the top (or right) end of the span.
+    label: String,
+    name: String,
+    group: String,
+    /// Whether the y-values are on the secondary y-axis.
+    secondary: bool,
+}
+
+impl Span {
+    /// Highlight the x-values from `xmin` to `xmax`.
+    pub fn vertical(xmin: f64, xmax: f64) -> Self {
+        Self::new(Orientation::Vertical, [xmin, xmax])
+    }
+
+    /// Highlight the y-values from `ymin` to `ymax`.
+    pub fn horizontal(ymin: f64, ymax: f64) -> Self {
+        Self::new(Orientation::Horizontal, [ymin, ymax])
+    }
+
+    fn new(orientation: Orientation, range: [f64; 2]) -> Self {
+        Self {
+            orientation,
+            range,
+            style: element_opts(&[
+                ("fill", "gray"),
+                ("fill-opacity", "0.15"),
+                ("stroke", "none"),
+            ]),
+            label: String::new(),
+            name: String::new(),
+            group: String::new(),
+            secondary: false,
+        }
+    }
+
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.style.insert("fill".to_string(), color.to_string());
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: f64) -> Self {
+        self.style
+            .insert("fill-opacity".to_string(), format!("{opacity}"));
+        self
+    }
+
+    /// Write `label` inside of the span, at its top (or right) end.
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = label.to_string();
+        self
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// List the span under the header `group` in the legend, see
+    /// `LinePlot::with_group`.
+    pub fn with_group(mut self, group: &str) -> Self {
+        self.group = group.to_string();
+        self
+    }
+
+    /// Place a horizontal span on the secondary y-axis of the axis it is
+    /// added to (see `Axis::with_y2lim`).
+    pub fn with_secondary_y(mut self, flag: bool) -> Self {
+        self.secondary = flag;
+        self
+    }
+}
+
+impl Element for Span {
+    fn to_tags(&self, ax: &Axis, fig: &Figure) -> Vec<Box<dyn svg::RenderTag>> {
+        let (x, y, u, v) = ax.transformations(fig, self.secondary);
+        let [a, b] = match self.orientation {
+            Orientation::Vertical => self.range.map(u),
+            Orientation::Horizontal => self.range.map(v),
+        };
+        if a.is_nan() || b.is_nan() {
+            return Vec::new();
+        }
+        let (a, b) = (a.min(b).max(0.0), a.max(b).min(1.0));
+        if a >= b {
+            return Vec::new();
+        }
+        let ([x0, x1], [y0, y1]) = match self.orientation {
+            Orientation::Vertical => ([x(a), x(b)], [y(0.0), y(1.0)]),
+            Orientation::Horizontal => ([x(0.0), x(1.0)], [y(a), y(b)]),
+        };
+        let mut tags: Vec<Box<dyn svg::RenderTag>> = vec![Box::new(Tag::<svg::Rect>::new(
+            x0,
+            y0,
+            x1 - x0,
+            y1 - y0,
+            Some(self.style.clone()),
+        ))];
+        if !self.label.is_empty() {
+            // The label hangs from the top of the span.
+            let ascent = measure(&self.label, ax.theme.tick_label_pixels()).ascent;
+            let text_y = y0 + TICK_LABEL_PAD + ascent;
+            let (text_x, anchor) = match self.orientation {
+                Orientation::Vertical => (0.5 * (x0 + x1), "middle"),
+                Orientation::Horizontal => (x1 - TICK_LABEL_PAD, "end"),
+            };
+            tags.push(element_label(
+                &self.label,
+                text_x,
+                text_y,
+                0.0,
+                anchor,
+                "dimgray",
+                &ax.theme,
+            ));
+        }
+        tags
+    }
+
+    fn add_svg_property(&mut self, key: &str, value: &str) {
+        self.style.insert(key.to_string(), value.to_string());
+    }
+
+    fn identifier(&self) -> &str {
+        "Span"
+    }
+
+    fn legend_entry(&self) -> Option<&dyn LegendEntry> {
+        Some(self)
+    }
+}
+
+impl LegendEntry for Span {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn group(&self) -> &str {
+        &self.group
+    }
+
+    fn legend_glyph(&self, x0: f64, x1: f64, y: f64) -> Vec<Box<dyn svg::RenderTag>> {
+        fill_glyph(x0, x1, y, &self.style)
+    }
+}
+
+/// A vertical line at an x-value (or a horizontal line at a y-value) over
+/// the whole axis, e.g. at the position of a known band.
+#[derive(Clone)]
+pub struct RefLine {
+    orientation: Orientation,
+    value: f64,
+    style: svg::Params,
+    line_style: LineStyle,
+    /// Text along the line, at its top (or right) end.
+    label: String,
+    name: String,
+    group: String,
+    /// Whether the y-value is on the secondary y-axis.
+    secondary: bool,
+}
+
+impl RefLine {
+    /// A vertical line at `x`.
+    pub fn vertical(x: f64) -> Self {
+        Self::new(Orientation::Vertical, x)
+    }
+
+    /// A horizontal line at `y`.
+    pub fn horizontal(y: f64) -> Self {
+        Self::new(Orientation::Horizontal, y)
+    }
+
+    fn new(orientation: Orientation, value: f64) -> Self {
+        Self {
+            orientation,
+            value,
+            style: element_opts(&[("stroke", "gray"), ("stroke-width", "1")]),
+            line_style: LineStyle::Dashed,
+            label: String::new(),
+            name: String::new(),
+            group: String::new(),
+            secondary: false,
+        }
+    }
+
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.style.insert("stroke".to_string(), color.to_string());
+        self
+    }
+
+    pub fn with_linewidth(mut self, linewidth: f64) -> Self {
+        self.style
+            .insert("stroke-width".to_string(), format!("{linewidth}"));
+        self
+    }
+
+    /// Draw the line solid, dashed (the default) or dotted.
+    pub fn with_line_style(mut self, line_style: LineStyle) -> Self {
+        self.line_style = line_style;
+        self
+    }
+
+    /// Write `label` along the line, at its top (or right) end.
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = label.to_string();
+        self
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// List the line under the header `group` in the legend, see
+    /// `LinePlot::with_group`.
+    pub fn with_group(mut self, group: &str) -> Self {
+        self.group = group.to_string();
+        self
+    }
+
+    /// Place a horizontal line on the secondary y-axis of the axis it is
+    /// added to (see `Axis::with_y2lim`).
+    pub fn with_secondary_y(mut self, flag: bool) -> Self {
+        self.secondary = flag;
+        self
+    }
+
+    fn color(&self) -> &str {
+        self.style.get("stroke").map_or("gray", |c| c.as_str())
+    }
+
+    fn linewidth(&self) -> f64 {
+        self.style
+            .get("stroke-width")
+            .and_then(|w| w.parse().ok())
+            .unwrap_or(1.0)
+    }
+
+    /// The style of the line, `None` if no line is drawn. A dasharray set as
+    /// a property takes precedence over the line style.
+    fn line_params(&self) -> Option<svg::Params> {
+        let mut style = self.style.clone();
+        if let Some(dasharray) = self.line_style.dasharray(self.linewidth()) {
+            style
+                .entry("stroke-dasharray".to_string())
+                .or_insert(dasharray);
+        }
+        (self.line_style != LineStyle::None).then_some(style)
+    }
+}
+
+impl Element for RefLine {
+    fn to_tags(&self, ax: &Axis, fig: &Figure) -> Vec<Box<dyn svg::RenderTag>> {
+        let Some(style) = self.line_params() else {
+            return Vec::new();
+        };
+        let (x, y, u, v) = ax.transformations(fig, self.secondary);
+        let position = match self.orientation {
+            Orientation::Vertical => u(self.value),
+            Orientation::Horizontal => v(self.value),
+        };
+        if !(0.0..=1.0).contains(&position) {
+            return Vec::new();
+        }
+        let ([x0, x1], [y0, y1]) = match self.orientation {
+            Orientation::Vertical => ([x(position); 2], [y(0.0), y(1.0)]),
+            Orientation::Horizontal => ([x(0.0), x(1.0)], [y(position); 2]),
+        };
+        let mut tags: Vec<Box<dyn svg::RenderTag>> =
+            vec![Box::new(Tag::<svg::Line>::new(x0, x1, y0, y1, Some(style)))];
+        if !self.label.is_empty() {
+            // The label reads along the line and ends at its top (or right)
+            // end, just left of (or above) the line.
+            let gap = 0.5 * TICK_LABEL_PAD;
+            let (text_x, text_y, angle) = match self.orientation {
+                Orientation::Vertical => (x0 - gap, y0 + TICK_LABEL_PAD, 270.0),
+                Orientation::Horizontal => {
+                    let descent = measure(&self.label, ax.theme.tick_label_pixels()).descent;
+                    (x1 - TICK_LABEL_PAD, y0 - gap - descent, 0.0)
+                }
+            };
+            tags.push(element_label(
+                &self.label,
+                text_x,
+                text_y,
+                angle,
+                "end",
+                self.color(),
+                &ax.theme,
+            ));
+        }
+        tags
+    }
+
+    fn add_svg_property(&mut self, key: &str, value: &str) {
+        self.style.insert(key.to_string(), value.to_string());
+    }
+
+    fn identifier(&self) -> &str {
+        "RefLine"
+    }
+
+    fn legend_entry(&self) -> Option<&dyn LegendEntry> {
+        Some(self)
+    }
+}
+
+impl LegendEntry for RefLine {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn group(&self) -> &str {
+        &self.group
+    }
+
+    fn legend_glyph(&self, x0: f64, x1: f64, y: f64) -> Vec<Box<dyn svg::RenderTag>> {
+        match self.line_params() {
+            Some(style) => vec![Box::new(Tag::<svg::Polyline>::new(
+                [x0, x1],
+                [y, y],
+                Some(style),
+            ))],
+            None => Vec::new(),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+//
+//
+// Helpers
+//
+//
+// ----------------------------------------------------------------------------
+
+/// Width of the widest of `labels` set in `font_size` (in pixels), in
+/// pixels.
+fn max_width(labels: &[String], font_size: f64) -> f64 {
+    labels
+        .iter()
+        .map(|label| measure(label, font_size).width)
+        .fold(0.0, f64::max)
+}
+
+/// An axis label centered at `x`, `y` (in pixels), rotated by `angle`.
+fn axis_label(text: &str, x: f64, y: f64, angle: f64, theme: &Theme) -> Box<dyn svg::RenderTag> {
+    Box::new(Tag::<svg::Text>::new(
+        x,
+        y,
+        angle,
+        text,
+        opts(&[
+            ("text-anchor", "middle"),
+            ("font-family", &theme.font_family),
+            ("font-size", &format!("{}pt", theme.label_size)),
+        ]),
+    ))
+}
+
+/// The label of a span or reference line at `x`, `y` (in pixels), rotated by
+/// `angle`, set in the size of the tick labels.
+fn element_label(
+    text: &str,
+    x: f64,
+    y: f64,
+    angle: f64,
+    anchor: &str,
+    color: &str,
+    theme: &Theme,
+) -> Box<dyn svg::RenderTag> {
+    Box::new(Tag::<svg::Text>::new(
+        x,
+        y,
+        angle,
+        text,
+        opts(&[
+            ("fill", color),
+            ("font-family", &theme.font_family),
+            ("font-size", &format!("{}pt", theme.tick_label_size)),
+            ("text-anchor", anchor),
+        ]),
+    ))
+}
 
 /// Generate svg::Params from a slice of pairs.
 pub fn element_opts(items: &[(&str, &str)]) -> svg::Params {
@@ -853,7 +2367,73 @@ pub fn element_opts(items: &[(&str, &str)]) -> svg::Params {
         .collect()
 }
 
+/// Tick positions on a logarithmic axis: the powers of ten within the
+/// limits, and their multiples of 2 and 5 if only a few decades are shown.
+fn log_positions(min: f64, max: f64) -> Vec<f64> {
+    if max <= 0.0 || !max.is_finite() {
+        return Vec::new();
+    }
+    // Non-positive limits cannot be shown, see `Scale::axis_limits`.
+    let min = if min > 0.0 { min } else { max / 1e3 };
+    let (lo, hi) = (min.log10().floor() as i32, max.log10().ceil() as i32);
+    let multiples: &[f64] = if (max / min).log10() <= 2.0 {
+        &[1.0, 2.0, 5.0]
+    } else {
+        &[1.0]
+    };
+    // Label at most about ten decades.
+    let every = ((hi - lo) / 10).max(1);
+    let tolerance = 1e-9;
+    (lo..=hi)
+        .filter(|k| (k - lo) % every == 0)
+        .flat_map(|k| multiples.iter().map(move |m| m * 10f64.powi(k)))
+        .filter(|z| min * (1.0 - tolerance) <= *z && *z <= max * (1.0 + tolerance))
+        .collect()
+}
+
+/// Positions of `num` minor ticks between the major ticks `a` and `b`. On
+/// logarithmic axes, the minor ticks of a decade are placed at its multiples
+/// (regardless of `num`), otherwise they are spaced geometrically.
+fn minor_positions(a: f64, b: f64, num: usize, scale: Scale) -> Vec<f64> {
+    if num == 0 {
+        return Vec::new();
+    }
+    if !scale.log {
+        let step = (b - a) / (num as f64 + 1.0);
+        return (1..=num).map(|i| a + i as f64 * step).collect();
+    }
+    if ((b / a) - 10.0).abs() < 1e-9 {
+        return (2..=9).map(|m| m as f64 * a).collect();
+    }
+    let ratio = (b / a).powf(1.0 / (num as f64 + 1.0));
+    (1..=num).map(|i| a * ratio.powi(i as i32)).collect()
+}
+
+/// Format the tick labels of an axis with the given scale.
+fn format_scale_ticks(tick_positions: &[f64], scale: Scale) -> Vec<String> {
+    if scale.log {
+        tick_positions.iter().map(|z| format_log_tick(*z)).collect()
+    } else {
+        format_ticks(tick_positions)
+    }
+}
+
+/// Format a tick on a logarithmic axis, which is a multiple of a power of
+/// ten: in decimal notation for moderate values, otherwise in scientific
+/// notation.
+pub fn format_log_tick(z: f64) -> String {
+    if (1e-3..1e4).contains(&z) {
+        let decimals = (-z.log10().floor()).max(0.0) as usize;
+        format!("{z:.decimals$}")
+    } else {
+        format!("{z:.0e}")
+    }
+}
+
 fn format_ticks(tick_positions: &[f64]) -> Vec<String> {
+    if tick_positions.is_empty() {
+        return Vec::new();
+    }
     let (magnitude_min, magnitude_max, mag_increment) =
         ticks_magnitude_and_increment(tick_positions);
 
@@ -906,6 +2486,341 @@ fn ticks_magnitude_and_increment(tick_positions: &[f64]) -> (i8, i8, i8) {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_stacked_axes() {
+        let axes = Axis::stacked(3, 0.05);
+        assert_eq!(axes.len(), 3);
+        let default = Axis::default();
+        assert!((axes[0].v - default.v).abs() < 1e-12);
+        let bottom = &axes[2];
+        assert!((bottom.v + bottom.height - default.v - default.height).abs() < 1e-12);
+        assert!((axes[1].v - axes[0].v - axes[0].height - 0.05).abs() < 1e-12);
+        assert_eq!(Axis::stacked(1, 0.05)[0].height, default.height);
+    }
+
+    #[test]
+    fn test_secondary_y_axis() {
+        let mut ax = Axis::default()
+            .with_xlim(0.0, 1.0)
+            .with_ylim(0.0, 1.0)
+            .with_y2lim(0.0, 100.0)
+            .with_y2label("Temperature")
+            .with_right_margin(0.1);
+        assert!(ax.ticks.y2pos.iter().all(|y| (0.0..=100.0).contains(y)));
+        assert!(ax.ticks.y2pos.len() > 1);
+
+        // Only the primary line leaves its axis.
+        let line = LinePlot::new(&[0.0, 1.0], &[0.0, 50.0]);
+        let whole = vec![(vec![0.0, 1.0], vec![0.0, 50.0])];
+        assert_ne!(ax.segment_lineplot_data(&line), whole);
+        let line = line.with_secondary_y(true);
+        assert_eq!(ax.segment_lineplot_data(&line), whole);
+
+        ax.add_line(line);
+        let mut fig = Figure::empty(400, 300);
+        ax.insert_into(&mut fig);
+        assert!(fig.render().contains("Temperature"));
+    }
+
+    #[test]
+    fn test_markup_labels() {
+        let mut ax = Axis::new(0.15, 0.1, 0.75, 0.75)
+            .with_xlim(0.0, 1.0)
+            .with_ylim(0.0, 1.0)
+            .with_xlabel("Wavenumber / cm^{-1}")
+            .with_ylabel("\\Delta A <1>")
+            .with_legend(true);
+        ax.add_line(LinePlot::new(&[0.0, 1.0], &[0.0, 1.0]).with_name("k_{B}T & co"));
+        // The x-label is lowered by the superscript.
+        let [_, _, bottom, _] = ax.label_extents();
+        let line = measure("", DEFAULT_FONT_SIZE);
+        assert!(bottom > TICK_LABEL_PAD + AXIS_LABEL_PAD + 2.0 * line.height());
+
+        let mut fig = Figure::empty(400, 300);
+        ax.insert_into(&mut fig);
+
+        let svg = fig.render();
+        assert!(svg.contains("<tspan>Wavenumber / cm</tspan>"));
+        assert!(svg.contains("-1</tspan>"));
+        assert!(svg.contains("font-size=\"70%\""));
+        assert!(svg.contains("dy=\"-0.5em\""));
+        // Texts are escaped, also without markup.
+        assert!(svg.contains(">\u{394} A &lt;1&gt;</text>"));
+        assert!(svg.contains(" &amp; co</tspan>"));
+
+        let pdf = String::from_utf8(fig.render_pdf()).unwrap();
+        assert!(pdf.contains("/F2 16 Tf (D) Tj /F1 16 Tf ( A <1>) Tj"));
+    }
+
+    #[test]
+    fn test_render_backends() {
+        let xs: Vec<f64> = (0..100).map(|i| i as f64 / 10.0).collect();
+        let ys: Vec<f64> = xs.iter().map(|x| x.sin()).collect();
+        let mut ax = Axis::new(0.15, 0.1, 0.75, 0.75)
+            .with_xlim(0.0, 10.0)
+            .with_ylim(-1.5, 1.5)
+            .with_xlabel("x / s")
+            .with_ylabel("(signal)")
+            .with_legend(true);
+        ax.add_line(
+            LinePlot::new(&xs, &ys)
+                .with_color("#1f77b4")
+                .with_name("sin"),
+        );
+        ax.add_area(Polygon::new(&[1.0, 2.0, 2.0, 1.0], &[0.0, 0.0, 1.0, 1.0]));
+        let mut fig = Figure::empty(400, 300);
+        ax.insert_into(&mut fig);
+
+        let pdf = fig.render_pdf();
+        let pdf = String::from_utf8(pdf).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4"));
+        assert!(pdf.contains("/MediaBox [0 0 300 225]"));
+        assert!(pdf.contains("(\\(signal\\)) Tj"));
+        assert!(pdf.ends_with("%%EOF\n"));
+
+        #[cfg(feature = "png")]
+        {
+            let png = fig.render_png(192.0).unwrap();
+            assert_eq!(&png[1..4], b"PNG");
+            // The size of the image is stored in its header.
+            assert_eq!(&png[16..24], &[0, 0, 3, 32, 0, 0, 2, 88]);
+        }
+    }
+
+    #[test]
+    fn test_inset() {
+        let mut ax = Axis::new(0.1, 0.1, 0.8, 0.8);
+        let inset = ax.inset(0.5, 0.5, 0.25, 0.5);
+        assert!((inset.u - 0.5).abs() < 1e-12);
+        assert!((inset.v - 0.1).abs() < 1e-12);
+        assert!((inset.width - 0.2).abs() < 1e-12);
+        assert!((inset.height - 0.4).abs() < 1e-12);
+
+        ax.add_marker_box([0.2, 0.4, 0.2, 0.4]);
+        let mut fig = Figure::empty(100, 100);
+        ax.insert_into(&mut fig);
+        inset.insert_into(&mut fig);
+        let svg = fig.render();
+        assert!(svg.contains("stroke-dasharray:4 3"));
+        assert!(svg.contains("fill:white"));
+    }
+
+    #[test]
+    fn test_line_styles_and_markers() {
+        let mut ax = Axis::new(0.0, 0.0, 1.0, 1.0)
+            .with_xlim(0.0, 10.0)
+            .with_ylim(0.0, 10.0)
+            .with_legend(true);
+        ax.add_line(
+            LinePlot::new(&[0.0, 5.0, 20.0], &[1.0, 2.0, 3.0])
+                .with_linewidth(2.0)
+                .with_line_style(LineStyle::Dashed)
+                .with_marker(Some(Marker::Square))
+                .with_name("line"),
+        );
+        ax.add_scatter(
+            ScatterPlot::new(&[1.0, 2.0], &[5.0, 5.0])
+                .with_marker(Marker::Triangle)
+                .with_color("red")
+                .with_name("peaks"),
+        );
+        let mut fig = Figure::empty(100, 100);
+        ax.insert_into(&mut fig);
+        let svg = fig.render();
+        assert!(svg.contains("stroke-dasharray:8 5"));
+        // Two squares inside of the axis and one in the legend, the axis
+        // itself is a rect as well.
+        assert_eq!(svg.matches("<rect").count(), 4);
+        // The scatter plot has no line, the legend shows its marker only.
+        assert_eq!(svg.matches("<polygon").count(), 3);
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert!(svg.contains("fill:red"));
+    }
+
+    #[test]
+    fn test_auto_margins() {
+        let mut fig = Figure::empty(400, 300);
+        let axes = Axis::stacked_in([0.0, 0.0, 1.0, 1.0], 2, 0.0);
+        let mut axes = axes.into_iter();
+        let top = axes
+            .next()
+            .unwrap()
+            .with_ylim(0.0, 1.0)
+            .with_xtick_labels(false)
+            .with_auto_margins(true);
+        let bottom = axes
+            .next()
+            .unwrap()
+            .with_ylim(0.0, 100000.0)
+            .with_xlabel("x")
+            .with_auto_margins(true);
+
+        // The labels of the lower axis are wider.
+        let [left, _, below, _] = bottom.label_extents();
+        assert!(left > top.label_extents()[0]);
+        let line = measure("", DEFAULT_FONT_SIZE);
+        let widest = bottom
+            .ticks
+            .y_tick_label_width(Scale::default(), DEFAULT_FONT_SIZE);
+        assert_eq!(widest, measure("80000", DEFAULT_FONT_SIZE).width);
+        assert!((left - (TICK_LABEL_PAD + widest)).abs() < 1e-9);
+        let expected = TICK_LABEL_PAD + AXIS_LABEL_PAD + 2.0 * line.height();
+        assert!((below - expected).abs() < 1e-9);
+
+        top.insert_into(&mut fig);
+        bottom.insert_into(&mut fig);
+        let [top, bottom] = fig.axes() else {
+            panic!("expected two axes");
+        };
+        // Both axes are aligned to the larger margin.
+        assert_eq!(top.u, bottom.u);
+        assert_eq!(top.width, bottom.width);
+        assert!((400.0 * bottom.u - left - OUTER_PAD).abs() < 1e-9);
+        let lowest = 300.0 * (bottom.v + bottom.height);
+        assert!((lowest - (300.0 - below - OUTER_PAD)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_annotations_and_areas() {
+        let mut ax = Axis::new(0.0, 0.0, 1.0, 1.0)
+            .with_xlim(0.0, 10.0)
+            .with_ylim(0.0, 10.0);
+        ax.add_label(Text::at("inside", 5.0, 5.0));
+        ax.add_label(Text::at("outside", 20.0, 5.0));
+        ax.add_area(Polygon::new(&[1.0, 2.0, 2.0], &[0.0, 0.0, 20.0]).with_color("red"));
+        let mut fig = Figure::empty(100, 100);
+        ax.insert_into(&mut fig);
+        let svg = fig.render();
+        assert!(svg.contains("inside"));
+        assert!(!svg.contains("outside"));
+        // The area is clipped to the top of the axis.
+        assert!(svg.contains("points=\"15,0 10,100 20,100 20,0\""));
+        assert!(svg.contains("fill:red"));
+
+        // A slanted edge leaving the axis is cut where it crosses the edge
+        // instead of running along the edge.
+        let clipped = clip_to_axis(vec![(0.5, 0.0), (1.5, 1.0), (0.5, 1.0)]);
+        assert_eq!(
+            clipped,
+            vec![(0.5, 0.0), (1.0, 0.5), (1.0, 1.0), (0.5, 1.0)]
+        );
+        assert!(clip_to_axis(vec![(2.0, 2.0), (3.0, 2.0), (3.0, 3.0)]).is_empty());
+    }
+
+    #[test]
+    fn test_fills_spans_and_ref_lines() {
+        let mut ax = Axis::new(0.0, 0.0, 1.0, 1.0)
+            .with_xlim(0.0, 10.0)
+            .with_ylim(0.0, 10.0)
+            .with_legend(true);
+        ax.add_fill(
+            FillBetween::new(&[0.0, 5.0, 10.0], &[1.0, 2.0, 1.0], &[0.0, 0.0, 0.0])
+                .with_color("blue")
+                .with_name("band"),
+        );
+        ax.add_span(Span::vertical(8.0, 12.0).with_label("window"));
+        ax.add_span(Span::horizontal(20.0, 30.0));
+        ax.add_span(Span::horizontal(2.0, 4.0).with_label("range"));
+        ax.add_ref_line(RefLine::horizontal(5.0).with_name("reference"));
+        ax.add_ref_line(RefLine::vertical(-1.0));
+        let entries: Vec<&str> = ax.legend_entries().iter().map(|e| e.name()).collect();
+        assert_eq!(entries, vec!["band", "", "", "", "reference", ""]);
+
+        let mut fig = Figure::empty(100, 100);
+        ax.insert_into(&mut fig);
+        let svg = fig.render();
+        // The fill runs along the first curve and back along the second.
+        assert!(svg.contains("points=\"0,90 50,80 100,90 100,100 50,100 0,100\""));
+        // The span is clipped to the axis, the one outside of it is dropped.
+        assert!(svg.contains("x=\"80\" y=\"0\" width=\"20\" height=\"100\""));
+        assert_eq!(svg.matches("fill-opacity:0.15").count(), 2);
+        assert!(svg.contains(">window</text>"));
+        // Labels hang from the top of their span, inside of it.
+        assert!(svg.contains("translate(94,77.488) rotate(0)"));
+        assert!(svg.contains(">range</text>"));
+        assert!(svg.contains("x1=\"0\" x2=\"100\" y1=\"50\" y2=\"50\""));
+        assert_eq!(svg.matches("stroke-dasharray").count(), 2);
+        assert!(svg.contains(">band</text>"));
+        assert!(svg.contains(">reference</text>"));
+
+        // Properties of reference lines override their style.
+        let mut line = RefLine::vertical(5.0).with_color("red");
+        line.add_svg_property("stroke-width", "3");
+        line.add_svg_property("stroke-dasharray", "1 1");
+        let style = line.line_params().unwrap();
+        assert_eq!(style["stroke"], "red");
+        assert_eq!(style["stroke-width"], "3");
+        assert_eq!(style["stroke-dasharray"], "1 1");
+    }
+
+    #[test]
+    fn test_log_ticks() {
+        assert_eq!(log_positions(1.0, 1000.0), vec![1.0, 10.0, 100.0, 1000.0]);
+        assert_eq!(
+            log_positions(0.5, 20.0),
+            vec![0.5, 1.0, 2.0, 5.0, 10.0, 20.0]
+        );
+        assert_eq!(log_positions(-1.0, 10.0).first(), Some(&0.01));
+        assert!(log_positions(-1.0, 0.0).is_empty());
+        assert_eq!(
+            format_scale_ticks(
+                &[0.001, 0.5, 100.0, 1e5],
+                Scale {
+                    log: true,
+                    reversed: false
+                }
+            ),
+            vec!["0.001", "0.5", "100", "1e5"]
+        );
+        assert_eq!(format_log_tick(1000.0000000002), "1000");
+        assert_eq!(format_log_tick(2e-5), "2e-5");
+        let minor = minor_positions(
+            10.0,
+            100.0,
+            3,
+            Scale {
+                log: true,
+                reversed: false,
+            },
+        );
+        assert_eq!(minor.len(), 8);
+        assert_eq!(
+            minor_positions(0.0, 1.0, 3, Scale::default()),
+            vec![0.25, 0.5, 0.75]
+        );
+    }
+
+    #[test]
+    fn test_scaled_axis() {
+        let log_reversed = Scale {
+            log: true,
+            reversed: true,
+        };
+        let ax = Axis::default()
+            .with_xlim(400.0, 4000.0)
+            .with_xscale(Scale {
+                log: false,
+                reversed: true,
+            })
+            .with_ylim(1.0, 100.0)
+            .with_yscale(log_reversed);
+        let fig = Figure::empty(100, 100);
+        let (_, _, u, v) = ax.transformations(&fig, false);
+        // The x-axis decreases to the right.
+        assert_eq!(u(4000.0), 0.0);
+        assert_eq!(u(400.0), 1.0);
+        // The y-axis is logarithmic and increases downwards (in axis
+        // coordinates, v increases downwards).
+        assert!((v(10.0) - 0.5).abs() < 1e-12);
+        assert!((v(100.0) - 1.0).abs() < 1e-12);
+
+        // Crossings are found on the logarithm of the data.
+        let line = LinePlot::new(&[1000.0, 1000.0], &[10.0, 1000.0]);
+        let segments = ax.segment_lineplot_data(&line);
+        assert_eq!(segments.len(), 1);
+        assert!((segments[0].1[1] - 100.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_ticks_magnitude_and_increment_1() {
         let pos = vec![0.001, 0.002, 0.003, 0.004];
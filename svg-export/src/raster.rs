@@ -0,0 +1,377 @@
+// Backend rasterising a `Figure` into a PNG image. Shapes are filled with
+// anti-aliasing by accumulating their coverage, lines are stroked as filled
+// quadrilaterals and texts are set in the Ubuntu font shipped with egui.
+
+use ab_glyph::{Font, FontRef, ScaleFont};
+use ab_glyph_rasterizer::{point, Rasterizer};
+
+use crate::backend::{dashes, Backend, Color, Style};
+
+pub struct RasterBackend {
+    width: usize,
+    height: usize,
+    /// Pixels of the figure per pixel of the image.
+    scale: f64,
+    /// RGB colors of the pixels, row by row, on a white background.
+    pixels: Vec<[f64; 3]>,
+    font: FontRef<'static>,
+}
+
+impl RasterBackend {
+    /// A backend drawing a figure of `width` x `height` pixels, at a
+    /// resolution of `dpi` (where 96 DPI draw one pixel per pixel).
+    pub fn new(width: u64, height: u64, dpi: f64) -> Result<Self, String> {
+        if !(dpi.is_finite() && dpi > 0.0) {
+            return Err(format!("invalid resolution of {dpi} DPI"));
+        }
+        let scale = dpi / 96.0;
+        let (width, height) = (
+            (width as f64 * scale).round() as usize,
+            (height as f64 * scale).round() as usize,
+        );
+        if width == 0 || height == 0 || width.saturating_mul(height) > 1 << 28 {
+            return Err(format!("invalid image size of {width}x{height} pixels"));
+        }
+        let font = FontRef::try_from_slice(epaint_default_fonts::UBUNTU_LIGHT)
+            .map_err(|err| format!("could not load font: {err}"))?;
+        Ok(Self {
+            width,
+            height,
+            scale,
+            pixels: vec![[1.0; 3]; width * height],
+            font,
+        })
+    }
+
+    /// The image encoded as PNG, with its resolution set to `dpi`.
+    pub fn finish(self, dpi: f64) -> Result<Vec<u8>, String> {
+        let mut buf = Vec::new();
+        let mut encoder = png::Encoder::new(&mut buf, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let pixels_per_meter = (dpi / 0.0254).round() as u32;
+        encoder.set_pixel_dims(Some(png::PixelDimensions {
+            xppu: pixels_per_meter,
+            yppu: pixels_per_meter,
+            unit: png::Unit::Meter,
+        }));
+        let data: Vec<u8> = self
+            .pixels
+            .iter()
+            .flat_map(|rgb| rgb.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8))
+            .collect();
+        let mut writer = encoder
+            .write_header()
+            .map_err(|err| format!("could not write png header: {err}"))?;
+        writer
+            .write_image_data(&data)
+            .map_err(|err| format!("could not write png data: {err}"))?;
+        writer
+            .finish()
+            .map_err(|err| format!("could not finish png: {err}"))?;
+        Ok(buf)
+    }
+
+    /// Blend `color` into the pixel at `x`, `y` with the coverage `alpha`.
+    fn blend(&mut self, x: usize, y: usize, color: Color, alpha: f64) {
+        let alpha = alpha.clamp(0.0, 1.0) * color.a;
+        let pixel = &mut self.pixels[y * self.width + x];
+        for (c, source) in pixel.iter_mut().zip([color.r, color.g, color.b]) {
+            *c = *c * (1.0 - alpha) + source * alpha;
+        }
+    }
+
+    /// Fill the union of the `polygons` (in pixels of the image).
+    fn fill_polygons(&mut self, polygons: &[Vec<[f64; 2]>], color: Color) {
+        // Only the bounding box of the polygons within the image is drawn.
+        let clip = [0.0, 0.0, self.width as f64, self.height as f64];
+        let polygons: Vec<Vec<[f64; 2]>> = polygons
+            .iter()
+            .map(|polygon| clip_polygon(polygon, clip))
+            .filter(|polygon| polygon.len() > 2)
+            .collect();
+        let Some([x0, y0, x1, y1]) = bounding_box(polygons.iter().flatten()) else {
+            return;
+        };
+        let (left, top) = (x0.floor(), y0.floor());
+        let (w, h) = ((x1.ceil() - left) as usize, (y1.ceil() - top) as usize);
+        if w == 0 || h == 0 {
+            return;
+        }
+        let mut rasterizer = Rasterizer::new(w, h);
+        for polygon in polygons.iter() {
+            // Overlapping polygons must have the same orientation, so that
+            // their coverage adds up instead of cancelling.
+            let reversed = signed_area(polygon) > 0.0;
+            let corners: Vec<[f64; 2]> = if reversed {
+                polygon.iter().rev().copied().collect()
+            } else {
+                polygon.clone()
+            };
+            for (i, [xa, ya]) in corners.iter().enumerate() {
+                let [xb, yb] = corners[(i + 1) % corners.len()];
+                rasterizer.draw_line(
+                    point((xa - left) as f32, (ya - top) as f32),
+                    point((xb - left) as f32, (yb - top) as f32),
+                );
+            }
+        }
+        let (left, top) = (left as usize, top as usize);
+        rasterizer.for_each_pixel_2d(|x, y, alpha| {
+            let (x, y) = (left + x as usize, top + y as usize);
+            if alpha > 0.0 && x < self.width && y < self.height {
+                self.blend(x, y, color, alpha.min(1.0) as f64);
+            }
+        });
+    }
+}
+
+impl Backend for RasterBackend {
+    fn stroke(&mut self, points: &[[f64; 2]], closed: bool, style: &Style) {
+        let Some(color) = style.stroke else {
+            return;
+        };
+        let mut points: Vec<[f64; 2]> = points.iter().map(|p| self.to_image(*p)).collect();
+        if let (true, Some(first)) = (closed, points.first()) {
+            points.push(*first);
+        }
+        let dasharray: Vec<f64> = style.dasharray.iter().map(|d| d * self.scale).collect();
+        // Lines are at least one pixel wide, to stay visible.
+        let half_width = 0.5 * (style.stroke_width * self.scale).max(1.0);
+        let mut polygons = Vec::new();
+        for dash in dashes(&points, &dasharray) {
+            for segment in dash.windows(2) {
+                polygons.push(segment_outline(segment[0], segment[1], half_width));
+            }
+            // Round joins and caps.
+            if half_width > 1.0 {
+                polygons.extend(dash.iter().map(|p| circle(*p, half_width)));
+            }
+        }
+        self.fill_polygons(&polygons, color);
+    }
+
+    fn fill(&mut self, points: &[[f64; 2]], style: &Style) {
+        let Some(color) = style.fill else {
+            return;
+        };
+        let polygon: Vec<[f64; 2]> = points.iter().map(|p| self.to_image(*p)).collect();
+        self.fill_polygons(&[polygon], color);
+    }
+
+    fn text(&mut self, text: &str, position: [f64; 2], angle: f64, style: &Style) {
+        let Some(color) = style.fill else {
+            return;
+        };
+        let font = self.font.as_scaled((style.font_size * self.scale) as f32);
+        // Set the text horizontally, with the baseline at the ascent.
+        let mut outlines = Vec::new();
+        let caret = self.set_text(text, font.scale(), |glyph| {
+            let glyph = ab_glyph::Glyph {
+                position: point(glyph.position.x, font.ascent()),
+                ..glyph
+            };
+            outlines.extend(self.font.outline_glyph(glyph));
+        });
+        let (w, h) = (
+            caret.ceil() as usize + 1,
+            (font.ascent() - font.descent()).ceil() as usize + 1,
+        );
+        let mut coverage = vec![0.0f32; w * h];
+        for outline in outlines.iter() {
+            let bounds = outline.px_bounds();
+            outline.draw(|x, y, c| {
+                let (x, y) = (
+                    bounds.min.x as i64 + x as i64,
+                    bounds.min.y as i64 + y as i64,
+                );
+                if (0..w as i64).contains(&x) && (0..h as i64).contains(&y) {
+                    let index = y as usize * w + x as usize;
+                    coverage[index] = (coverage[index] + c).min(1.0);
+                }
+            });
+        }
+
+        // Place the text by mapping each pixel of the image back into it.
+        let [x0, y0] = self.to_image(position);
+        let origin = [style.anchor.offset() * caret as f64, font.ascent() as f64];
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let corners = [
+            [0.0, 0.0],
+            [w as f64, 0.0],
+            [w as f64, h as f64],
+            [0.0, h as f64],
+        ]
+        .map(|[u, v]| {
+            let (u, v) = (u - origin[0], v - origin[1]);
+            [x0 + u * cos - v * sin, y0 + u * sin + v * cos]
+        });
+        let Some([left, top, right, bottom]) = bounding_box(corners.iter()) else {
+            return;
+        };
+        let xs = (left.floor().max(0.0) as usize)..(right.ceil().min(self.width as f64) as usize);
+        let ys = (top.floor().max(0.0) as usize)..(bottom.ceil().min(self.height as f64) as usize);
+        for y in ys {
+            for x in xs.clone() {
+                let (dx, dy) = (x as f64 + 0.5 - x0, y as f64 + 0.5 - y0);
+                let (u, v) = (
+                    dx * cos + dy * sin + origin[0],
+                    -dx * sin + dy * cos + origin[1],
+                );
+                if u < 0.0 || v < 0.0 || u >= w as f64 || v >= h as f64 {
+                    continue;
+                }
+                let c = coverage[v as usize * w + u as usize];
+                if c > 0.0 {
+                    self.blend(x, y, color, c as f64);
+                }
+            }
+        }
+    }
+
+    fn text_width(&self, text: &str, style: &Style) -> f64 {
+        let scale = ab_glyph::PxScale::from((style.font_size * self.scale) as f32);
+        self.set_text(text, scale, |_| {}) as f64 / self.scale
+    }
+}
+
+impl RasterBackend {
+    /// Lay out the glyphs of `text` in a row at the size `scale`, passing
+    /// each to `glyph`. Returns the width of the text, in pixels of the
+    /// image.
+    fn set_text(
+        &self,
+        text: &str,
+        scale: ab_glyph::PxScale,
+        mut glyph: impl FnMut(ab_glyph::Glyph),
+    ) -> f32 {
+        let font = self.font.as_scaled(scale);
+        let mut caret = 0.0;
+        let mut previous = None;
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                caret += font.kern(previous, id);
+            }
+            glyph(id.with_scale_and_position(scale, point(caret, 0.0)));
+            caret += font.h_advance(id);
+            previous = Some(id);
+        }
+        caret
+    }
+
+    fn to_image(&self, [x, y]: [f64; 2]) -> [f64; 2] {
+        [x * self.scale, y * self.scale]
+    }
+}
+
+/// The rectangle covered by a line segment of the width `2 * half_width`.
+fn segment_outline([x0, y0]: [f64; 2], [x1, y1]: [f64; 2], half_width: f64) -> Vec<[f64; 2]> {
+    let length = (x1 - x0).hypot(y1 - y0);
+    if length == 0.0 || !length.is_finite() {
+        return Vec::new();
+    }
+    let (nx, ny) = (
+        -(y1 - y0) / length * half_width,
+        (x1 - x0) / length * half_width,
+    );
+    vec![
+        [x0 + nx, y0 + ny],
+        [x1 + nx, y1 + ny],
+        [x1 - nx, y1 - ny],
+        [x0 - nx, y0 - ny],
+    ]
+}
+
+fn circle([x, y]: [f64; 2], radius: f64) -> Vec<[f64; 2]> {
+    let n = 16;
+    (0..n)
+        .map(|i| {
+            let phi = 2.0 * std::f64::consts::PI * i as f64 / n as f64;
+            [x + radius * phi.cos(), y + radius * phi.sin()]
+        })
+        .collect()
+}
+
+fn signed_area(polygon: &[[f64; 2]]) -> f64 {
+    (0..polygon.len())
+        .map(|i| {
+            let ([xa, ya], [xb, yb]) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+            xa * yb - xb * ya
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// The smallest rectangle `[xmin, ymin, xmax, ymax]` containing `points`.
+fn bounding_box<'a>(points: impl Iterator<Item = &'a [f64; 2]>) -> Option<[f64; 4]> {
+    points.fold(None, |bounds, [x, y]| match bounds {
+        Some([x0, y0, x1, y1]) => Some([x0.min(*x), y0.min(*y), x1.max(*x), y1.max(*y)]),
+        None => Some([*x, *y, *x, *y]),
+    })
+}
+
+/// Clip `polygon` to the rectangle `[xmin, ymin, xmax, ymax]` (Sutherland-
+/// Hodgman), dropping corners which are not finite.
+fn clip_polygon(polygon: &[[f64; 2]], [xmin, ymin, xmax, ymax]: [f64; 4]) -> Vec<[f64; 2]> {
+    let mut points: Vec<[f64; 2]> = polygon
+        .iter()
+        .filter(|[x, y]| x.is_finite() && y.is_finite())
+        .copied()
+        .collect();
+    // Each edge of the rectangle, as the coordinate and the side to keep.
+    let edges: [(usize, f64, bool); 4] = [
+        (0, xmin, true),
+        (0, xmax, false),
+        (1, ymin, true),
+        (1, ymax, false),
+    ];
+    for (axis, limit, above) in edges {
+        let inside = |p: &[f64; 2]| {
+            if above {
+                p[axis] >= limit
+            } else {
+                p[axis] <= limit
+            }
+        };
+        let input = std::mem::take(&mut points);
+        for (i, current) in input.iter().enumerate() {
+            let previous = input[(i + input.len() - 1) % input.len()];
+            if inside(current) != inside(&previous) {
+                let t = (limit - previous[axis]) / (current[axis] - previous[axis]);
+                points.push([
+                    previous[0] + t * (current[0] - previous[0]),
+                    previous[1] + t * (current[1] - previous[1]),
+                ]);
+            }
+            if inside(current) {
+                points.push(*current);
+            }
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clip_polygon() {
+        let square = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+        let clipped = clip_polygon(&square, [0.0, 0.0, 2.0, 2.0]);
+        assert_eq!(signed_area(&clipped).abs(), 1.0);
+        assert!(clip_polygon(&square, [5.0, 5.0, 6.0, 6.0]).is_empty());
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut backend = RasterBackend::new(10, 10, 96.0).unwrap();
+        backend.fill_polygons(
+            &[vec![[2.0, 2.0], [6.0, 2.0], [6.0, 6.0], [2.0, 6.0]]],
+            Color::BLACK,
+        );
+        assert_eq!(backend.pixels[3 * 10 + 3], [0.0; 3]);
+        assert_eq!(backend.pixels[8 * 10 + 8], [1.0; 3]);
+    }
+}
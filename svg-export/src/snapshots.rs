@@ -0,0 +1,174 @@
+// Snapshot tests comparing the rendered markup of reference figures byte for
+// byte with the files in `test/snapshots`. After an intended change of the
+// output, the files are rewritten by running the tests with
+// `UPDATE_SNAPSHOTS=1` (see the justfile), and the diff is reviewed in git.
+
+use std::path::PathBuf;
+
+use crate::{
+    Axis, Figure, FigureSize, Frame, Legend, LegendPosition, LinePlot, LineStyle, Marker, Polygon,
+    Scale, ScatterPlot, SizeUnit, Text, Theme, TickDirection,
+};
+
+/// Compare `svg` with the snapshot `name`, or rewrite the snapshot.
+fn assert_snapshot(name: &str, svg: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("test")
+        .join("snapshots")
+        .join(format!("{name}.svg"));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(&path, svg).expect("could not write snapshot");
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("could not read snapshot {}: {err}", path.display()));
+    if expected != svg {
+        let line = expected
+            .lines()
+            .zip(svg.lines())
+            .position(|(a, b)| a != b)
+            .unwrap_or(expected.lines().count().min(svg.lines().count()));
+        panic!(
+            "{name}.svg differs from its snapshot in line {}, \
+             run with UPDATE_SNAPSHOTS=1 to accept the change",
+            line + 1
+        );
+    }
+}
+
+fn sine(n: usize, phase: f64) -> (Vec<f64>, Vec<f64>) {
+    let xs: Vec<f64> = (0..n).map(|i| i as f64 / (n - 1) as f64 * 10.0).collect();
+    let ys = xs.iter().map(|x| (x + phase).sin()).collect();
+    (xs, ys)
+}
+
+#[test]
+fn test_snapshot_lines() {
+    let mut ax = Axis::new(0.0, 0.0, 1.0, 1.0)
+        .with_xlim(0.0, 10.0)
+        .with_ylim(-1.5, 1.5)
+        .with_xlabel("Wavenumber / cm^{-1}")
+        .with_ylabel("\\Delta A")
+        .with_auto_margins(true)
+        .with_legend_layout(
+            Legend::new()
+                .with_position(LegendPosition::UpperRight)
+                .with_frame(true)
+                .with_group_headers(true),
+        );
+    let (xs, ys) = sine(50, 0.0);
+    ax.add_line(
+        LinePlot::new(&xs, &ys)
+            .with_color("#1f77b4")
+            .with_name("sample <1> & \"reference\"")
+            .with_group("group 'A'"),
+    );
+    let (xs, ys) = sine(20, 1.0);
+    ax.add_line(
+        LinePlot::new(&xs, &ys)
+            .with_color("#ff7f0e")
+            .with_line_style(LineStyle::Dashed)
+            .with_marker(Some(Marker::Square))
+            .with_name("CO_{2}")
+            .with_group("group 'A'"),
+    );
+    ax.add_area(Polygon::new(&[2.0, 3.0, 3.0, 2.0], &[-1.5, -1.5, 1.5, 1.5]).with_opacity(0.2));
+    ax.add_label(Text::at("peak", 1.6, 1.1));
+    let mut fig = Figure::empty(400, 300);
+    ax.insert_into(&mut fig);
+
+    let svg = fig.render();
+    // The same figure always yields the same markup.
+    assert_eq!(svg, fig.render());
+    assert_snapshot("lines", &svg);
+}
+
+#[test]
+fn test_snapshot_stacked() {
+    let mut fig = Figure::empty(400, 400);
+    let axes = Axis::stacked_in([0.0, 0.0, 1.0, 1.0], 2, 0.0);
+    let mut axes = axes.into_iter();
+
+    let mut top = axes
+        .next()
+        .unwrap()
+        .with_xlim(1.0, 1000.0)
+        .with_ylim(0.0, 1.0)
+        .with_y2lim(0.0, 100.0)
+        .with_y2label("T / \\deg C")
+        .with_xscale(Scale {
+            log: true,
+            reversed: false,
+        })
+        .with_xtick_labels(false)
+        .with_auto_margins(true);
+    let xs: Vec<f64> = (0..10).map(|i| 10f64.powf(i as f64 / 3.0)).collect();
+    let ys: Vec<f64> = xs.iter().map(|x| x.log10() / 3.0).collect();
+    top.add_scatter(
+        ScatterPlot::new(&xs, &ys)
+            .with_color("green")
+            .with_marker(Marker::Triangle),
+    );
+    let temperatures: Vec<f64> = xs.iter().map(|x| 20.0 + 20.0 * x.log10()).collect();
+    top.add_line(
+        LinePlot::new(&xs, &temperatures)
+            .with_color("red")
+            .with_secondary_y(true),
+    );
+
+    let mut bottom = axes
+        .next()
+        .unwrap()
+        .with_xlim(1.0, 1000.0)
+        .with_ylim(0.0, 5000.0)
+        .with_xscale(Scale {
+            log: true,
+            reversed: false,
+        })
+        .with_xlabel("t / s")
+        .with_ylabel("I / counts")
+        .with_auto_margins(true);
+    let counts: Vec<f64> = xs.iter().map(|x| 5000.0 / (1.0 + x / 100.0)).collect();
+    bottom.add_line(LinePlot::new(&xs, &counts).with_line_style(LineStyle::Dotted));
+
+    top.insert_into(&mut fig);
+    bottom.insert_into(&mut fig);
+    assert_snapshot("stacked", &fig.render());
+}
+
+#[test]
+fn test_snapshot_themed() {
+    let theme = Theme {
+        font_family: "Arial".to_string(),
+        label_size: 9.0,
+        tick_label_size: 8.0,
+        tick_direction: TickDirection::Out,
+        tick_length: Some(4.0),
+        frame: Frame::Box,
+        color_cycle: vec!["#0072b2".to_string(), "#e69f00".to_string()],
+        legend: Legend::new()
+            .with_position(LegendPosition::LowerLeft)
+            .with_font_size(8.0),
+    };
+    let (width, height) = FigureSize {
+        width: 85.0,
+        height: 60.0,
+        unit: SizeUnit::Mm,
+    }
+    .pixels();
+    let mut fig = Figure::empty(width, height);
+    let mut ax = Axis::new(0.0, 0.0, 1.0, 1.0)
+        .with_theme(&theme)
+        .with_legend(true)
+        .with_xlim(0.0, 10.0)
+        .with_ylim(-1.5, 1.5)
+        .with_xlabel("t / s")
+        .with_ylabel("U / V")
+        .with_auto_margins(true);
+    for (i, phase) in [0.0, 1.0, 2.0].into_iter().enumerate() {
+        let (xs, ys) = sine(30, phase);
+        ax.add_line(LinePlot::new(&xs, &ys).with_name(&format!("phase {i}")));
+    }
+    ax.insert_into(&mut fig);
+    assert_snapshot("themed", &fig.render());
+}
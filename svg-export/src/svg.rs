@@ -7,6 +7,9 @@ use std::{
     io::Seek,
 };
 
+use crate::backend::{Anchor, Backend, Style};
+use crate::markup::{self, Script, Span};
+
 // ----------------------------------------------------------------------------
 //
 //
@@ -15,10 +18,14 @@ use std::{
 //
 // ----------------------------------------------------------------------------
 
-pub type Params = std::collections::HashMap<String, String>;
+/// Attributes or style properties of a tag, rendered in the order they were
+/// inserted, so that the same figure always yields the same markup.
+pub type Params = indexmap::IndexMap<String, String>;
 
 pub trait RenderTag: std::fmt::Debug {
     fn render(&self, buf: &mut String);
+    /// Draw the tag with `backend`, instead of rendering it to markup.
+    fn draw(&self, backend: &mut dyn Backend);
 }
 
 impl<T> RenderTag for Tag<T>
@@ -28,12 +35,12 @@ where
     fn render(&self, buf: &mut String) {
         write!(buf, "<{}", self.kind.identifier()).expect(FAILED_STRING_WRITE);
         for (k, v) in self.parameters.iter() {
-            write!(buf, " {k}=\"{v}\"").expect(FAILED_STRING_WRITE);
+            write!(buf, " {k}=\"{}\"", escape(v)).expect(FAILED_STRING_WRITE);
         }
         if !self.style.is_empty() {
             write!(buf, " style=\"").expect(FAILED_STRING_WRITE);
             for (k, v) in self.style.iter() {
-                write!(buf, "{k}:{v};").expect(FAILED_STRING_WRITE);
+                write!(buf, "{k}:{};", escape(v)).expect(FAILED_STRING_WRITE);
             }
             // Remove last surplus space.
             buf.pop();
@@ -43,11 +50,26 @@ where
             write!(buf, " /").expect(FAILED_STRING_WRITE);
         }
         write!(buf, ">").expect(FAILED_STRING_WRITE);
+        // Each tag starts on a new line, unless it is part of a text.
+        if !self.kind.has_text() {
+            buf.push('\n');
+        }
         for c in self.children.iter() {
             c.render(buf);
         }
         if self.closing {
             write!(buf, "</{}>", self.kind.identifier()).expect(FAILED_STRING_WRITE);
+            if !self.kind.is_text() {
+                buf.push('\n');
+            }
+        }
+    }
+
+    fn draw(&self, backend: &mut dyn Backend) {
+        let style = Style::from_params(&self.parameters, &self.style);
+        self.kind.draw(&style, &self.children, backend);
+        for c in self.children.iter() {
+            c.draw(backend);
         }
     }
 }
@@ -56,10 +78,14 @@ impl RenderTag for String {
     fn render(&self, buf: &mut String) {
         buf.push_str(self);
     }
+
+    // The content of text tags is drawn by the tags.
+    fn draw(&self, _backend: &mut dyn Backend) {}
 }
 
 pub fn render(svg_tag: &Tag<SVG>) -> String {
-    let mut raw_svg = String::new();
+    let mut raw_svg =
+        String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n");
     svg_tag.render(&mut raw_svg);
     raw_svg
 }
@@ -122,21 +148,17 @@ impl Tag<SVG> {
 impl Tag<Circle> {
     pub fn new(cx: f64, cy: f64, r: f64, style: Option<Params>) -> Self {
         let children = Vec::new();
-        let mut parameters: Params = [
-            ("cx", format!("{cx}")),
-            ("cy", format!("{cy}")),
-            ("r", format!("{r}")),
-        ]
-        .into_iter()
-        .map(|(k, v)| (k.to_string(), v))
-        .collect();
+        let mut parameters: Params = [("cx", num(cx)), ("cy", num(cy)), ("r", num(r))]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
 
         Self {
             parameters,
             style: style.unwrap_or_default(),
             children,
             closing: false,
-            kind: Circle {},
+            kind: Circle { cx, cy, r },
         }
     }
 }
@@ -145,10 +167,10 @@ impl Tag<Rect> {
     pub fn new(x: f64, y: f64, width: f64, height: f64, style: Option<Params>) -> Self {
         let children = Vec::new();
         let mut parameters: Params = [
-            ("x", format!("{x}")),
-            ("y", format!("{y}")),
-            ("width", format!("{width}")),
-            ("height", format!("{height}")),
+            ("x", num(x)),
+            ("y", num(y)),
+            ("width", num(width)),
+            ("height", num(height)),
         ]
         .into_iter()
         .map(|(k, v)| (k.to_string(), v))
@@ -159,28 +181,82 @@ impl Tag<Rect> {
             style: style.unwrap_or_default(),
             children,
             closing: false,
-            kind: Rect {},
+            kind: Rect {
+                x,
+                y,
+                width,
+                height,
+            },
         }
     }
 }
 
 impl Tag<Text> {
+    /// A text tag, with `text` in the markup of the `markup` module. Scripts
+    /// are set in `tspan` tags, shifting the baseline.
     pub fn new(x: f64, y: f64, angle: f64, text: &str, style: Option<Params>) -> Self {
         let children = Vec::new();
-        let mut parameters: Params = [("transform", format!("translate({x},{y}) rotate({angle})"))]
-            .into_iter()
-            .map(|(k, v)| (k.to_string(), v))
-            .collect();
+        let mut parameters: Params = [(
+            "transform",
+            format!("translate({},{}) rotate({})", num(x), num(y), num(angle)),
+        )]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
 
+        let spans = markup::parse(text);
         let mut res = Self {
             parameters,
             style: style.unwrap_or_default(),
             children,
             closing: true,
-            kind: Text {},
+            kind: Text {
+                x,
+                y,
+                angle,
+                spans: spans.clone(),
+            },
         };
 
-        res.add_child(text.to_string());
+        match &spans[..] {
+            [] => {}
+            [span] if span.script == Script::Normal => res.add_child(escape(&span.text)),
+            _ => {
+                // The rise of the current baseline, in units of the font size.
+                let mut rise = 0.0;
+                for span in spans.iter() {
+                    res.add_child(Tag::<TSpan>::new(span, rise));
+                    rise = span.script.rise();
+                }
+            }
+        }
+        res
+    }
+}
+
+impl Tag<TSpan> {
+    /// A `tspan` tag for `span`, following text on a baseline raised by
+    /// `rise` times the font size of the text.
+    fn new(span: &Span, rise: f64) -> Self {
+        let scale = span.script.scale();
+        let mut parameters = Params::new();
+        if scale != 1.0 {
+            parameters.insert("font-size".to_string(), format!("{}%", num(scale * 100.0)));
+        }
+        // `dy` is relative to the font size of the span itself.
+        let dy = (rise - span.script.rise()) / scale;
+        if dy != 0.0 {
+            parameters.insert("dy".to_string(), format!("{}em", num(dy)));
+        }
+
+        let mut res = Self {
+            parameters,
+            style: Params::new(),
+            children: Vec::new(),
+            closing: true,
+            kind: TSpan {},
+        };
+        res.add_child(escape(&span.text));
         res
     }
 }
@@ -189,10 +265,10 @@ impl Tag<Line> {
     pub fn new(x1: f64, x2: f64, y1: f64, y2: f64, style: Option<Params>) -> Self {
         let children = Vec::new();
         let mut parameters: Params = [
-            ("x1", format!("{x1}")),
-            ("x2", format!("{x2}")),
-            ("y1", format!("{y1}")),
-            ("y2", format!("{y2}")),
+            ("x1", num(x1)),
+            ("x2", num(x2)),
+            ("y1", num(y1)),
+            ("y2", num(y2)),
             ("stroke", "black".to_string()),
         ]
         .into_iter()
@@ -204,7 +280,7 @@ impl Tag<Line> {
             style: style.unwrap_or_default(),
             children,
             closing: false,
-            kind: Line {},
+            kind: Line { x1, x2, y1, y2 },
         }
     }
 }
@@ -215,10 +291,8 @@ impl Tag<Polyline> {
         style: Option<Params>,
     ) -> Self {
         let children = Vec::new();
-        let mut raw_points = String::new();
-        for (x, y) in xs.into_iter().zip(ys.into_iter()) {
-            write!(raw_points, "{x},{y} ");
-        }
+        let points: Vec<[f64; 2]> = xs.into_iter().zip(ys).map(|(x, y)| [x, y]).collect();
+        let raw_points = raw_points(&points);
         let mut parameters: Params = [("points", raw_points), ("fill", "none".to_string())]
             .into_iter()
             .map(|(k, v)| (k.to_string(), v))
@@ -229,7 +303,31 @@ impl Tag<Polyline> {
             style: style.unwrap_or_default(),
             children,
             closing: false,
-            kind: Polyline {},
+            kind: Polyline { points },
+        }
+    }
+}
+
+impl Tag<Polygon> {
+    pub fn new(
+        xs: impl IntoIterator<Item = f64>,
+        ys: impl IntoIterator<Item = f64>,
+        style: Option<Params>,
+    ) -> Self {
+        let children = Vec::new();
+        let points: Vec<[f64; 2]> = xs.into_iter().zip(ys).map(|(x, y)| [x, y]).collect();
+        let raw_points = raw_points(&points);
+        let mut parameters: Params = [("points", raw_points)]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+
+        Self {
+            parameters,
+            style: style.unwrap_or_default(),
+            children,
+            closing: false,
+            kind: Polygon { points },
         }
     }
 }
@@ -242,21 +340,64 @@ impl Tag<Polyline> {
 //
 // ----------------------------------------------------------------------------
 
+// The kinds keep their geometry, to draw them with a `Backend`.
+
 #[derive(Debug)]
 pub struct SVG {}
 #[derive(Debug)]
-pub struct Circle {}
+pub struct Circle {
+    cx: f64,
+    cy: f64,
+    r: f64,
+}
+#[derive(Debug)]
+pub struct Rect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+#[derive(Debug)]
+pub struct Text {
+    x: f64,
+    y: f64,
+    angle: f64,
+    spans: Vec<Span>,
+}
 #[derive(Debug)]
-pub struct Rect {}
+pub struct TSpan {}
 #[derive(Debug)]
-pub struct Text {}
+pub struct Line {
+    x1: f64,
+    x2: f64,
+    y1: f64,
+    y2: f64,
+}
 #[derive(Debug)]
-pub struct Line {}
+pub struct Polyline {
+    points: Vec<[f64; 2]>,
+}
 #[derive(Debug)]
-pub struct Polyline {}
+pub struct Polygon {
+    points: Vec<[f64; 2]>,
+}
 
 trait Identifier {
     fn identifier(&self) -> &'static str;
+
+    /// Whether the content of the tag is text, where whitespace matters.
+    fn has_text(&self) -> bool {
+        false
+    }
+
+    /// Whether the tag is part of the content of a text.
+    fn is_text(&self) -> bool {
+        false
+    }
+
+    /// Draw the shape of this kind of tag, with the resolved `style` of the
+    /// tag. Its `children` are drawn afterwards.
+    fn draw(&self, style: &Style, children: &[Box<dyn RenderTag>], backend: &mut dyn Backend) {}
 }
 
 impl Identifier for SVG {
@@ -269,34 +410,184 @@ impl Identifier for Circle {
     fn identifier(&self) -> &'static str {
         "circle"
     }
+
+    fn draw(&self, style: &Style, _: &[Box<dyn RenderTag>], backend: &mut dyn Backend) {
+        let n = 64;
+        let points: Vec<[f64; 2]> = (0..n)
+            .map(|i| {
+                let phi = 2.0 * std::f64::consts::PI * i as f64 / n as f64;
+                [self.cx + self.r * phi.cos(), self.cy + self.r * phi.sin()]
+            })
+            .collect();
+        draw_shape(&points, true, style, backend);
+    }
 }
 
 impl Identifier for Rect {
     fn identifier(&self) -> &'static str {
         "rect"
     }
+
+    fn draw(&self, style: &Style, _: &[Box<dyn RenderTag>], backend: &mut dyn Backend) {
+        let (x0, y0, x1, y1) = (self.x, self.y, self.x + self.width, self.y + self.height);
+        draw_shape(
+            &[[x0, y0], [x1, y0], [x1, y1], [x0, y1]],
+            true,
+            style,
+            backend,
+        );
+    }
 }
 
 impl Identifier for Text {
     fn identifier(&self) -> &'static str {
         "text"
     }
+
+    fn has_text(&self) -> bool {
+        true
+    }
+
+    fn draw(&self, style: &Style, _: &[Box<dyn RenderTag>], backend: &mut dyn Backend) {
+        if style.fill.is_none() {
+            return;
+        }
+        match &self.spans[..] {
+            [] => {}
+            [span] if span.script == Script::Normal => {
+                backend.text(&span.text, [self.x, self.y], self.angle, style)
+            }
+            spans => {
+                // Set the spans one after another from the start of the whole
+                // text, with scripts shifted perpendicular to the baseline.
+                let styles: Vec<Style> = spans
+                    .iter()
+                    .map(|span| Style {
+                        font_size: style.font_size * span.script.scale(),
+                        anchor: Anchor::Start,
+                        ..style.clone()
+                    })
+                    .collect();
+                let widths: Vec<f64> = spans
+                    .iter()
+                    .zip(styles.iter())
+                    .map(|(span, style)| backend.text_width(&span.text, style))
+                    .collect();
+                let (sin, cos) = self.angle.to_radians().sin_cos();
+                let mut caret = -style.anchor.offset() * widths.iter().sum::<f64>();
+                for ((span, span_style), width) in spans.iter().zip(styles.iter()).zip(widths) {
+                    let rise = span.script.rise() * style.font_size;
+                    let position = [
+                        self.x + caret * cos + rise * sin,
+                        self.y + caret * sin - rise * cos,
+                    ];
+                    backend.text(&span.text, position, self.angle, span_style);
+                    caret += width;
+                }
+            }
+        }
+    }
+}
+
+impl Identifier for TSpan {
+    fn identifier(&self) -> &'static str {
+        "tspan"
+    }
+
+    fn has_text(&self) -> bool {
+        true
+    }
+
+    fn is_text(&self) -> bool {
+        true
+    }
 }
 
 impl Identifier for Line {
     fn identifier(&self) -> &'static str {
         "line"
     }
+
+    fn draw(&self, style: &Style, _: &[Box<dyn RenderTag>], backend: &mut dyn Backend) {
+        if style.stroke.is_some() {
+            backend.stroke(&[[self.x1, self.y1], [self.x2, self.y2]], false, style);
+        }
+    }
 }
 
 impl Identifier for Polyline {
     fn identifier(&self) -> &'static str {
         "polyline"
     }
+
+    fn draw(&self, style: &Style, _: &[Box<dyn RenderTag>], backend: &mut dyn Backend) {
+        draw_shape(&self.points, false, style, backend);
+    }
+}
+
+impl Identifier for Polygon {
+    fn identifier(&self) -> &'static str {
+        "polygon"
+    }
+
+    fn draw(&self, style: &Style, _: &[Box<dyn RenderTag>], backend: &mut dyn Backend) {
+        draw_shape(&self.points, true, style, backend);
+    }
+}
+
+/// Fill and stroke the outline `points`, as far as `style` requires.
+fn draw_shape(points: &[[f64; 2]], closed: bool, style: &Style, backend: &mut dyn Backend) {
+    if points.len() < 2 {
+        return;
+    }
+    if style.fill.is_some() && points.len() > 2 {
+        backend.fill(points, style);
+    }
+    if style.stroke.is_some() {
+        backend.stroke(points, closed, style);
+    }
+}
+
+/// The `points` attribute of a polyline or polygon.
+fn raw_points(points: &[[f64; 2]]) -> String {
+    let points: Vec<String> = points
+        .iter()
+        .map(|[x, y]| format!("{},{}", num(*x), num(*y)))
+        .collect();
+    points.join(" ")
 }
 
 const FAILED_STRING_WRITE: &str = "Failed to write into string.";
 
+/// Escape the characters of `text` with a meaning in XML, for text content
+/// as well as for attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Format a number compactly with at most three decimals, as the markup
+/// and PDF need no more for pixels. PDF does not accept exponents.
+pub(crate) fn num(value: f64) -> String {
+    let value = if value.is_finite() { value } else { 0.0 };
+    let formatted = format!("{value:.3}");
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    match trimmed {
+        "" | "-0" => "0".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
 pub fn opts(items: &[(&str, &str)]) -> Option<Params> {
     Some(
         items
@@ -0,0 +1,203 @@
+// A theme collects the appearance of the axes of a figure (fonts, ticks,
+// frame, colors and legend), so that figures made by different people or at
+// different times look alike. It is applied to an axis with
+// `Axis::with_theme`, which also passes it on to the lines added afterwards.
+
+use crate::legend::Legend;
+
+/// Pixels per inch, as in SVG and CSS. The PDF and PNG output use the same
+/// resolution (see `Figure::render_png`), so figures keep their size.
+const PIXELS_PER_INCH: f64 = 96.0;
+
+/// The direction of the ticks, relative to the axis.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TickDirection {
+    In,
+    Out,
+    /// Centered on the axis.
+    #[default]
+    InOut,
+}
+
+impl TickDirection {
+    pub const ALL: [TickDirection; 3] =
+        [TickDirection::In, TickDirection::Out, TickDirection::InOut];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TickDirection::In => "In",
+            TickDirection::Out => "Out",
+            TickDirection::InOut => "In and Out",
+        }
+    }
+
+    /// The fractions of a tick `(inside, outside)` of the axis.
+    pub(crate) fn split(&self) -> (f64, f64) {
+        match self {
+            TickDirection::In => (1.0, 0.0),
+            TickDirection::Out => (0.0, 1.0),
+            TickDirection::InOut => (0.5, 0.5),
+        }
+    }
+}
+
+/// The lines drawn along the edges of an axis.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Frame {
+    /// Only the ticks mark the axis.
+    #[default]
+    None,
+    /// Lines along the bottom and left edges, and along the right edge if
+    /// the axis has a secondary y-axis.
+    Open,
+    /// A box around the axis.
+    Box,
+}
+
+impl Frame {
+    pub const ALL: [Frame; 3] = [Frame::None, Frame::Open, Frame::Box];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Frame::None => "None",
+            Frame::Open => "Open",
+            Frame::Box => "Box",
+        }
+    }
+}
+
+/// The unit of a `FigureSize`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SizeUnit {
+    #[default]
+    Px,
+    Mm,
+    In,
+}
+
+impl SizeUnit {
+    pub const ALL: [SizeUnit; 3] = [SizeUnit::Px, SizeUnit::Mm, SizeUnit::In];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SizeUnit::Px => "px",
+            SizeUnit::Mm => "mm",
+            SizeUnit::In => "in",
+        }
+    }
+
+    /// The number of pixels of one unit.
+    fn pixels(&self) -> f64 {
+        match self {
+            SizeUnit::Px => 1.0,
+            SizeUnit::Mm => PIXELS_PER_INCH / 25.4,
+            SizeUnit::In => PIXELS_PER_INCH,
+        }
+    }
+}
+
+/// The size of a figure, e.g. the width of a column of a journal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FigureSize {
+    pub width: f64,
+    pub height: f64,
+    pub unit: SizeUnit,
+}
+
+impl FigureSize {
+    /// The size in pixels, as passed to `Figure::empty`.
+    pub fn pixels(&self) -> (u64, u64) {
+        let to_pixels = |length: f64| (length * self.unit.pixels()).round().max(1.0) as u64;
+        (to_pixels(self.width), to_pixels(self.height))
+    }
+}
+
+/// The appearance of an axis, see `Axis::with_theme`. The default theme
+/// yields the same figures as an axis without a theme.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// The font of all texts in the SVG output. The PDF output uses Helvetica
+    /// and the PNG output Ubuntu Light regardless, and the layout the metrics
+    /// of Helvetica.
+    pub font_family: String,
+    /// Font size of the axis labels, in points.
+    pub label_size: f64,
+    /// Font size of the tick labels, in points.
+    pub tick_label_size: f64,
+    pub tick_direction: TickDirection,
+    /// Length of the ticks in pixels, or `None` for ticks scaled with the
+    /// axis (a fiftieth of its height on the x-axis and a hundredth of its
+    /// width on the y-axis).
+    pub tick_length: Option<f64>,
+    pub frame: Frame,
+    /// Colors of the lines added without a color, in turn.
+    pub color_cycle: Vec<String>,
+    /// The layout of the legend, if the axis has one.
+    pub legend: Legend,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            font_family: "sans".to_string(),
+            label_size: 12.0,
+            tick_label_size: 12.0,
+            tick_direction: TickDirection::default(),
+            tick_length: None,
+            frame: Frame::default(),
+            color_cycle: Vec::new(),
+            legend: Legend::default(),
+        }
+    }
+}
+
+impl Theme {
+    /// Font size of the axis labels, in pixels.
+    pub(crate) fn label_pixels(&self) -> f64 {
+        self.label_size * 4.0 / 3.0
+    }
+
+    /// Font size of the tick labels, in pixels.
+    pub(crate) fn tick_label_pixels(&self) -> f64 {
+        self.tick_label_size * 4.0 / 3.0
+    }
+
+    /// The length of the ticks outside of the axis, in pixels. Ticks scaled
+    /// with the axis are not taken into account.
+    pub(crate) fn tick_outside(&self) -> f64 {
+        self.tick_length
+            .map_or(0.0, |length| self.tick_direction.split().1 * length)
+    }
+
+    /// The color of the `index`-th line added without a color.
+    pub fn cycle_color(&self, index: usize) -> Option<&str> {
+        match self.color_cycle.len() {
+            0 => None,
+            n => Some(&self.color_cycle[index % n]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_figure_size() {
+        let size = FigureSize {
+            width: 85.0,
+            height: 60.0,
+            unit: SizeUnit::Mm,
+        };
+        assert_eq!(size.pixels(), (321, 227));
+        let size = FigureSize {
+            width: 3.5,
+            height: 2.5,
+            unit: SizeUnit::In,
+        };
+        assert_eq!(size.pixels(), (336, 240));
+    }
+}